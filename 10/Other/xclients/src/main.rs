@@ -9,20 +9,24 @@ use std::thread;
 use std::time::Duration;
 
 const TIMEOUT: Duration = Duration::from_secs(300);
-const USAGE: &str = "usage: ./xclient <num_clients> <port> [ip_address]";
+const USAGE: &str = "usage: ./xclient <num_clients> <port> [ip_address] [depth]";
+
+/// The minmax lookahead used by each client if no depth is given
+const DEFAULT_DEPTH: usize = 1;
 
 fn main() {
-    let (num_clients, address) = parse_args();
-    run_clients(num_clients, address);
+    let args = std::env::args().collect::<Vec<_>>();
+    let (num_clients, address, depth) = parse_args(&args);
+    run_clients(num_clients, address, depth);
 }
 
-fn run_clients(num_clients: usize, address: String) {
+fn run_clients(num_clients: usize, address: String, depth: usize) {
     let threads = (0..num_clients).map(|num| {
         let address = address.clone();
         thread::spawn(move || {
-            let ai_player = AIClient::new(Box::new(ClientStrategy));
+            let ai_player = AIClient::new(Box::new(ClientStrategy { depth }));
             let mut client = ClientToServerProxy::new("AIClient".to_string(), Box::new(ai_player), &address, TIMEOUT)
-                .expect(&format!("Unable to connect to server on thread {}", num));
+                .unwrap_or_else(|error| panic!("Unable to connect to server on thread {}: {:?}", num, error));
 
             client.tournament_loop();
         })
@@ -33,21 +37,49 @@ fn run_clients(num_clients: usize, address: String) {
     }
 }
 
-fn parse_args() -> (usize, String) {
-    let args = std::env::args().collect::<Vec<_>>();
+/// Parses the program's arguments (including the program name) into the
+/// number of clients to run, the address to connect to, and the minmax depth
+/// each client should use.
+fn parse_args(args: &[String]) -> (usize, String, usize) {
     let num_clients = args.get(1).and_then(|arg|  arg.parse().ok()).expect(USAGE);
     let port = args.get(2).expect(USAGE);
     let ip = args.get(3).map_or("127.0.0.1", String::as_str);
-    (num_clients, format!("{}:{}", ip, port))
+    let depth = args.get(4).map_or(DEFAULT_DEPTH, |arg| arg.parse().expect(USAGE));
+    (num_clients, format!("{}:{}", ip, port), depth)
+}
+
+struct ClientStrategy {
+    depth: usize,
 }
 
-struct ClientStrategy;
 impl strategy::Strategy for ClientStrategy {
     fn find_placement(&mut self, gamestate: &GameState) -> Placement {
         strategy::find_zigzag_placement(gamestate)
     }
 
     fn find_move(&mut self, game: &mut GameTree) -> Move {
-        strategy::find_minmax_move(game, 1)
+        strategy::find_minmax_move(game, self.depth)
     }
 }
+
+#[test]
+fn test_parse_args_without_depth() {
+    let args = ["xclients", "5", "12345"].iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(parse_args(&args), (5, "127.0.0.1:12345".to_string(), DEFAULT_DEPTH));
+
+    let args = ["xclients", "3", "12345", "10.0.0.2"].iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(parse_args(&args), (3, "10.0.0.2:12345".to_string(), DEFAULT_DEPTH));
+}
+
+#[test]
+fn test_parse_args_with_depth() {
+    let args = ["xclients", "5", "12345", "127.0.0.1", "3"].iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(parse_args(&args), (5, "127.0.0.1:12345".to_string(), 3));
+}
+
+#[test]
+#[should_panic]
+fn test_parse_args_invalid_depth() {
+    let args = ["xclients", "5", "12345", "127.0.0.1", "deep"].iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    parse_args(&args);
+}
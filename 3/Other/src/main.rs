@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use serde_json::Deserializer;
 use serde::Deserialize;
 
-use fish::common::board::Board;
+use fish::common::board::{ Board, DimensionError, exit_invalid_board };
 use fish::common::boardposn::BoardPosn;
 
 /// Represents the input given to the xboard program through the command line
@@ -24,8 +24,9 @@ impl JSONBoardAndPosn {
 }
 
 /// Converts a JSON representation of a board to
-/// the board module's Board representation.
-fn board_from_json(board_and_posn: JSONBoardAndPosn) -> Board {
+/// the board module's Board representation, failing if
+/// the board has no tiles or is too large.
+fn board_from_json(board_and_posn: JSONBoardAndPosn) -> Result<Board, DimensionError> {
     let board = board_and_posn.board;
     let rows = board.len();
     let columns = board.iter().map(|row| row.len()).max().unwrap_or(0);
@@ -45,14 +46,14 @@ fn board_from_json(board_and_posn: JSONBoardAndPosn) -> Board {
         }
     }
 
-    Board::with_holes(rows as u32, columns as u32, holes, 0)
+    Board::checked_with_holes(rows as u32, columns as u32, holes, 0)
 }
 
 fn main() {
     let stdin = std::io::stdin();
     let json_board_posn = JSONBoardAndPosn::from_reader(stdin.lock());
     let starting_position = json_board_posn.position;
-    let board = board_from_json(json_board_posn);
+    let board = board_from_json(json_board_posn).unwrap_or_else(exit_invalid_board);
 
     let starting_tile = board.get_tile(
         starting_position[1] as u32,
@@ -77,7 +78,7 @@ fn test_board_from_json() {
     let position = input.position;
 
     let expected = Board::with_holes(3, 3, vec![(0,1).into(), (2,2).into()], 0);
-    let output = board_from_json(input);
+    let output = board_from_json(input).unwrap();
 
     assert_eq!(output.tiles.len(), 7); // 9 tiles - 2 holes
     assert_eq!(expected.tiles, output.tiles);
@@ -109,7 +110,7 @@ fn test_board_from_json_uneven_rows() {
         (3,2).into(),
         (4,2).into(),
     ], 0);
-    let output = board_from_json(input);
+    let output = board_from_json(input).unwrap();
 
     assert_eq!(output.tiles.len(), 10); // 12 tiles - 2 holes
     assert_eq!(expected.tiles, output.tiles);
@@ -120,3 +121,15 @@ fn test_board_from_json_uneven_rows() {
     assert_eq!(expected_tile, output_tile);
     assert_eq!(output_tile.all_reachable_tiles(&output, &HashSet::new()).len(), 2);
 }
+
+#[test]
+fn test_board_from_json_invalid_dimensions() {
+    let empty = JSONBoardAndPosn { position: [0, 0], board: vec![] };
+    assert_eq!(board_from_json(empty).err(), Some(DimensionError::Empty));
+
+    let empty_rows = JSONBoardAndPosn { position: [0, 0], board: vec![vec![], vec![]] };
+    assert_eq!(board_from_json(empty_rows).err(), Some(DimensionError::Empty));
+
+    let too_wide = JSONBoardAndPosn { position: [0, 0], board: vec![vec![1; 1000]] };
+    assert_eq!(board_from_json(too_wide).err(), Some(DimensionError::TooLarge));
+}
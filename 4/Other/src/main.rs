@@ -5,7 +5,7 @@ use serde_json::Deserializer;
 use serde::{ Serialize, Deserialize };
 
 use fish::common::action::Move;
-use fish::common::board::Board;
+use fish::common::board::{ Board, DimensionError, exit_invalid_board };
 use fish::common::tile::TileId;
 use fish::common::direction::{ Direction, Direction::* };
 use fish::common::gamestate::GameState;
@@ -49,8 +49,9 @@ impl JSONPlayersAndBoard {
 }
 
 /// Converts a JSON representation of a board to
-/// the board module's Board representation.
-fn board_from_json(json_board: &JSONBoard) -> Board {
+/// the board module's Board representation, failing if
+/// the board has no tiles or is too large.
+fn board_from_json(json_board: &JSONBoard) -> Result<Board, DimensionError> {
     let columns = json_board.iter().map(|row| row.len()).max().unwrap_or(0);
     let mut tiles = vec![];
 
@@ -70,7 +71,7 @@ fn board_from_json(json_board: &JSONBoard) -> Board {
         tiles.push(row);
     }
 
-    Board::from_tiles(tiles)
+    Board::checked_from_tiles(tiles)
 }
 
 fn make_color_mapping(gamestate: &GameState, json_players: &[JSONPlayer]) -> HashMap<PlayerId, JSONColor> {
@@ -132,22 +133,6 @@ fn try_move_penguin(gamestate: &mut GameState, tile_id: TileId, direction: Direc
     }
 }
 
-fn serialize_board(board: &Board) -> JSONBoard {
-    let mut rows = vec![];
-
-    for row_i in 0 .. board.height {
-        let mut new_row = vec![];
-        for col_i in 0 .. board.width {
-            let tile = board.get_tile(col_i, row_i);
-            let fish_count = tile.map_or(0, |tile| tile.fish_count as u32);
-            new_row.push(fish_count);
-        }
-        rows.push(new_row);
-    }
-
-    rows
-}
-
 fn serialize_player(player: &Player, board: &Board, color_mapping: &HashMap<PlayerId, JSONColor>) -> JSONPlayer {
     let mut places = vec![];
 
@@ -175,7 +160,7 @@ fn serialize_players(gamestate: &GameState, color_mapping: &HashMap<PlayerId, JS
 }
 
 fn serialize_gamestate(gamestate: &GameState, color_mapping: &HashMap<PlayerId, JSONColor>) -> JSONPlayersAndBoard {
-    let board = serialize_board(&gamestate.board);
+    let board = gamestate.board.to_fish_grid();
     let players = serialize_players(gamestate, color_mapping);
 
     JSONPlayersAndBoard { players, board }
@@ -184,7 +169,7 @@ fn serialize_gamestate(gamestate: &GameState, color_mapping: &HashMap<PlayerId,
 fn main() {
     let stdin = std::io::stdin();
     let json = JSONPlayersAndBoard::from_reader(stdin.lock());
-    let board = board_from_json(&json.board);
+    let board = board_from_json(&json.board).unwrap_or_else(exit_invalid_board);
 
     let mut gamestate = GameState::new(board, json.players.len());
 
@@ -3,7 +3,7 @@ use serde_json::{json, Deserializer};
 use serde::{ Serialize, Deserialize };
 
 use fish::common::action::Move;
-use fish::common::board::Board;
+use fish::common::board::{ Board, DimensionError, exit_invalid_board };
 use fish::common::direction::Direction::*;
 use fish::common::gamestate::GameState;
 use fish::common::penguin::Penguin;
@@ -55,8 +55,9 @@ impl JSONStateAndMove {
 }
 
 /// Converts a JSON representation of a board to
-/// the board module's Board representation.
-fn board_from_json(json_board: &JSONBoard) -> Board {
+/// the board module's Board representation, failing if
+/// the board has no tiles or is too large.
+fn board_from_json(json_board: &JSONBoard) -> Result<Board, DimensionError> {
     let columns = json_board.iter().map(|row| row.len()).max().unwrap_or(0);
     let mut tiles = vec![];
 
@@ -76,7 +77,7 @@ fn board_from_json(json_board: &JSONBoard) -> Board {
         tiles.push(row);
     }
 
-    Board::from_tiles(tiles)
+    Board::checked_from_tiles(tiles)
 }
 
 fn move_from_json(state: &GameState, from_pos: JSONPosition, to_pos: JSONPosition) -> Move {
@@ -142,7 +143,7 @@ fn find_neighboring_move(game: &GameTree, first_player_tile: TileId) -> Option<M
 fn main() {
     let stdin = std::io::stdin();
     let json = JSONStateAndMove::from_reader(stdin.lock());
-    let board = board_from_json(&json.state.board);
+    let board = board_from_json(&json.state.board).unwrap_or_else(exit_invalid_board);
 
     let mut gamestate = GameState::new(board, json.state.players.len());
 
@@ -2,7 +2,7 @@
 use serde_json::json;
 use serde::{ Serialize, Deserialize };
 
-use fish::common::board::Board;
+use fish::common::board::{ Board, DimensionError, exit_invalid_board };
 use fish::common::gamestate::GameState;
 use fish::common::penguin::Penguin;
 use fish::common::game_tree::GameTree;
@@ -35,10 +35,15 @@ enum JSONColor {
 /// Json pair of [ board_row, board_column ]
 type JSONPosition = [u32; 2];
 
+/// The maximum number of game tree nodes to search before settling on the
+/// best move found so far, so that an overly large depth can't run forever.
+const NODE_BUDGET: usize = 1_000_000;
+
 
 /// Converts a JSON representation of a board to
-/// the board module's Board representation.
-fn board_from_json(json_board: &JSONBoard) -> Board {
+/// the board module's Board representation, failing if
+/// the board has no tiles or is too large.
+fn board_from_json(json_board: &JSONBoard) -> Result<Board, DimensionError> {
     let columns = json_board.iter().map(|row| row.len()).max().unwrap_or(0);
     let mut tiles = vec![];
 
@@ -58,7 +63,7 @@ fn board_from_json(json_board: &JSONBoard) -> Board {
         tiles.push(row);
     }
 
-    Board::from_tiles(tiles)
+    Board::checked_from_tiles(tiles)
 }
 
 fn place_penguins(gamestate: &mut GameState, json_players: &[JSONPlayer]) {
@@ -91,7 +96,7 @@ fn main() {
     let depth: usize = serde_json::from_value(json[0].clone()).unwrap();
     let json_state: JSONState = serde_json::from_value(json[1].clone()).unwrap();
 
-    let board = board_from_json(&json_state.board);
+    let board = board_from_json(&json_state.board).unwrap_or_else(exit_invalid_board);
     let mut gamestate = GameState::new(board, json_state.players.len());
     place_penguins(&mut gamestate, &json_state.players);
     
@@ -102,7 +107,7 @@ fn main() {
     if game_tree.is_game_over() {
         println!("false");
     } else {
-        let move_ = strategy::find_minmax_move(&mut game_tree, depth);
+        let move_ = strategy::find_minmax_move_capped(&mut game_tree, depth, NODE_BUDGET);
         let from_pos = gamestate.board.get_tile_position(move_.from);
         let to_pos = gamestate.board.get_tile_position(move_.to);
         println!("{}", json!([[from_pos.y, from_pos.x], [to_pos.y, to_pos.x]]));
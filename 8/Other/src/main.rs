@@ -1,7 +1,7 @@
 use serde_json::json;
 use serde::{ Serialize, Deserialize };
 
-use fish::common::board::Board;
+use fish::common::board::{ Board, exit_invalid_board };
 use fish::common::gamestate::GameState;
 use fish::common::game_tree::GameTree;
 use fish::common::action::{Move, Placement};
@@ -39,7 +39,7 @@ fn main() {
     let stdin = std::io::stdin();
     let description: GameDescription = serde_json::from_reader(stdin.lock()).unwrap();
 
-    let board = Board::with_no_holes(description.row, description.column, description.fish);
+    let board = Board::checked_with_no_holes(description.row, description.column, description.fish).unwrap_or_else(exit_invalid_board);
 
     let players = description.players.iter().map(|player| {
         Box::new(AIClient::new(Box::new(player.clone()))) as Box<dyn Client>
@@ -1,9 +1,11 @@
 use fish::common::tile::TileId;
 use fish::common::gamestate::GameState;
-use fish::common::action::{ Placement, Move, PlayerMove };
+use fish::common::game_tree::GameTree;
+use fish::common::action::{ Placement, Move, PlayerMove, MoveError };
 use fish::common::util::make_n;
 use fish::common::player::PlayerColor;
 use fish::server::ai_client::AIClient;
+use fish::server::strategy;
 use fish::server::referee::run_game;
 use fish::server::client::Client;
 use fish::server::remote_client::RemoteClient;
@@ -18,7 +20,10 @@ use std::io::Write;
 const USAGE: &str = "Usage: ./xgui <AI player count (1-3)>";
 const ADDRESS: &str = "127.0.0.1:8080";
 const TIMEOUT_SECS: u64 = 1;
-const PLAYER_USAGE: &str = "Usage:\n'place [tile_id]'\n'move [penguin_id] to [tile_id]'";
+const PLAYER_USAGE: &str = "Usage:\n'place [tile_id]'\n'move [penguin_id] to [tile_id]'\n'hint'";
+
+/// How many rounds the minmax strategy looks ahead when suggesting a move
+const HINT_LOOKAHEAD: usize = 2;
 
 fn main() {
     start_game_server();
@@ -27,7 +32,7 @@ fn main() {
 
     match try_with_timeout(timeout, || {
         let client = Box::new(HumanClient);
-        ClientToServerProxy::new("Human".to_string(), client, ADDRESS, Duration::from_secs(30))
+        ClientToServerProxy::new("Human".to_string(), client, ADDRESS, Duration::from_secs(30)).ok()
     }) {
         // run a human controlled player loop
         Some(mut proxy) => { proxy.tournament_loop(); },
@@ -68,39 +73,97 @@ impl Client for HumanClient {
 
     fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
         println!("{:?}\nYour turn to place a penguin:\n", gamestate);
-        Some(parse_placement_input())
+        Some(parse_placement_input(gamestate))
     }
 
     fn get_move(&mut self, gamestate: &GameState, _previous: &[PlayerMove]) -> Option<Move> {
         println!("{:?}\nYour turn to make a move:\n", gamestate);
-        Some(parse_move_input())
+        Some(parse_move_input(gamestate))
     }
 }
 
-fn parse_placement_input() -> Placement {
+/// Suggest a placement for the current player using the zigzag strategy
+fn suggest_placement(gamestate: &GameState) -> Placement {
+    strategy::find_zigzag_placement(gamestate)
+}
+
+/// Suggest a move for the current player using the minmax strategy
+fn suggest_move(gamestate: &GameState) -> Move {
+    strategy::find_minmax_move(&mut GameTree::new(gamestate), HINT_LOOKAHEAD)
+}
+
+/// Read a line of input from the player after printing the usage prompt
+fn read_input() -> String {
     print!("{}\n> ", PLAYER_USAGE);
     std::io::stdout().flush().ok();
     let mut input = String::new();
     std::io::stdin().read_line(&mut input).unwrap();
-    let words = input.trim().split(" ").collect::<Vec<_>>();
+    input
+}
 
-    match (words[0], words[1].parse()) {
-        ("place", Ok(id)) => Placement::new(TileId(id)),
-        _ => parse_placement_input(),
+fn parse_placement_input(gamestate: &GameState) -> Placement {
+    let input = read_input();
+
+    match input.trim().split(" ").collect::<Vec<_>>().as_slice() {
+        ["place", id] => match id.parse() {
+            Ok(id) => Placement::new(TileId(id)),
+            _ => parse_placement_input(gamestate),
+        },
+        ["hint"] => {
+            println!("Hint: place {}", suggest_placement(gamestate).tile_id.0);
+            parse_placement_input(gamestate)
+        },
+        _ => parse_placement_input(gamestate),
     }
 }
 
-fn parse_move_input() -> Move {
-    print!("{}\n> ", PLAYER_USAGE);
-    std::io::stdout().flush().ok();
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    let words = input.trim().split(" ").collect::<Vec<_>>();
+fn parse_move_input(gamestate: &GameState) -> Move {
+    let input = read_input();
+
+    match input.trim().split(" ").collect::<Vec<_>>().as_slice() {
+        ["hint"] => {
+            let move_ = suggest_move(gamestate);
+            println!("Hint: move {} to {}", move_.from.0, move_.to.0);
+            parse_move_input(gamestate)
+        },
+        _ => match check_move_input(&input, gamestate) {
+            Ok(move_) => move_,
+            Err(reason) => {
+                println!("Invalid move: {}", reason);
+                parse_move_input(gamestate)
+            },
+        },
+    }
+}
 
-    match (words[0], words[1].parse(), words[2], words[3].parse()) {
-        ("move", Ok(from_tile), "to", Ok(to_tile)) => 
-            Move::new(TileId(from_tile), TileId(to_tile)),
-        _ => parse_move_input(),
+/// Parse a 'move [tile_id] to [tile_id]' command and check that it is legal for
+/// the current player, so that the human is re-prompted rather than kicked for
+/// an illegal move. Returns the reason the input was rejected on failure.
+fn check_move_input(input: &str, gamestate: &GameState) -> Result<Move, String> {
+    let move_ = match input.trim().split(" ").collect::<Vec<_>>().as_slice() {
+        ["move", from_tile, "to", to_tile] => match (from_tile.parse(), to_tile.parse()) {
+            (Ok(from_tile), Ok(to_tile)) => Move::new(TileId(from_tile), TileId(to_tile)),
+            _ => return Err("tile ids must be non-negative integers".to_string()),
+        },
+        _ => return Err("expected 'move [tile_id] to [tile_id]'".to_string()),
+    };
+
+    gamestate.validate_move(gamestate.current_turn, move_)
+        .map(|_| move_)
+        .map_err(|error| move_error_reason(error, move_, gamestate))
+}
+
+/// Describe why the given move was rejected in terms a human player can act on
+fn move_error_reason(error: MoveError, move_: Move, gamestate: &GameState) -> String {
+    match error {
+        MoveError::UnknownPlayer => "you are no longer in this game".to_string(),
+        MoveError::NoPenguin => format!("you have no penguin on tile {}", move_.from.0),
+        MoveError::NoMovement => "a penguin must move to a different tile".to_string(),
+        MoveError::DestinationOccupied => format!("tile {} already has a penguin on it", move_.to.0),
+        MoveError::Unreachable if gamestate.get_tile(move_.to).is_none() =>
+            format!("tile {} is a hole or off the board", move_.to.0),
+        MoveError::Unreachable =>
+            format!("tile {} cannot be reached in a straight line from tile {}", move_.to.0, move_.from.0),
     }
 }
 
@@ -130,3 +193,47 @@ fn create_players(listener: &TcpListener) -> Vec<Box<dyn Client>> {
     players.push(Box::new(RemoteClient::new(stream, Duration::from_secs(30))));
     players
 }
+
+#[test]
+fn test_suggest_move() {
+    let mut gamestate = GameState::with_default_board(3, 5, 2);
+
+    gamestate.place_all_zigzag();
+
+    // The suggestion is a valid move matching the minmax strategy,
+    // and suggesting it does not change the gamestate
+    let before = gamestate.clone();
+    let move_ = suggest_move(&gamestate);
+    assert!(gamestate.get_valid_moves().contains(&move_));
+    assert_eq!(move_, strategy::find_minmax_move(&mut GameTree::new(&gamestate), HINT_LOOKAHEAD));
+    assert_eq!(gamestate, before);
+}
+
+#[test]
+fn test_check_move_input() {
+    // 3 rows x 5 columns, tile ids are x * 3 + y
+    let mut gamestate = GameState::with_default_board(3, 5, 2);
+    gamestate.board.remove_tile(TileId(1));
+
+    gamestate.place_all_zigzag();
+
+    // Zigzag placement puts the first player's first penguin on tile 0, and none on tile 2
+    let valid_move = gamestate.get_valid_moves()[0];
+    let input = format!("move {} to {}", valid_move.from.0, valid_move.to.0);
+    assert_eq!(check_move_input(&input, &gamestate), Ok(valid_move));
+
+    assert_eq!(check_move_input("move a to 3", &gamestate),
+        Err("tile ids must be non-negative integers".to_string()));
+    assert_eq!(check_move_input("place 3", &gamestate),
+        Err("expected 'move [tile_id] to [tile_id]'".to_string()));
+    assert_eq!(check_move_input("move 2 to 14", &gamestate),
+        Err("you have no penguin on tile 2".to_string()));
+    assert_eq!(check_move_input("move 0 to 0", &gamestate),
+        Err("a penguin must move to a different tile".to_string()));
+    assert_eq!(check_move_input("move 0 to 1", &gamestate),
+        Err("tile 1 is a hole or off the board".to_string()));
+    assert_eq!(check_move_input("move 0 to 99", &gamestate),
+        Err("tile 99 is a hole or off the board".to_string()));
+    assert_eq!(check_move_input("move 0 to 14", &gamestate),
+        Err("tile 14 cannot be reached in a straight line from tile 0".to_string()));
+}
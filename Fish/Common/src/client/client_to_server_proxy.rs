@@ -1,10 +1,11 @@
 use crate::server::client::Client;
+use crate::common::action::{ Action, Move };
 use crate::server::message::*;
 use crate::common::util;
 
-use std::net::TcpStream;
+use std::net::{ TcpStream, ToSocketAddrs };
 use std::time::Duration;
-use std::io::Write;
+use std::io::{ ErrorKind, Write };
 
 use serde::Deserialize;
 use serde_json::Deserializer;
@@ -24,82 +25,458 @@ pub struct ClientToServerProxy {
     stream: TcpStream,
     timeout: Duration,
     player_count: usize,
+    phase: ProxyPhase,
+
+    /// The valid moves listed in the most recent take-turn message, if the server sent any.
+    /// These are also given to the client via Client::receive_valid_moves.
+    valid_moves: Option<Vec<Move>>,
+
+    /// The most times a response is resent when the server's next message does not
+    /// arrive in time, or None if responses are never resent. See set_acked_sends.
+    acked_send_retries: Option<usize>,
+
+    /// The most recent name or response sent to the server, kept to be resent.
+    last_sent: Option<String>,
+
+    /// The number of messages received from the server so far. When acked sends are
+    /// enabled each response is sent numbered with this, see NumberedResponse.
+    messages_received: usize,
+
+    /// Whether to ask the server at signup to list valid moves in take-turn messages.
+    /// See set_request_valid_moves.
+    request_valid_moves: bool,
+}
+
+/// Which part of a game the proxy's client is currently playing, tracked from
+/// the messages received from the server.
+///
+/// The protocol has no message marking the end of the placement phase. Instead,
+/// the first take-turn message of a game implies that every penguin has been
+/// placed, at which point any placement bookkeeping is reset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ProxyPhase {
+    /// Not currently playing a game
+    Waiting,
+
+    /// Placing penguins, with the number of penguins placed so far this game
+    PlacingPenguins { placed: usize },
+
+    /// Moving penguins, after every penguin was placed
+    MovingPenguins,
+}
+
+/// The reasons a ClientToServerProxy can fail to connect to a server. See ClientToServerProxy::new.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProxyError {
+    /// The address is not of the form host:port, or the host could not be resolved
+    InvalidAddress,
+
+    /// Nothing is listening for connections at the address
+    ConnectionRefused,
+
+    /// The server did not respond to the connection attempt in time
+    TimedOut,
+
+    /// The read and write timeouts could not be set on the stream, e.g. because the timeout is zero
+    InvalidTimeout,
+
+    /// Any other error connecting to the server, e.g. the network being unreachable
+    Io(ErrorKind),
 }
 
 impl ClientToServerProxy {
-    pub fn new(name: String, client: Box<dyn Client>, address: &str, timeout: Duration) -> Option<ClientToServerProxy> {
-        let stream = TcpStream::connect(address).ok()?;
-        stream.set_read_timeout(Some(timeout)).unwrap();
-        stream.set_write_timeout(Some(timeout)).unwrap();
-        Some(ClientToServerProxy {
+    /// Connects to the server at the given address, using the given timeout for every
+    /// read from and write to the server. Returns an error describing why the connection
+    /// could not be made, if it could not.
+    pub fn new(name: String, client: Box<dyn Client>, address: &str, timeout: Duration) -> Result<ClientToServerProxy, ProxyError> {
+        let addresses = address.to_socket_addrs()
+            .map_err(|_| ProxyError::InvalidAddress)?
+            .collect::<Vec<_>>();
+
+        let stream = TcpStream::connect(&addresses[..]).map_err(|error| match error.kind() {
+            ErrorKind::ConnectionRefused => ProxyError::ConnectionRefused,
+            ErrorKind::TimedOut => ProxyError::TimedOut,
+            kind => ProxyError::Io(kind),
+        })?;
+
+        stream.set_read_timeout(Some(timeout)).map_err(|_| ProxyError::InvalidTimeout)?;
+        stream.set_write_timeout(Some(timeout)).map_err(|_| ProxyError::InvalidTimeout)?;
+        Ok(ClientToServerProxy {
             name,
             client,
             stream,
             timeout,
             player_count: 0,
+            phase: ProxyPhase::Waiting,
+            valid_moves: None,
+            acked_send_retries: None,
+            last_sent: None,
+            messages_received: 0,
+            request_valid_moves: false,
         })
     }
 
+    /// Enables or disables acked sends. With acked sends enabled the server's next message
+    /// is treated as the acknowledgement of the proxy's last response, or of its name. If
+    /// that message does not arrive within the timeout, the response is assumed lost and
+    /// sent again, up to max_retries times, before giving up as usual. Disabled by default.
+    ///
+    /// The server's next message is often merely slow, e.g. while opponents take their
+    /// turns, so a response may be received twice. To let the server ignore duplicates,
+    /// enabling acked sends also asks the server at signup to accept numbered responses,
+    /// see NumberedResponse. This must be set before the proxy's name is sent, and the
+    /// server must support numbered responses as RemoteClient does.
+    pub fn set_acked_sends(&mut self, max_retries: Option<usize>) {
+        self.acked_send_retries = max_retries;
+    }
+
+    /// Sets whether the proxy asks the server, when sending its name, to list the
+    /// client's valid moves in each take-turn message. This is an extension to the
+    /// published protocol, so it is disabled by default. Servers that don't support
+    /// it will generally reject the name. See SignupMessage.
+    pub fn set_request_valid_moves(&mut self, request_valid_moves: bool) {
+        self.request_valid_moves = request_valid_moves;
+    }
+
     /// Loops until the entire game is finished, forwarding each
     /// received message to the inner Client, returning early
     /// if any incoming message is malformed.
     pub fn tournament_loop(&mut self) -> Option<bool> {
         self.send_name()?;
         loop {
-            match self.receive()? {
-                ServerToClientMessage::Start(_) => {
-                    self.client.tournament_starting()?;
-                    self.send(ClientToServerMessage::Void(JSONVoid::Void))?;
-                },
+            match self.receive_acked()? {
                 ServerToClientMessage::End((won,)) => {
+                    self.phase = ProxyPhase::Waiting;
                     self.client.tournament_ending(won)?;
                     self.send(ClientToServerMessage::Void(JSONVoid::Void))?;
                     return Some(won)
                 },
-                ServerToClientMessage::PlayingAs(_) => {
-                    self.send(ClientToServerMessage::Void(JSONVoid::Void))?;
-                },
-                ServerToClientMessage::PlayingWith((colors,)) => {
-                    self.player_count = colors.len() + 1;
-                    self.send(ClientToServerMessage::Void(JSONVoid::Void))?;
-                },
-                ServerToClientMessage::Setup((json_gamestate,)) => {
-                    let gamestate = json_gamestate.to_common_game_state(self.player_count);
-                    let placement = self.client.get_placement(&gamestate)?;
-                    let json_position = placement_to_json_position(&gamestate.board, placement);
-                    self.send(ClientToServerMessage::Position(json_position))?;
-                },
-                ServerToClientMessage::TakeTurn(json_gamestate, _) => {
-                    let gamestate = json_gamestate.to_common_game_state(self.player_count);
-                    let move_ = self.client.get_move(&gamestate, &[])?;
-                    let json_move = move_to_json_action(&gamestate.board, move_);
-                    self.send(ClientToServerMessage::Action(json_move))?;
-                },
+                message => self.handle_message(message)?,
             }
         }
     }
 
+    /// Forwards a single message other than End to the inner Client,
+    /// sending its response back to the server.
+    fn handle_message(&mut self, message: ServerToClientMessage) -> Option<()> {
+        match message {
+            ServerToClientMessage::Start(_) => {
+                self.client.tournament_starting()?;
+                self.send(ClientToServerMessage::Void(JSONVoid::Void))
+            },
+            ServerToClientMessage::End(_) => unreachable!("End messages are handled by tournament_loop"),
+            ServerToClientMessage::PlayingAs(_) => {
+                self.phase = ProxyPhase::PlacingPenguins { placed: 0 };
+                self.send(ClientToServerMessage::Void(JSONVoid::Void))
+            },
+            ServerToClientMessage::PlayingWith((colors,)) => {
+                self.player_count = colors.len() + 1;
+                self.send(ClientToServerMessage::Void(JSONVoid::Void))
+            },
+            ServerToClientMessage::Setup((json_gamestate,)) => {
+                let gamestate = json_gamestate.to_common_game_state(self.player_count);
+                let placement = self.client.get_placement(&gamestate)?;
+                let message = action_to_message(&gamestate.board, Action::PlacePenguin(placement));
+
+                self.phase = match self.phase {
+                    ProxyPhase::PlacingPenguins { placed } => ProxyPhase::PlacingPenguins { placed: placed + 1 },
+                    _ => ProxyPhase::PlacingPenguins { placed: 1 },
+                };
+                self.send(message)
+            },
+            ServerToClientMessage::TakeTurn(json_gamestate, _, json_valid_moves) => {
+                // The first take-turn of a game implies placement is complete
+                self.phase = ProxyPhase::MovingPenguins;
+
+                let gamestate = json_gamestate.to_common_game_state(self.player_count);
+                // Any listed move that is out of bounds is skipped rather than failing the whole turn
+                self.valid_moves = json_valid_moves.map(|json_valid_moves| json_valid_moves.into_iter()
+                    .filter_map(|json_action| json_action_to_move(&gamestate.board, json_action))
+                    .collect());
+
+                if let Some(valid_moves) = &self.valid_moves {
+                    self.client.receive_valid_moves(valid_moves);
+                }
+
+                let move_ = self.client.get_move(&gamestate, &[])?;
+                self.send(action_to_message(&gamestate.board, Action::MovePenguin(move_)))
+            },
+        }
+    }
+
+    /// The valid moves the server listed in its most recent take-turn message,
+    /// or None if it did not list any.
+    pub fn valid_moves(&self) -> Option<&[Move]> {
+        self.valid_moves.as_deref()
+    }
+
     /// Send the client's name through the stream. The client's name is a bit special
     /// in that it is not a ClientToServerMessage since it could otherwise collide with
     /// the "void" message if the client names themselves "void".
     pub fn send_name(&mut self) -> Option<()> {
-        let json_name = serde_json::to_string(&self.name).ok()?;
-        self.stream.write(json_name.as_bytes()).ok()?;
-        Some(())
+        let numbered_responses = self.acked_send_retries.is_some();
+        let signup = if self.request_valid_moves || numbered_responses {
+            SignupMessage::WithExtensions {
+                name: self.name.clone(),
+                valid_moves: self.request_valid_moves,
+                numbered_responses,
+            }
+        } else {
+            SignupMessage::Name(self.name.clone())
+        };
+        let json_name = serde_json::to_string(&signup).ok()?;
+        self.write(json_name)
+    }
+
+    /// Receive the next ServerToClientMessage. If acked sends are enabled, the last
+    /// name or response sent is resent each time no message arrives within self.timeout,
+    /// up to the configured number of retries. See set_acked_sends.
+    fn receive_acked(&mut self) -> Option<ServerToClientMessage> {
+        let mut retries_left = self.acked_send_retries.unwrap_or(0);
+        loop {
+            match self.receive() {
+                Some(message) => return Some(message),
+                None if retries_left > 0 => {
+                    retries_left -= 1;
+                    let last_sent = self.last_sent.clone()?;
+                    self.write(last_sent)?;
+                },
+                None => return None,
+            }
+        }
     }
 
     /// Receive an arbitrary ServerToClientMessage from self.stream,
     /// waiting a maximum Duration of self.timeout
     fn receive(&mut self) -> Option<ServerToClientMessage> {
         let mut de = Deserializer::from_reader(self.stream.try_clone().unwrap());
-        util::try_with_timeout(self.timeout, || {
+        let message = util::try_with_timeout(self.timeout, || {
             ServerToClientMessage::deserialize(&mut de).ok()
-        })
+        })?;
+        self.messages_received += 1;
+        Some(message)
     }
 
-    /// Send an arbitrary ClientToServerMessage to self.stream
+    /// Send an arbitrary ClientToServerMessage to self.stream, numbered if acked sends are enabled
     fn send(&mut self, message: ClientToServerMessage) -> Option<()> {
-        self.stream.write(serde_json::to_string(&message).ok()?.as_bytes()).ok()?;
+        let message = if self.acked_send_retries.is_some() {
+            serde_json::to_string(&NumberedResponse(self.messages_received, serde_json::to_value(message).ok()?))
+        } else {
+            serde_json::to_string(&message)
+        };
+        self.write(message.ok()?)
+    }
+
+    /// Write the given serialized message to self.stream, remembering it in case it must be resent
+    fn write(&mut self, message: String) -> Option<()> {
+        self.stream.write(message.as_bytes()).ok()?;
+        self.last_sent = Some(message);
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::gamestate::GameState;
+    use crate::common::player::PlayerColor;
+    use crate::server::ai_client::AIClient;
+    use crate::server::remote_client::RemoteClient;
+    use crate::server::strategy::find_zigzag_placement;
+    use crate::server::strategy::tests::take_zigzag_placement;
+
+    use std::net::TcpListener;
+    use std::thread;
+
+    use serde_json::{ json, Value };
+
+    #[test]
+    fn test_proxy_connection_errors() {
+        let connect = |address: &str, timeout: Duration| {
+            let client = Box::new(AIClient::with_zigzag_minmax_strategy());
+            ClientToServerProxy::new("test".to_string(), client, address, timeout).err()
+        };
+
+        // Nothing is listening on this port
+        assert_eq!(connect("127.0.0.1:8099", Duration::from_secs(1)), Some(ProxyError::ConnectionRefused));
+
+        assert_eq!(connect("not an address", Duration::from_secs(1)), Some(ProxyError::InvalidAddress));
+        assert_eq!(connect("127.0.0.1", Duration::from_secs(1)), Some(ProxyError::InvalidAddress));
+
+        let listener = TcpListener::bind("127.0.0.1:8100").expect("Could not create listener");
+        assert_eq!(connect("127.0.0.1:8100", Duration::from_secs(0)), Some(ProxyError::InvalidTimeout));
+        drop(listener);
+    }
+
+    /// The proxy should count its placements while penguins are being placed, then
+    /// switch to moving penguins upon the first take-turn message.
+    #[test]
+    fn test_proxy_phase_switches_on_first_take_turn() {
+        let listener = TcpListener::bind("127.0.0.1:8095").expect("Could not create listener");
+        let client = Box::new(AIClient::with_zigzag_minmax_strategy());
+        let mut proxy = ClientToServerProxy::new("test".to_string(), client, "127.0.0.1:8095", Duration::from_secs(1))
+            .expect("Could not connect proxy");
+        let _server_stream = listener.accept().expect("Could not accept proxy");
+
+        assert_eq!(proxy.phase, ProxyPhase::Waiting);
+
+        proxy.handle_message(ServerToClientMessage::PlayingAs((PlayerColor::red,))).unwrap();
+        proxy.handle_message(ServerToClientMessage::PlayingWith((vec![PlayerColor::white],))).unwrap();
+        assert_eq!(proxy.phase, ProxyPhase::PlacingPenguins { placed: 0 });
+
+        let mut state = GameState::with_default_board(3, 5, 2);
+        for placed in 1 ..= 4 {
+            proxy.handle_message(ServerToClientMessage::Setup((serialize_gamestate(&state),))).unwrap();
+            assert_eq!(proxy.phase, ProxyPhase::PlacingPenguins { placed });
+
+            // Both players place a penguin before this player places again
+            take_zigzag_placement(&mut state);
+            take_zigzag_placement(&mut state);
+        }
+
+        proxy.handle_message(ServerToClientMessage::TakeTurn(serialize_gamestate(&state), vec![], None)).unwrap();
+        assert_eq!(proxy.phase, ProxyPhase::MovingPenguins);
+        assert_eq!(proxy.valid_moves(), None);
+    }
+
+    /// The valid moves listed in a take-turn message should be exposed as Moves on the proxy's
+    /// board, skipping any listed move that is out of bounds
+    #[test]
+    fn test_proxy_receives_valid_moves() {
+        let listener = TcpListener::bind("127.0.0.1:8097").expect("Could not create listener");
+        let client = Box::new(AIClient::with_zigzag_minmax_strategy());
+        let mut proxy = ClientToServerProxy::new("test".to_string(), client, "127.0.0.1:8097", Duration::from_secs(1))
+            .expect("Could not connect proxy");
+        let _server_stream = listener.accept().expect("Could not accept proxy");
+
+        proxy.handle_message(ServerToClientMessage::PlayingAs((PlayerColor::red,))).unwrap();
+        proxy.handle_message(ServerToClientMessage::PlayingWith((vec![PlayerColor::white],))).unwrap();
+
+        let mut state = GameState::with_default_board(3, 5, 2);
+        state.place_all_zigzag();
+
+        let mut message: Value = serde_json::from_str(&take_turn_message_with_valid_moves(&state, &[])).unwrap();
+        message[1][2].as_array_mut().unwrap().push(serde_json::json!([[99, 99], [0, 0]]));
+        proxy.handle_message(serde_json::from_value(message).unwrap()).unwrap();
+
+        // The proxy reconstructs the state with the current player first, which
+        // leaves the board and therefore the tile ids of each move unchanged
+        let mut expected = state.get_valid_moves();
+        let mut valid_moves = proxy.valid_moves().unwrap().to_vec();
+        expected.sort_by_key(|move_| (move_.from, move_.to));
+        valid_moves.sort_by_key(|move_| (move_.from, move_.to));
+        assert_eq!(valid_moves, expected);
+    }
+
+    /// Signs a proxy up with a RemoteClient on the given port, then has the RemoteClient request
+    /// a move. Returns the valid moves the proxy received in the take-turn message.
+    fn valid_moves_after_signup(port: usize, request_valid_moves: bool) -> Option<Vec<Move>> {
+        let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).expect("Could not create listener");
+        let client = Box::new(AIClient::with_zigzag_minmax_strategy());
+        let mut proxy = ClientToServerProxy::new("test".to_string(), client, &format!("127.0.0.1:{}", port), Duration::from_secs(1))
+            .expect("Could not connect proxy");
+        proxy.set_request_valid_moves(request_valid_moves);
+        proxy.player_count = 2;
+
+        let (stream, _) = listener.accept().expect("Could not accept proxy");
+        let mut remote_client = RemoteClient::new(stream, Duration::from_secs(1));
+        proxy.send_name().unwrap();
+        assert_eq!(remote_client.get_name(Duration::from_secs(1)), Some("test".to_string()));
+
+        let mut state = GameState::with_default_board(3, 5, 2);
+        state.place_all_zigzag();
+        let server_thread = thread::spawn(move || remote_client.get_move(&state, &[]));
+
+        let message = proxy.receive().unwrap();
+        proxy.handle_message(message).unwrap();
+        assert!(server_thread.join().unwrap().is_some());
+        proxy.valid_moves().map(|moves| moves.to_vec())
+    }
+
+    /// Valid moves are only listed in take-turn messages to clients that asked for them at signup
+    #[test]
+    fn test_proxy_requests_valid_moves_at_signup() {
+        assert_eq!(valid_moves_after_signup(8103, false), None);
+        assert!(!valid_moves_after_signup(8104, true).unwrap().is_empty());
+    }
+
+    /// Runs a tournament of a single start and end message against a proxy on the given port,
+    /// ignoring the proxy's first response to the start message as if it were lost. Returns
+    /// what the proxy's tournament_loop returned.
+    fn run_tournament_dropping_first_response(port: usize, acked_send_retries: Option<usize>) -> Option<bool> {
+        let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).expect("Could not create listener");
+
+        let proxy_thread = thread::spawn(move || {
+            let client = Box::new(AIClient::with_zigzag_minmax_strategy());
+            let address = format!("127.0.0.1:{}", port);
+            let mut proxy = ClientToServerProxy::new("test".to_string(), client, &address, Duration::from_millis(300))
+                .expect("Could not connect proxy");
+            proxy.set_acked_sends(acked_send_retries);
+            proxy.tournament_loop()
+        });
+
+        let (mut stream, _) = listener.accept().expect("Could not accept proxy");
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut responses = Deserializer::from_reader(stream.try_clone().unwrap()).into_iter::<Value>();
+
+        // With acked sends the proxy signs up for numbered responses
+        let (name, void): (Value, fn(usize) -> Value) = match acked_send_retries {
+            Some(_) => (json!({ "name": "test", "valid-moves": false, "numbered-responses": true }), |n: usize| json!([n, "void"])),
+            None => (json!("test"), |_| json!("void")),
+        };
+        assert_eq!(responses.next().unwrap().unwrap(), name);
+
+        stream.write_all(start_message().as_bytes()).unwrap();
+        assert_eq!(responses.next().unwrap().unwrap(), void(1));
+
+        // The first void is dropped, so the end message is only sent if it is resent
+        if let Some(Ok(response)) = responses.next() {
+            assert_eq!(response, void(1));
+            stream.write_all(end_message(true).as_bytes()).unwrap();
+            assert_eq!(responses.next().unwrap().unwrap(), void(2));
+        }
+
+        proxy_thread.join().expect("Proxy thread crashed")
+    }
+
+    #[test]
+    fn test_proxy_acked_send_retries_dropped_response() {
+        assert_eq!(run_tournament_dropping_first_response(8101, Some(2)), Some(true));
+
+        // Without acked sends the proxy gives up waiting for the next message
+        assert_eq!(run_tournament_dropping_first_response(8102, None), None);
+    }
+
+    /// While an opponent takes a turn longer than the proxy's timeout, the proxy resends its
+    /// last response. The RemoteClient should ignore the duplicates rather than reading them as
+    /// the response to its next message.
+    #[test]
+    fn test_proxy_acked_sends_slow_opponent() {
+        let listener = TcpListener::bind("127.0.0.1:8107").expect("Could not create listener");
+
+        let proxy_thread = thread::spawn(move || {
+            let client = Box::new(AIClient::with_zigzag_minmax_strategy());
+            let mut proxy = ClientToServerProxy::new("test".to_string(), client, "127.0.0.1:8107", Duration::from_millis(100))
+                .expect("Could not connect proxy");
+            proxy.set_acked_sends(Some(5));
+            proxy.tournament_loop()
+        });
+
+        let (stream, _) = listener.accept().expect("Could not accept proxy");
+        let mut remote_client = RemoteClient::new(stream, Duration::from_secs(1));
+        assert_eq!(remote_client.get_name(Duration::from_secs(1)), Some("test".to_string()));
+
+        let state = GameState::with_default_board(3, 5, 2);
+        assert_eq!(remote_client.tournament_starting(), Some(()));
+        assert_eq!(remote_client.initialize_game(&state, PlayerColor::red), Some(()));
+
+        // The opponent's turn outlasts several of the proxy's timeouts
+        thread::sleep(Duration::from_millis(350));
+
+        assert_eq!(remote_client.get_placement(&state), Some(find_zigzag_placement(&state)));
+        assert_eq!(remote_client.last_failure(), None);
+
+        assert_eq!(remote_client.tournament_ending(true), Some(()));
+        assert_eq!(proxy_thread.join().expect("Proxy thread crashed"), Some(true));
+    }
+}
@@ -3,28 +3,47 @@
 //! involves translating the Board to a series of images within a gtk::Application,
 //! but in the future this will also handle player input and receiving server
 //! updates in separate submodules within client.
-use crate::common::tile::{ TileId, Tile };
+use crate::common::tile::Tile;
 use crate::common::gamestate::{ GameState, SharedGameState };
 use crate::common::player::PlayerColor;
-use crate::common::board::Board;
 use crate::common::boardposn::BoardPosn;
+use crate::common::replay::Replay;
 
-use gdk_pixbuf::InterpType;
+use gdk_pixbuf::{ InterpType, Pixbuf };
 use gio::prelude::*;
 use gtk::prelude::*;
 use gtk::{ StateFlags, Image, Fixed };
 use gdk::RGBA;
+use glib::Continue;
+
+use std::cell::RefCell;
+use std::path::{ Path, PathBuf };
+use std::rc::Rc;
+use std::time::Duration;
 
 /// Declare external modules
 pub mod client_to_server_proxy;
 
-const FISH_FILENAME_TEMPLATE: &str = "assets/fish";
-const HEXAGON_FILENAME: &str = "assets/hexagon.png";
+/// The environment variable which, if set, overrides the directory image assets
+/// are loaded from. See default_assets_dir.
+pub const ASSETS_DIR_VARIABLE: &str = "FISH_ASSETS_DIR";
+
+/// The directory image assets are loaded from when ASSETS_DIR_VARIABLE is not set,
+/// relative to the working directory.
+const DEFAULT_ASSETS_DIR: &str = "assets";
+
+// Filenames of each image asset within the assets directory
+const FISH_FILENAME_TEMPLATE: &str = "fish";
+const HEXAGON_FILENAME: &str = "hexagon.png";
 
-const RED_PENGUIN_FILENAME: &str = "assets/penguin-blue.png";
-const WHITE_PENGUIN_FILENAME: &str = "assets/penguin-green.png";
-const BROWN_PENGUIN_FILENAME: &str = "assets/penguin-pink.png";
-const BLACK_PENGUIN_FILENAME: &str = "assets/penguin-purple.png";
+const RED_PENGUIN_FILENAME: &str = "penguin-blue.png";
+const WHITE_PENGUIN_FILENAME: &str = "penguin-green.png";
+const BROWN_PENGUIN_FILENAME: &str = "penguin-pink.png";
+const BLACK_PENGUIN_FILENAME: &str = "penguin-purple.png";
+
+/// Opacity of the hexagon drawn in place of a hole, darkening it
+/// against the background so the shape of the board stays visible.
+const HOLE_OPACITY: f64 = 0.25;
 
 /// Text to display above the current turn player image.
 const CURRENT_TURN_TEXT: &str = "Current Turn:";
@@ -33,6 +52,10 @@ const CURRENT_TURN_TEXT: &str = "Current Turn:";
 /// the image of the player whose turn it currently is.
 const CURRENT_TURN_TEXT_HEIGHT: i32 = 15;
 
+/// How long show_live_ui waits between turns, so that each
+/// turn stays on screen long enough to follow.
+const LIVE_TURN_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Width and height of the current window in pixels.
 const WINDOW_SIZE: (i32, i32) = (1600, 900);
 
@@ -46,20 +69,88 @@ const PLAYER_IMAGE_MARGIN: (i32, i32) = (
     (PLAYER_IMAGE_SIZE.1 as f32 * 1.5) as i32
 );
 
-/// Creates a single gtk::Image containing 1-5 fish
+/// The reasons an image asset can fail to load
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetError {
+    /// There is no file at the given path
+    Missing(PathBuf),
+
+    /// The file at the given path exists but could not be loaded as an image
+    Unreadable(PathBuf),
+}
+
+/// Returns the directory to load image assets from: the value of the
+/// ASSETS_DIR_VARIABLE environment variable if it is set, otherwise the
+/// assets directory relative to the working directory.
+pub fn default_assets_dir() -> PathBuf {
+    std::env::var_os(ASSETS_DIR_VARIABLE)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_ASSETS_DIR))
+}
+
+/// Returns the path of the asset with the given filename within the given
+/// assets directory, or AssetError::Missing if there is no such file.
+fn resolve_asset_path(assets_dir: &Path, filename: &str) -> Result<PathBuf, AssetError> {
+    let path = assets_dir.join(filename);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(AssetError::Missing(path))
+    }
+}
+
+/// Loads the image asset with the given filename from the given assets directory
+fn load_pixbuf(assets_dir: &Path, filename: &str) -> Result<Pixbuf, AssetError> {
+    let path = resolve_asset_path(assets_dir, filename)?;
+    Pixbuf::new_from_file(&path).map_err(|_| AssetError::Unreadable(path))
+}
+
+/// Checks that every image asset can be loaded from the given assets directory,
+/// so that a missing asset is reported before any window is shown.
+fn check_assets(assets_dir: &Path) -> Result<(), AssetError> {
+    let fish_filenames = (1 ..= 5).map(|fish_count| format!("{}{}.png", FISH_FILENAME_TEMPLATE, fish_count));
+    let other_filenames = vec![HEXAGON_FILENAME, RED_PENGUIN_FILENAME, WHITE_PENGUIN_FILENAME,
+        BROWN_PENGUIN_FILENAME, BLACK_PENGUIN_FILENAME].into_iter().map(String::from);
+
+    for filename in fish_filenames.chain(other_filenames) {
+        load_pixbuf(assets_dir, &filename)?;
+    }
+    Ok(())
+}
+
+/// Loads the image with the given filename, scaling both its width and height by
+/// the given scale. The image is loaded unchanged when the scale is 1.
+fn make_scaled_image(assets_dir: &Path, filename: &str, scale: f64) -> Result<Image, AssetError> {
+    let pixbuf = load_pixbuf(assets_dir, filename)?;
+    if (scale - 1.0).abs() < std::f64::EPSILON {
+        return Ok(Image::new_from_pixbuf(Some(&pixbuf)));
+    }
+
+    let (width, height) = scale_size((pixbuf.get_width(), pixbuf.get_height()), scale);
+    let scaled = pixbuf.scale_simple(width, height, InterpType::Hyper);
+    Ok(Image::new_from_pixbuf(scaled.as_ref()))
+}
+
+/// Scales a (width, height) pair by the given scale, never returning a
+/// dimension smaller than 1px.
+fn scale_size((width, height): (i32, i32), scale: f64) -> (i32, i32) {
+    (std::cmp::max(1, (width as f64 * scale) as i32), std::cmp::max(1, (height as f64 * scale) as i32))
+}
+
+/// Creates a single gtk::Image containing 1-5 fish, scaled by the given scale.
 /// This function will panic if given 0 fish.
 /// If asked for > 5 fish, this function will return an image of only 5 fish.
-fn make_fish_image(fish_count: usize) -> Image {
+fn make_fish_image(assets_dir: &Path, fish_count: usize, scale: f64) -> Result<Image, AssetError> {
     assert_ne!(fish_count, 0);
 
     // Limit tiles to displaying a max of 5 fish - that is all we have images for.
     let fish_count = std::cmp::min(5, fish_count);
     let filename = format!("{}{}.png", FISH_FILENAME_TEMPLATE, fish_count);
-    Image::new_from_file(filename)
+    make_scaled_image(assets_dir, &filename, scale)
 }
 
 /// Creates a single gtk::Image containing a penguin of the given color
-fn get_penguin_image(color: PlayerColor, width: i32, height: i32) -> Image {
+fn get_penguin_image(assets_dir: &Path, color: PlayerColor, width: i32, height: i32) -> Result<Image, AssetError> {
     let filename = match color {
         PlayerColor::red => RED_PENGUIN_FILENAME,
         PlayerColor::white => WHITE_PENGUIN_FILENAME,
@@ -67,9 +158,9 @@ fn get_penguin_image(color: PlayerColor, width: i32, height: i32) -> Image {
         PlayerColor::black => BLACK_PENGUIN_FILENAME,
     };
 
-    let pixbuf = Image::new_from_file(filename).get_pixbuf().unwrap();
+    let pixbuf = load_pixbuf(assets_dir, filename)?;
     let scaled = pixbuf.scale_simple(width, height, InterpType::Hyper);
-    Image::new_from_pixbuf(scaled.as_ref())
+    Ok(Image::new_from_pixbuf(scaled.as_ref()))
 }
 
 /// Adds the given Image to the layout, centering the image on the hexagonal tile.
@@ -81,11 +172,11 @@ fn add_image_centered_on_tile(layout: &Fixed, image: &Image, hexagon_size: (i32,
         hexagon_size.1 / 2 - image_size.1 / 2);
 }
 
-/// Generates a GTK drawing of a specific Tile
+/// Generates a GTK drawing of a specific Tile, with each image scaled by the given scale.
 /// Returns the drawing and a tuple of (width, height) in px of the tile
-fn make_tile_layout(tile: &Tile, penguin_color: Option<PlayerColor>) -> (Fixed, (i32, i32)) {
+fn make_tile_layout(assets_dir: &Path, tile: &Tile, penguin_color: Option<PlayerColor>, scale: f64) -> Result<(Fixed, (i32, i32)), AssetError> {
     let layout = Fixed::new();
-    let hexagon = Image::new_from_file(HEXAGON_FILENAME);
+    let hexagon = make_scaled_image(assets_dir, HEXAGON_FILENAME, scale)?;
     let hexagon_size = get_image_size(&hexagon);
     layout.add(&hexagon);
 
@@ -93,17 +184,29 @@ fn make_tile_layout(tile: &Tile, penguin_color: Option<PlayerColor>) -> (Fixed,
         // Scale the large penguin image down to (1/4 of the tile width, 1/2 of the tile height)
         // This size is rather arbitrary, it was just picked since it looks decent and is small
         // enough to show the fish underneath the penguin.
-        let penguin = get_penguin_image(color, hexagon_size.0 / 4, hexagon_size.1 / 2);
+        let penguin = get_penguin_image(assets_dir, color, hexagon_size.0 / 4, hexagon_size.1 / 2)?;
         add_image_centered_on_tile(&layout, &penguin, hexagon_size);
     }
 
     let fish_count = tile.get_fish_count();
     if fish_count > 0 {
-        let fish = make_fish_image(fish_count);
+        let fish = make_fish_image(assets_dir, fish_count, scale)?;
         add_image_centered_on_tile(&layout, &fish, hexagon_size);
     }
 
-    (layout, hexagon_size)
+    Ok((layout, hexagon_size))
+}
+
+/// Generates a GTK drawing of a hole in the board, an empty darkened hexagon
+/// scaled by the given scale.
+/// Returns the drawing and a tuple of (width, height) in px of the hole
+fn make_hole_layout(assets_dir: &Path, scale: f64) -> Result<(Fixed, (i32, i32)), AssetError> {
+    let layout = Fixed::new();
+    let hexagon = make_scaled_image(assets_dir, HEXAGON_FILENAME, scale)?;
+    let hexagon_size = get_image_size(&hexagon);
+    hexagon.set_opacity(HOLE_OPACITY);
+    layout.add(&hexagon);
+    Ok((layout, hexagon_size))
 }
 
 /// Gets the width and height of a gtk Image
@@ -114,8 +217,7 @@ fn get_image_size(img: &Image) -> (i32, i32) {
 
 /// Returns (x, y) tuple of position of tile in screen pixels where (0, 0)
 /// is the top-left most point and (SCREEN_WIDTH, SCREEN_HEIGHT) is the bottom right.
-fn get_tile_position_px(board: &Board, tile_id: TileId, (tile_width, tile_height): (i32, i32)) -> (i32, i32) {
-    let BoardPosn { x: col, y: row } = board.get_tile_position(tile_id);
+fn get_tile_position_px(BoardPosn { x: col, y: row }: BoardPosn, (tile_width, tile_height): (i32, i32)) -> (i32, i32) {
     let y = row as i32 * tile_height / 2;
 
     // odd rows are shifted an additional (2/3) to the right to interleave the hexagons in subsequent rows 
@@ -125,11 +227,38 @@ fn get_tile_position_px(board: &Board, tile_id: TileId, (tile_width, tile_height
     (x, y)
 }
 
+/// Returns the (width, height) in px of a board with the given number of columns and
+/// rows when each of its tiles is the given size, following get_tile_position_px.
+fn get_board_size_px(columns: u32, rows: u32, (tile_width, tile_height): (i32, i32)) -> (i32, i32) {
+    if columns == 0 || rows == 0 {
+        return (0, 0);
+    }
+
+    let last_row = if rows > 1 { 1 } else { 0 };
+    let (right_x, _) = get_tile_position_px(BoardPosn { x: columns - 1, y: last_row }, (tile_width, tile_height));
+    let (_, bottom_y) = get_tile_position_px(BoardPosn { x: 0, y: rows - 1 }, (tile_width, tile_height));
+    (right_x + tile_width, bottom_y + tile_height)
+}
+
+/// Returns the largest scale, up to 1, at which a board with the given number of
+/// columns and rows of tiles of the given unscaled size fits within the given window.
+/// Boards that already fit are drawn at their original size rather than enlarged.
+fn get_fit_scale(columns: u32, rows: u32, tile_size: (i32, i32), (window_width, window_height): (i32, i32)) -> f64 {
+    let (board_width, board_height) = get_board_size_px(columns, rows, tile_size);
+    if board_width == 0 || board_height == 0 {
+        return 1.0;
+    }
+
+    let width_scale = window_width as f64 / board_width as f64;
+    let height_scale = window_height as f64 / board_height as f64;
+    width_scale.min(height_scale).min(1.0)
+}
+
 /// Creates a widget layout containing a penguin icon with the color of the current
 /// player as well as a "current turn" text widget to indicate whose turn it is.
-fn make_current_turn_widget(gamestate: &GameState) -> gtk::Fixed {
+fn make_current_turn_widget(assets_dir: &Path, gamestate: &GameState) -> Result<gtk::Fixed, AssetError> {
     let current_player = &gamestate.players[&gamestate.current_turn];
-    let player_image = get_penguin_image(current_player.color, PLAYER_IMAGE_SIZE.0, PLAYER_IMAGE_SIZE.1);
+    let player_image = get_penguin_image(assets_dir, current_player.color, PLAYER_IMAGE_SIZE.0, PLAYER_IMAGE_SIZE.1)?;
 
     let layout = Fixed::new();
     layout.add(&player_image);
@@ -144,46 +273,215 @@ fn make_current_turn_widget(gamestate: &GameState) -> gtk::Fixed {
     layout.move_(&text,
                  WINDOW_SIZE.0 - PLAYER_IMAGE_MARGIN.0,
                  WINDOW_SIZE.1 - PLAYER_IMAGE_MARGIN.1 - CURRENT_TURN_TEXT_HEIGHT);
-    layout
+    Ok(layout)
 }
 
-/// Creates and displays a window in a given application displaying the given gamestate.
-/// The window draws itself each frame and holds a copy of the gamestate. Resultingly,
-/// any changes made to the shared gamestate will automatically be updated in the window
-/// the next time it is redrawn.
-fn make_window(application: &gtk::Application, gamestate: SharedGameState) {
-    let window = gtk::ApplicationWindow::new(application);
+/// Creates a layout drawing the given gamestate: each tile of the board, and whose turn it is.
+///
+/// Each tile is drawn with its images scaled by the given scale, or if None is given,
+/// by the largest scale that fits the whole board within the window (see get_fit_scale).
+/// Images are loaded from the given assets directory.
+fn make_gamestate_layout(assets_dir: &Path, gamestate: &GameState, scale: Option<f64>) -> Result<Fixed, AssetError> {
     let layout = Fixed::new();
+    let board = &gamestate.board;
+    let scale = match scale {
+        Some(scale) => scale,
+        None => {
+            let hexagon = load_pixbuf(assets_dir, HEXAGON_FILENAME)?;
+            let hexagon_size = (hexagon.get_width(), hexagon.get_height());
+            get_fit_scale(board.width, board.height, hexagon_size, WINDOW_SIZE)
+        },
+    };
 
-    window.override_background_color(StateFlags::NORMAL, Some(&RGBA::blue()));
-
-    // Draw each board tile
-    let gamestate_ref = gamestate.borrow();
-    for (tile_id, tile) in gamestate_ref.board.tiles.iter() {
-        let penguin_color_on_tile = gamestate_ref.get_color_on_tile(*tile_id);
-        let (tile_layout, tile_layout_size) = make_tile_layout(tile, penguin_color_on_tile);
+    // Draw each board tile, drawing holes as empty hexagons
+    for (position, tile) in board.tiles_row_major() {
+        let (tile_layout, tile_layout_size) = match tile {
+            Some(tile) => make_tile_layout(assets_dir, tile, gamestate.get_color_on_tile(tile.tile_id), scale)?,
+            None => make_hole_layout(assets_dir, scale)?,
+        };
         layout.add(&tile_layout);
-        let (new_x, new_y) = get_tile_position_px(&gamestate_ref.board, *tile_id, tile_layout_size);
+        let (new_x, new_y) = get_tile_position_px(position, tile_layout_size);
         layout.move_(&tile_layout, new_x, new_y); // moves to absolute x/y pos
     }
 
     // Add an icon and text representing whose turn it is to the bottom-left.
-    layout.add(&make_current_turn_widget(&gamestate_ref));
+    layout.add(&make_current_turn_widget(assets_dir, gamestate)?);
+    Ok(layout)
+}
+
+/// Creates and displays a window in a given application displaying the given gamestate
+/// as it is when the window is created. See make_gamestate_layout for how the gamestate is
+/// drawn, and redraw_window to show any changes made to the gamestate since.
+fn make_window(application: &gtk::Application, gamestate: &GameState, scale: Option<f64>, assets_dir: &Path) -> Result<gtk::ApplicationWindow, AssetError> {
+    let window = gtk::ApplicationWindow::new(application);
+    window.override_background_color(StateFlags::NORMAL, Some(&RGBA::blue()));
+    window.add(&make_gamestate_layout(assets_dir, gamestate, scale)?);
 
     window.set_default_size(WINDOW_SIZE.0, WINDOW_SIZE.1);
+    window.show_all();
+    Ok(window)
+}
+
+/// Replaces the contents of a window created by make_window with a drawing of the given gamestate.
+fn redraw_window(window: &gtk::ApplicationWindow, gamestate: &GameState, scale: Option<f64>, assets_dir: &Path) -> Result<(), AssetError> {
+    let layout = make_gamestate_layout(assets_dir, gamestate, scale)?;
+    for child in window.get_children() {
+        window.remove(&child);
+    }
     window.add(&layout);
     window.show_all();
+    Ok(())
 }
 
 /// Builds and shows the client side UI for the game.
 /// This takes care of window creation as well.
-pub fn show_ui(gamestate: SharedGameState) {
+///
+/// Tiles are drawn at the given scale, or scaled to fit the board within the window if None.
+/// Images are loaded from the given assets directory, see default_assets_dir. Returns an
+/// error without showing any window if any image is missing or cannot be loaded.
+pub fn show_ui(gamestate: SharedGameState, scale: Option<f64>, assets_dir: &Path) -> Result<(), AssetError> {
+    check_assets(assets_dir)?;
+
+    let application = gtk::Application::new(None, Default::default())
+        .expect("Initialization failed...");
+
+    let assets_dir = assets_dir.to_path_buf();
+    application.connect_activate(move |app| {
+        if let Err(error) = make_window(app, &gamestate.borrow(), scale, &assets_dir) {
+            eprintln!("Failed to load image asset: {:?}", error);
+            app.quit();
+        }
+    });
+
+    application.run(&[]);
+    Ok(())
+}
+
+/// Builds and shows the client side UI for a game as it is being played. Every
+/// LIVE_TURN_INTERVAL the given play_turn function is called, e.g. to play a turn of
+/// a referee::LiveGame writing into the given gamestate, and the window is redrawn to
+/// show the gamestate afterward. play_turn should return false once the game is over,
+/// after which the final gamestate stays on screen until the window is closed.
+///
+/// play_turn is called from the UI's own thread so a slow turn will briefly freeze the
+/// window. As with show_ui, an error is returned without showing any window if any
+/// image is missing or cannot be loaded.
+pub fn show_live_ui<F>(gamestate: SharedGameState, scale: Option<f64>, assets_dir: &Path, play_turn: F) -> Result<(), AssetError>
+    where F: FnMut() -> bool + 'static
+{
+    show_updating_ui(gamestate, scale, assets_dir, LIVE_TURN_INTERVAL, play_turn)
+}
+
+/// Builds and shows the client side UI replaying the given game, applying one of its
+/// actions every step_delay until all have been applied. See Replay::step. The board
+/// is scaled to fit within the window and images are loaded from the given assets directory.
+pub fn replay_ui(mut replay: Replay, step_delay: Duration, assets_dir: &Path) -> Result<(), AssetError> {
+    show_updating_ui(replay.shared_state(), None, assets_dir, step_delay, move || replay.step())
+}
+
+/// Shows the given gamestate as in show_ui, calling update every interval and redrawing the
+/// window afterward until update returns false. See show_live_ui.
+fn show_updating_ui<F>(gamestate: SharedGameState, scale: Option<f64>, assets_dir: &Path, interval: Duration, update: F) -> Result<(), AssetError>
+    where F: FnMut() -> bool + 'static
+{
+    check_assets(assets_dir)?;
+
     let application = gtk::Application::new(None, Default::default())
         .expect("Initialization failed...");
 
+    let assets_dir = assets_dir.to_path_buf();
+    let interval_ms = interval.as_millis() as u32;
+    let update = Rc::new(RefCell::new(update));
     application.connect_activate(move |app| {
-        make_window(app, gamestate.clone());
+        let window = match make_window(app, &gamestate.borrow(), scale, &assets_dir) {
+            Ok(window) => window,
+            Err(error) => {
+                eprintln!("Failed to load image asset: {:?}", error);
+                app.quit();
+                return;
+            },
+        };
+
+        let (gamestate, assets_dir, update) = (gamestate.clone(), assets_dir.clone(), update.clone());
+        gtk::timeout_add(interval_ms, move || {
+            let updating = (&mut *update.borrow_mut())();
+
+            if let Err(error) = redraw_window(&window, &gamestate.borrow(), scale, &assets_dir) {
+                eprintln!("Failed to load image asset: {:?}", error);
+                return Continue(false);
+            }
+            Continue(updating)
+        });
     });
 
     application.run(&[]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Size of assets/hexagon.png in pixels
+    const HEXAGON_SIZE: (i32, i32) = (300, 200);
+
+    /// Asserts the board fits within the window at the fit scale, and that
+    /// it could not be much larger and still fit.
+    fn assert_board_fits(columns: u32, rows: u32) -> f64 {
+        let scale = get_fit_scale(columns, rows, HEXAGON_SIZE, WINDOW_SIZE);
+        let (width, height) = get_board_size_px(columns, rows, scale_size(HEXAGON_SIZE, scale));
+
+        assert!(width <= WINDOW_SIZE.0 && height <= WINDOW_SIZE.1,
+            "{}x{} board is {}x{}px at scale {}", columns, rows, width, height, scale);
+        scale
+    }
+
+    #[test]
+    fn test_resolve_asset_path() {
+        let assets_dir = std::env::temp_dir().join("fish-test-resolve-asset-path");
+        std::fs::create_dir_all(&assets_dir).unwrap();
+        std::fs::write(assets_dir.join(HEXAGON_FILENAME), b"").unwrap();
+
+        assert_eq!(resolve_asset_path(&assets_dir, HEXAGON_FILENAME), Ok(assets_dir.join(HEXAGON_FILENAME)));
+        assert_eq!(resolve_asset_path(&assets_dir, RED_PENGUIN_FILENAME),
+            Err(AssetError::Missing(assets_dir.join(RED_PENGUIN_FILENAME))));
+
+        // A directory is not an asset, even if it has an asset's name
+        assert_eq!(resolve_asset_path(assets_dir.parent().unwrap(), "fish-test-resolve-asset-path"),
+            Err(AssetError::Missing(assets_dir.clone())));
+
+        // The empty file resolves but is not a valid image
+        assert_eq!(load_pixbuf(&assets_dir, HEXAGON_FILENAME).err(),
+            Some(AssetError::Unreadable(assets_dir.join(HEXAGON_FILENAME))));
+
+        std::fs::remove_dir_all(&assets_dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_board_size_px() {
+        // 0     2     4
+        //    1     3     5
+        assert_eq!(get_board_size_px(3, 2, HEXAGON_SIZE), (1300, 300));
+        assert_eq!(get_board_size_px(3, 1, HEXAGON_SIZE), (1100, 200));
+        assert_eq!(get_board_size_px(1, 5, HEXAGON_SIZE), (500, 600));
+        assert_eq!(get_board_size_px(0, 5, HEXAGON_SIZE), (0, 0));
+    }
+
+    #[test]
+    fn test_get_fit_scale() {
+        // Small boards are not enlarged
+        assert_eq!(assert_board_fits(3, 5), 1.0);
+        assert_eq!(assert_board_fits(1, 1), 1.0);
+
+        // Wide boards are limited by the window width, 4100px wide at full size
+        let scale = assert_board_fits(10, 4);
+        assert!((scale - 1600.0 / 4100.0).abs() < 1e-9);
+
+        // Tall boards are limited by the window height, 2100px tall at full size
+        let scale = assert_board_fits(2, 20);
+        assert!((scale - 900.0 / 2100.0).abs() < 1e-9);
+
+        assert_board_fits(25, 25);
+        assert_board_fits(100, 3);
+    }
 }
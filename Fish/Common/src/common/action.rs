@@ -17,6 +17,12 @@ use serde::{ Serialize, Deserialize };
 /// tile it is moving to must be reachable from the penguin's current
 /// tile, etc. See GameState::move_avatar_for_player for more details
 /// on making moves and the conditions for which they are valid.
+///
+/// Moves are ordered by the TileId they start from, then by the TileId they end on.
+/// This gives a well-defined order for sorting moves, but since TileIds are
+/// column-major it is not the same as ordering by board position. Strategies
+/// that tie-break by position (see strategy::find_best_move) must compare the
+/// BoardPosns of the tiles rather than rely on this ordering.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Move {
     pub from: TileId,
@@ -32,6 +38,29 @@ impl Move {
     }
 }
 
+/// The reasons a Move can be rejected. See GameState::validate_move.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// The player making the move is not in the game
+    UnknownPlayer,
+
+    /// The player has no penguin on the tile being moved from
+    NoPenguin,
+
+    /// The move starts and ends on the same tile. This is checked separately
+    /// from Unreachable since a tile is never considered reachable from itself.
+    NoMovement,
+
+    /// The destination already has a penguin on it. This is checked separately
+    /// from Unreachable so that an occupied destination is always rejected, even
+    /// if the path to it is otherwise clear.
+    DestinationOccupied,
+
+    /// The destination cannot be reached in a straight line from the starting
+    /// tile, either because it is a hole, out of bounds, or blocked by a hole or penguin
+    Unreachable,
+}
+
 /// Represents a move that has been  made by a given player
 #[derive(Copy, Clone, Debug)]
 pub struct PlayerMove {
@@ -41,9 +70,12 @@ pub struct PlayerMove {
 }
 
 impl PlayerMove {
+    /// Creates a PlayerMove from a Move made on the given state, returning None
+    /// if either end of the move is not a tile on the board. The move may come from
+    /// an untrusted client so its TileIds are not assumed to be valid.
     pub fn new(mover: PlayerColor, move_: Move, state: &GameState) -> Option<PlayerMove> {
-        let from = state.board.get_tile_position(move_.from);
-        let to = state.board.get_tile_position(move_.to);
+        let from = state.board.try_get_tile_position(move_.from)?;
+        let to = state.board.try_get_tile_position(move_.to)?;
         Some(PlayerMove { mover, from, to })
     }
 }
@@ -57,6 +89,9 @@ impl PlayerMove {
 /// will validate the message came from the player whose turn it
 /// currently is then make the move if possible. See
 /// GameState::place_avatar_for_player for more info on invalid placements.
+///
+/// Placements are ordered by the TileId they place onto. As with Moves, this is
+/// not the same as ordering by board position since TileIds are column-major.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Placement {
     pub tile_id: TileId,
@@ -70,3 +105,92 @@ impl Placement {
         Placement { tile_id }
     }
 }
+
+/// The reasons a Placement can be rejected. See GameState::validate_placement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    /// The player making the placement is not in the game
+    UnknownPlayer,
+
+    /// The player has already placed all of their penguins
+    NoUnplacedPenguin,
+
+    /// The tile being placed onto is a hole or out of bounds
+    NoTile,
+
+    /// The tile being placed onto already has a penguin on it
+    Occupied,
+}
+
+/// Either action a player can take on their turn: placing a penguin while
+/// penguins are being placed, or moving one afterward.
+/// See GameState::apply_action for applying an Action to a game.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    PlacePenguin(Placement),
+    MovePenguin(Move),
+}
+
+/// Which kind of Action a game expects next, without the details of the action itself.
+/// See GamePhase::expected_action.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ActionKind {
+    Placement,
+    Move,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::board::Board;
+
+    #[test]
+    fn test_move_sort_order() {
+        let mut moves = vec![
+            Move::new(TileId(3), TileId(1)),
+            Move::new(TileId(0), TileId(5)),
+            Move::new(TileId(3), TileId(0)),
+            Move::new(TileId(0), TileId(2)),
+            Move::new(TileId(1), TileId(9)),
+        ];
+        moves.sort();
+
+        assert_eq!(moves, vec![
+            Move::new(TileId(0), TileId(2)),
+            Move::new(TileId(0), TileId(5)),
+            Move::new(TileId(1), TileId(9)),
+            Move::new(TileId(3), TileId(0)),
+            Move::new(TileId(3), TileId(1)),
+        ]);
+    }
+
+    #[test]
+    fn test_placement_sort_order() {
+        let mut placements = vec![
+            Placement::new(TileId(4)),
+            Placement::new(TileId(0)),
+            Placement::new(TileId(7)),
+            Placement::new(TileId(2)),
+        ];
+        placements.sort();
+
+        assert_eq!(placements, vec![
+            Placement::new(TileId(0)),
+            Placement::new(TileId(2)),
+            Placement::new(TileId(4)),
+            Placement::new(TileId(7)),
+        ]);
+    }
+
+    /// On a 3x3 board tile 1 is at (0, 1) and tile 3 is at (1, 0). Ordering by
+    /// TileId puts tile 1 first while ordering by position (row first) puts tile 3 first.
+    #[test]
+    fn test_move_order_is_not_position_order() {
+        let board = Board::with_no_holes(3, 3, 1);
+        let a = Move::new(TileId(1), TileId(0));
+        let b = Move::new(TileId(3), TileId(0));
+
+        assert!(a < b);
+        assert!(board.get_tile_position(b.from) < board.get_tile_position(a.from));
+    }
+}
@@ -10,7 +10,12 @@
 //! on line 22.
 use crate::common::tile::{ Tile, TileId };
 use crate::common::boardposn::BoardPosn;
-use std::collections::BTreeMap;
+use crate::common::direction::Direction;
+use crate::common::action::Move;
+use crate::common::util;
+use std::cmp::Reverse;
+use std::convert::TryFrom;
+use std::collections::{ BTreeMap, HashSet };
 
 use serde::{ Serialize, Deserialize };
 
@@ -36,6 +41,80 @@ pub struct Board {
     pub height: u32,
 }
 
+/// Named board layouts so that games can be played on boards with a consistent
+/// size and amount of fish. See Board::preset for the layout of each.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BoardPreset {
+    /// A small board with only 1 or 2 fish per tile
+    Sparse,
+
+    /// A medium board with 1 to 3 fish per tile
+    Standard,
+
+    /// A medium board with 3 to 5 fish per tile
+    Dense,
+
+    /// A large board with 1 to 4 fish per tile and a regular pattern of holes
+    Holey,
+}
+
+/// The largest number of rows or columns a board created with a checked
+/// constructor such as Board::checked_with_no_holes may have.
+///
+/// Boards from the unchecked constructors or deserialized from json may be larger.
+/// Their tile ids are still computed with checked arithmetic, so a position whose id
+/// would not fit in a TileId is treated as out of bounds rather than wrapping around.
+pub const MAX_BOARD_DIMENSION: u32 = 100;
+
+/// The most fish a tile of a board created by Board::with_seed may have.
+pub const MAX_RANDOM_FISH: usize = 5;
+
+/// The reasons a board's dimensions can be rejected. See Board::check_dimensions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DimensionError {
+    /// The board would have no rows or no columns, and thus no tiles
+    Empty,
+
+    /// The board's rows or columns exceed MAX_BOARD_DIMENSION
+    TooLarge,
+}
+
+/// Prints the given error to stderr and exits the process with status 1. Binaries that
+/// build boards from their input use this to reject invalid boards in the same way, e.g.
+/// `Board::checked_from_tiles(tiles).unwrap_or_else(exit_invalid_board)`.
+pub fn exit_invalid_board<T>(error: DimensionError) -> T {
+    eprintln!("Invalid board dimensions: {:?}", error);
+    std::process::exit(1)
+}
+
+/// The reasons Board::from_text can fail to parse a board
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text contains no rows of tiles
+    Empty,
+
+    /// A character other than a digit, 'x', or whitespace was found at the given row and column
+    InvalidCharacter { row: usize, column: usize, character: char },
+
+    /// The given row has a different number of tiles than the first row
+    RaggedRow { row: usize },
+}
+
+/// The ways a board's tiles can fail to link to each other consistently.
+/// See Board::validate_adjacency.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdjacencyError {
+    /// The tile is stored under a different TileId than its own tile_id
+    MismatchedId { key: TileId, tile_id: TileId },
+
+    /// The tile lists a neighbor in the given direction which is not on the board
+    MissingNeighbor { tile: TileId, direction: Direction, neighbor: TileId },
+
+    /// The tile lists a neighbor in the given direction, but that neighbor does
+    /// not list the tile back in the opposite direction
+    NotLinkedBack { tile: TileId, direction: Direction, neighbor: TileId },
+}
+
 impl Board {
     /// Creates a board that has the same number of fish on every tile and has no holes
     ///
@@ -69,26 +148,56 @@ impl Board {
 
         for x in 0 .. width {
             for y in 0 .. height { // ids are generated in row-major order
-                let is_odd_row = y % 2; // 1 if odd, 0 if not
-                let is_even_row = (y + 1) % 2;
-                let tile_id = Board::compute_tile_id(width, height, x, y).unwrap();
-
-                tiles.insert(tile_id, Tile {
-                    tile_id,
-                    fish_count: fish_per_tile,
-                    northeast: Board::compute_tile_id(width, height, x + is_odd_row, y - 1),
-                    northwest: Board::compute_tile_id(width, height, x - is_even_row, y - 1),
-                    north:     Board::compute_tile_id(width, height, x, y - 2),
-                    south:     Board::compute_tile_id(width, height, x, y + 2),
-                    southeast: Board::compute_tile_id(width, height, x + is_odd_row, y + 1),
-                    southwest: Board::compute_tile_id(width, height, x - is_even_row, y + 1),
-                });
+                let tile = Board::make_tile(width, height, x, y, fish_per_tile);
+                tiles.insert(tile.tile_id, tile);
             }
         }
         
         Board { tiles, width: columns, height: rows }
     }
 
+    /// Creates a board with the given number of rows and columns and no holes, giving
+    /// each tile between 1 and MAX_RANDOM_FISH fish chosen pseudo-randomly from the
+    /// given seed. The same seed always produces the same board.
+    pub fn with_seed(rows: u32, columns: u32, seed: u64) -> Board {
+        let mut board = Board::with_no_holes(rows, columns, 1);
+        let mut seed_state = seed;
+
+        for tile in board.tiles.values_mut() {
+            tile.fish_count = 1 + util::random_range(&mut seed_state, MAX_RANDOM_FISH);
+        }
+
+        board
+    }
+
+    /// Checks that a board with the given number of rows and columns would have at least
+    /// one tile, and no more than MAX_BOARD_DIMENSION rows or columns. The unchecked
+    /// constructors accept any dimensions, so boards built from untrusted input such
+    /// as JSON should be built with the checked constructors instead.
+    pub fn check_dimensions(rows: u32, columns: u32) -> Result<(), DimensionError> {
+        if rows == 0 || columns == 0 {
+            Err(DimensionError::Empty)
+        } else if rows > MAX_BOARD_DIMENSION || columns > MAX_BOARD_DIMENSION {
+            Err(DimensionError::TooLarge)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Creates a board as in Board::with_no_holes after checking its dimensions
+    /// with Board::check_dimensions.
+    pub fn checked_with_no_holes(rows: u32, columns: u32, fish_per_tile: usize) -> Result<Board, DimensionError> {
+        Board::check_dimensions(rows, columns)?;
+        Ok(Board::with_no_holes(rows, columns, fish_per_tile))
+    }
+
+    /// Creates a board as in Board::with_holes after checking its dimensions
+    /// with Board::check_dimensions.
+    pub fn checked_with_holes(rows: u32, columns: u32, holes: Vec<BoardPosn>, min_tiles_with_1_fish: u32) -> Result<Board, DimensionError> {
+        Board::check_dimensions(rows, columns)?;
+        Ok(Board::with_holes(rows, columns, holes, min_tiles_with_1_fish))
+    }
+
     /// Creates a board that has holes in specific places and is set
     /// up with a minimum number of 1-fish tiles
     pub fn with_holes(rows: u32, columns: u32, mut holes: Vec<BoardPosn>, min_tiles_with_1_fish: u32) -> Board {
@@ -118,20 +227,58 @@ impl Board {
     /// tile at that position. If the number is 0, there
     /// is a hole at that position.
     pub fn from_tiles(tiles: Vec<Vec<u32>>) -> Board {
-        let rows = tiles.len() as u32;
-        let columns = tiles.get(0).map_or(0, |row| row.len()) as u32;
-        
+        let grid = tiles.into_iter().map(|row| {
+            row.into_iter().map(|fish_count| {
+                if fish_count == 0 { None } else { Some(fish_count as usize) }
+            }).collect()
+        }).collect();
+
+        Board::from_fish_grid(grid)
+    }
+
+    /// Creates a board as in Board::from_tiles after checking its dimensions with
+    /// Board::check_dimensions. The board has a column for each entry of its longest row.
+    pub fn checked_from_tiles(tiles: Vec<Vec<u32>>) -> Result<Board, DimensionError> {
+        let rows = u32::try_from(tiles.len()).map_err(|_| DimensionError::TooLarge)?;
+        let columns = tiles.iter().map(|row| row.len()).max().unwrap_or(0);
+        let columns = u32::try_from(columns).map_err(|_| DimensionError::TooLarge)?;
+
+        Board::check_dimensions(rows, columns)?;
+        Ok(Board::from_tiles(tiles))
+    }
+
+    /// Returns the fish on each position of this board as a list of rows, with holes as 0.
+    /// This is the board format used in JSON messages. Since tiles with no fish are also 0,
+    /// this is only the inverse of Board::from_tiles for boards where every tile has fish.
+    pub fn to_fish_grid(&self) -> Vec<Vec<u32>> {
+        (0 .. self.height).map(|row| {
+            (0 .. self.width).map(|column| {
+                self.get_tile(column, row).map_or(0, |tile| tile.fish_count as u32)
+            }).collect()
+        }).collect()
+    }
+
+    /// Create a Board from a 2D Vec in row-major order
+    /// (a list of rows), where each entry in the matrix is
+    /// None if there is a hole at that position, or Some with the
+    /// number of fish on the tile at that position. Unlike from_tiles,
+    /// Some(0) is a tile with no fish on it rather than a hole.
+    pub fn from_fish_grid(grid: Vec<Vec<Option<usize>>>) -> Board {
+        let rows = grid.len() as u32;
+        let columns = grid.first().map_or(0, |row| row.len()) as u32;
+
         let mut board = Board::with_no_holes(rows, columns, 1);
 
-        for (y, row) in tiles.into_iter().enumerate() {
+        for (y, row) in grid.into_iter().enumerate() {
             for (x, fish_count) in row.into_iter().enumerate() {
                 let tile = board.get_tile_mut(x as u32, y as u32).unwrap();
 
-                if fish_count == 0 {
-                    let tile_id = tile.tile_id;
-                    board.remove_tile(tile_id);
-                } else {
-                    tile.fish_count = fish_count as usize;
+                match fish_count {
+                    Some(fish_count) => tile.fish_count = fish_count,
+                    None => {
+                        let tile_id = tile.tile_id;
+                        board.remove_tile(tile_id);
+                    },
                 }
             }
         }
@@ -139,14 +286,102 @@ impl Board {
         board
     }
 
+    /// Create a Board from text where each non-empty line is a row of the board.
+    /// Each digit is a tile with that many fish on it (0 being a tile with no fish)
+    /// and each 'x' is a hole. Whitespace is ignored, so odd rows may be indented
+    /// to mirror the offset layout documented on Board::with_no_holes:
+    ///
+    /// 1 2 x
+    ///  3 0 1
+    /// x 4 1
+    ///
+    /// Every row must have the same number of tiles.
+    pub fn from_text(text: &str) -> Result<Board, ParseError> {
+        let mut grid: Vec<Vec<Option<usize>>> = vec![];
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let row_index = grid.len();
+            let mut row = vec![];
+
+            for character in line.chars().filter(|character| !character.is_whitespace()) {
+                let tile = match character {
+                    'x' => None,
+                    _ => match character.to_digit(10) {
+                        Some(fish_count) => Some(fish_count as usize),
+                        None => return Err(ParseError::InvalidCharacter { row: row_index, column: row.len(), character }),
+                    },
+                };
+                row.push(tile);
+            }
+
+            if !grid.is_empty() && grid[0].len() != row.len() {
+                return Err(ParseError::RaggedRow { row: row_index });
+            }
+            grid.push(row);
+        }
+
+        if grid.is_empty() {
+            Err(ParseError::Empty)
+        } else {
+            Ok(Board::from_fish_grid(grid))
+        }
+    }
+
+    /// Creates the board for the given preset. The same preset always creates the same board:
+    /// the fish on each tile are a fixed pattern of the tile's position rather than random.
+    ///
+    /// Sparse:   6 rows x 4 columns, 1-2 fish per tile, no holes
+    /// Standard: 8 rows x 5 columns, 1-3 fish per tile, no holes
+    /// Dense:    8 rows x 5 columns, 3-5 fish per tile, no holes
+    /// Holey:    10 rows x 6 columns, 1-4 fish per tile, every tile with (x + 2y) % 5 == 4 is a hole
+    pub fn preset(preset: BoardPreset) -> Board {
+        let (rows, columns) = match preset {
+            BoardPreset::Sparse => (6, 4),
+            BoardPreset::Standard | BoardPreset::Dense => (8, 5),
+            BoardPreset::Holey => (10, 6),
+        };
+
+        let fish_at = |x: usize, y: usize| match preset {
+            BoardPreset::Sparse => Some(1 + (x + y) % 2),
+            BoardPreset::Standard => Some(1 + (x + 2 * y) % 3),
+            BoardPreset::Dense => Some(3 + (2 * x + y) % 3),
+            BoardPreset::Holey if (x + 2 * y) % 5 == 4 => None,
+            BoardPreset::Holey => Some(1 + (3 * x + y) % 4),
+        };
+
+        let grid = (0 .. rows).map(|y| (0 .. columns).map(|x| fish_at(x, y)).collect()).collect();
+        Board::from_fish_grid(grid)
+    }
+
+    /// Creates the Tile at (tile_x, tile_y) with links to each of its in-bounds neighbors,
+    /// using the neighbor formulas documented on Board::with_no_holes. The neighbors
+    /// are not checked for holes. Panics if the position is out of bounds.
+    fn make_tile(width: i64, height: i64, x: i64, y: i64, fish_count: usize) -> Tile {
+        let is_odd_row = y % 2; // 1 if odd, 0 if not
+        let is_even_row = (y + 1) % 2;
+
+        Tile {
+            tile_id: Board::compute_tile_id(width, height, x, y).unwrap(),
+            fish_count,
+            northeast: Board::compute_tile_id(width, height, x + is_odd_row, y - 1),
+            northwest: Board::compute_tile_id(width, height, x - is_even_row, y - 1),
+            north:     Board::compute_tile_id(width, height, x, y - 2),
+            south:     Board::compute_tile_id(width, height, x, y + 2),
+            southeast: Board::compute_tile_id(width, height, x + is_odd_row, y + 1),
+            southwest: Board::compute_tile_id(width, height, x - is_even_row, y + 1),
+        }
+    }
+
     /// Computes the TileId for a tile at (tile_x, tile_y) iff the tile is within the given boundaries.
-    /// tile_x and tile_y are given as (col, row) rather than position in px
+    /// tile_x and tile_y are given as (col, row) rather than position in px.
+    /// Also returns None if the id would overflow, which can only happen on boards far
+    /// larger than MAX_BOARD_DIMENSION.
     fn compute_tile_id(board_width: i64, board_height: i64, tile_x: i64, tile_y: i64) -> Option<TileId> {
         if tile_x < 0 || tile_y < 0 || tile_x >= board_width || tile_y >= board_height {
             None
         } else {
-            let id = tile_x * board_height + tile_y;
-            Some(TileId(id as usize))
+            let id = tile_x.checked_mul(board_height)?.checked_add(tile_y)?;
+            usize::try_from(id).ok().map(TileId)
         }
     }
 
@@ -154,11 +389,26 @@ impl Board {
     /// Position returned is (col, row) rather than position in px
     /// Assumes tile_id is valid for this board
     pub fn get_tile_position(&self, tile_id: TileId) -> BoardPosn {
-        let x = tile_id.0 as u32 / self.height;
-        let y = tile_id.0 as u32 % self.height;
+        let height = self.height as usize;
+        let x = (tile_id.0 / height) as u32;
+        let y = (tile_id.0 % height) as u32;
         BoardPosn { x, y }
     }
 
+    /// Computes the position of a tile on this board from its id, as in get_tile_position,
+    /// but returns None if the id is out of bounds or refers to a hole. This should be
+    /// preferred for ids that may be untrusted, e.g. ones received from another client.
+    pub fn try_get_tile_position(&self, tile_id: TileId) -> Option<BoardPosn> {
+        self.tiles.get(&tile_id)?;
+        let position = self.get_tile_position(tile_id);
+
+        if position.x < self.width && position.y < self.height {
+            Some(position)
+        } else {
+            None
+        }
+    }
+
     /// Returns the TileId of the tile at tile_x, tile_y on this board
     /// or None if it's a hole or out of bounds
     pub fn get_tile_id(&self, tile_x: u32, tile_y: u32) -> Option<TileId> {
@@ -183,6 +433,141 @@ impl Board {
         self.tiles.get_mut(&expected_tile_id)
     }
 
+    /// Returns true if the given position is within the bounds of this board
+    /// but has no tile on it.
+    pub fn is_hole(&self, pos: BoardPosn) -> bool {
+        pos.x < self.width && pos.y < self.height && self.get_tile(pos.x, pos.y).is_none()
+    }
+
+    /// Returns the number of tiles still present on this board.
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns the number of positions within the bounds of this board that have no tile,
+    /// whether they started as holes or their tiles were removed during the game.
+    pub fn hole_count(&self) -> usize {
+        self.width as usize * self.height as usize - self.tile_count()
+    }
+
+    /// Checks that every tile's neighbors are on this board and link back to it: if
+    /// a tile lists another as its neighbor in some direction, that neighbor must list
+    /// the tile in the opposite direction. Boards built and modified through Board's
+    /// methods always satisfy this, so this is mainly useful in tests and for boards
+    /// that were deserialized. Returns the first inconsistency found, if any.
+    pub fn validate_adjacency(&self) -> Result<(), AdjacencyError> {
+        for (key, tile) in self.tiles.iter() {
+            if *key != tile.tile_id {
+                return Err(AdjacencyError::MismatchedId { key: *key, tile_id: tile.tile_id });
+            }
+
+            for direction in Direction::iter() {
+                if let Some(neighbor_id) = tile.get_neighbor_id(direction) {
+                    let neighbor = self.tiles.get(neighbor_id).ok_or(
+                        AdjacencyError::MissingNeighbor { tile: *key, direction, neighbor: *neighbor_id })?;
+
+                    if neighbor.get_neighbor_id(direction.opposite()) != Some(key) {
+                        return Err(AdjacencyError::NotLinkedBack { tile: *key, direction, neighbor: *neighbor_id });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every position on this board in row-major order, paired with the
+    /// tile at that position or None if the position is a hole.
+    pub fn tiles_row_major(&self) -> Vec<(BoardPosn, Option<&Tile>)> {
+        let mut tiles = vec![];
+        for y in 0 .. self.height {
+            for x in 0 .. self.width {
+                tiles.push((BoardPosn { x, y }, self.get_tile(x, y)));
+            }
+        }
+        tiles
+    }
+
+    /// Returns each tile adjacent to the given tile along with the Direction it lies in,
+    /// in the order of Direction::iter. Holes are not included, and a tile that is itself
+    /// a hole has no neighbors.
+    pub fn neighbors_with_direction(&self, tile_id: TileId) -> Vec<(Direction, TileId)> {
+        match self.tiles.get(&tile_id) {
+            Some(tile) => Direction::iter()
+                .filter_map(|direction| tile.get_neighbor_id(direction).map(|neighbor_id| (direction, *neighbor_id)))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns each adjacency between two tiles on this board as an undirected edge,
+    /// with the lower TileId first. Every edge appears exactly once, and the edges
+    /// are sorted by TileId. Holes have no edges.
+    pub fn edge_list(&self) -> Vec<(TileId, TileId)> {
+        let mut edges: Vec<_> = self.tiles.iter().flat_map(|(tile_id, tile)| {
+            Direction::iter()
+                .filter_map(move |direction| tile.get_neighbor_id(direction))
+                .filter(move |neighbor_id| *neighbor_id > tile_id)
+                .map(move |neighbor_id| (*tile_id, *neighbor_id))
+        }).collect();
+
+        edges.sort();
+        edges
+    }
+
+    /// Returns the tile with the most tiles reachable from it in a straight line while
+    /// no penguins are on the board, the natural best first placement. Ties are broken
+    /// in favor of the lowest position: the topmost tile, then the leftmost.
+    /// Returns None if the board has no tiles.
+    pub fn most_connected_tile(&self) -> Option<TileId> {
+        let no_penguins = HashSet::new();
+        self.tiles.values()
+            .map(|tile| {
+                let reachable = tile.all_reachable_tiles(self, &no_penguins).len();
+                (reachable, Reverse(self.get_tile_position(tile.tile_id)), tile.tile_id)
+            })
+            .max()
+            .map(|(_, _, tile_id)| tile_id)
+    }
+
+    /// Returns the Direction travelled by the given Move, or None if its destination
+    /// cannot be reached in a straight line of tiles from its starting tile. Only holes
+    /// block the line - penguins are not considered, so the Move may still be invalid.
+    pub fn move_direction(&self, move_: Move) -> Option<Direction> {
+        Direction::iter().find(|direction| self.straight_line(move_.from, *direction).contains(&move_.to))
+    }
+
+    /// Returns the ids of each tile passed over by the given Move in order, from its
+    /// starting tile to its destination inclusive. Returns an empty Vec if there is no
+    /// straight line of tiles between them, see Board::move_direction.
+    pub fn move_path(&self, move_: Move) -> Vec<TileId> {
+        let direction = match self.move_direction(move_) {
+            Some(direction) => direction,
+            None => return vec![],
+        };
+
+        let line = self.straight_line(move_.from, direction);
+        let end = line.iter().position(|tile_id| *tile_id == move_.to).unwrap();
+
+        let mut path = vec![move_.from];
+        path.extend_from_slice(&line[..= end]);
+        path
+    }
+
+    /// Returns the ids of the tiles in a straight line from the given tile in the given
+    /// direction, stopping at the first hole or the edge of the board. The starting tile
+    /// itself is not included.
+    fn straight_line(&self, from: TileId, direction: Direction) -> Vec<TileId> {
+        let mut line = vec![];
+        let mut current = self.tiles.get(&from);
+
+        while let Some(neighbor_id) = current.and_then(|tile| tile.get_neighbor_id(direction)) {
+            line.push(*neighbor_id);
+            current = self.tiles.get(neighbor_id);
+        }
+
+        line
+    }
+
     /// Removes a given Tile from the board if possible.
     /// Returns the amount of fish on the tile, or 0 if a tile was not removed.
     pub fn remove_tile(&mut self, tile_id: TileId) -> usize {
@@ -194,6 +579,40 @@ impl Board {
             0
         }
     }
+
+    /// Removes the tile at the given position if it is present, or restores it with
+    /// fish_count fish if it is a hole. fish_count is ignored when removing a tile, so
+    /// toggling a tile back should pass the fish it had before it was removed. A restored
+    /// tile is linked to each of its neighbors that is not a hole, and those neighbors
+    /// are linked back to it.
+    ///
+    /// Returns true if the tile is now present on the board, or false if it is now
+    /// a hole. Positions that are out of bounds are left untouched and return false.
+    pub fn toggle_tile(&mut self, pos: BoardPosn, fish_count: usize) -> bool {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let tile_id = match Board::compute_tile_id(width, height, pos.x as i64, pos.y as i64) {
+            Some(tile_id) => tile_id,
+            None => return false,
+        };
+
+        if self.tiles.contains_key(&tile_id) {
+            self.remove_tile(tile_id);
+            false
+        } else {
+            let mut tile = Board::make_tile(width, height, pos.x as i64, pos.y as i64, fish_count);
+
+            for direction in Direction::iter() {
+                let neighbor_link = tile.get_neighbor_id_mut(direction);
+                match neighbor_link.and_then(|id| self.tiles.get_mut(&id)) {
+                    Some(neighbor) => *neighbor.get_neighbor_id_mut(direction.opposite()) = Some(tile_id),
+                    None => *neighbor_link = None,
+                }
+            }
+
+            self.tiles.insert(tile_id, tile);
+            true
+        }
+    }
 }
 
 // Can we use Board::with_no_holes to initialize tiles?
@@ -221,6 +640,150 @@ fn test_board_with_no_holes() {
     }
 }
 
+// Does Board::from_text build the same board as the equivalent
+// fish grid, and reject malformed text?
+#[test]
+fn test_board_from_text() {
+    let text = "
+        1 2 x
+         3 0 1
+        x 4 1
+    ";
+    let expected = Board::from_fish_grid(vec![
+        vec![Some(1), Some(2), None],
+        vec![Some(3), Some(0), Some(1)],
+        vec![None, Some(4), Some(1)],
+    ]);
+    assert_eq!(Board::from_text(text), Ok(expected));
+
+    assert_eq!(Board::from_text(" \n\n"), Err(ParseError::Empty));
+    assert_eq!(Board::from_text("1 2\n3 y"),
+        Err(ParseError::InvalidCharacter { row: 1, column: 1, character: 'y' }));
+    assert_eq!(Board::from_text("1 2\n3 4 5"), Err(ParseError::RaggedRow { row: 1 }));
+}
+
+#[test]
+fn test_to_fish_grid() {
+    let grid = vec![
+        vec![1, 0, 3, 5],
+        vec![0, 2, 2, 1],
+        vec![4, 1, 0, 0],
+    ];
+    assert_eq!(Board::from_tiles(grid.clone()).to_fish_grid(), grid);
+    assert_eq!(Board::with_no_holes(2, 3, 2).to_fish_grid(), vec![vec![2, 2, 2], vec![2, 2, 2]]);
+}
+
+#[test]
+fn test_tile_and_hole_count() {
+    let board = Board::with_no_holes(3, 4, 2);
+    assert_eq!(board.tile_count(), 12);
+    assert_eq!(board.hole_count(), 0);
+
+    let mut board = Board::with_holes(3, 4, vec![(0, 0).into(), (2, 1).into(), (3, 2).into()], 0);
+    assert_eq!(board.tile_count(), 9);
+    assert_eq!(board.hole_count(), 3);
+
+    // Removing a tile during the game also makes a hole
+    board.remove_tile(TileId(1));
+    assert_eq!(board.tile_count(), 8);
+    assert_eq!(board.hole_count(), 4);
+
+    // The number of positions of a board too large to build does not overflow
+    let huge_board = Board { tiles: BTreeMap::new(), width: 100_000, height: 100_000 };
+    assert_eq!(huge_board.hole_count(), 10_000_000_000);
+}
+
+#[test]
+fn test_validate_adjacency() {
+    let mut board = Board::with_holes(4, 3, vec![(1, 1).into()], 0);
+    assert_eq!(board.validate_adjacency(), Ok(()));
+
+    for tile_id in [0, 6, 7].iter() {
+        board.remove_tile(TileId(*tile_id));
+    }
+    board.toggle_tile((1, 1).into(), 1);
+    assert_eq!(board.validate_adjacency(), Ok(()));
+
+    // 0   4   8
+    //   1   5   9
+    // 2   6   10
+    //   3   7   11
+    let mut corrupted = Board::with_no_holes(4, 3, 1);
+    corrupted.tiles.get_mut(&TileId(5)).unwrap().south = None;
+    assert_eq!(corrupted.validate_adjacency(),
+        Err(AdjacencyError::NotLinkedBack { tile: TileId(7), direction: Direction::North, neighbor: TileId(5) }));
+
+    let mut corrupted = Board::with_no_holes(4, 3, 1);
+    corrupted.tiles.remove(&TileId(9));
+    assert_eq!(corrupted.validate_adjacency(),
+        Err(AdjacencyError::MissingNeighbor { tile: TileId(8), direction: Direction::Southeast, neighbor: TileId(9) }));
+
+    let mut corrupted = Board::with_no_holes(4, 3, 1);
+    corrupted.tiles.get_mut(&TileId(2)).unwrap().tile_id = TileId(3);
+    assert_eq!(corrupted.validate_adjacency(),
+        Err(AdjacencyError::MismatchedId { key: TileId(2), tile_id: TileId(3) }));
+}
+
+// Does each preset have enough tiles for every player count, and can
+// the penguins still move once they are all placed?
+#[test]
+fn test_board_preset_is_playable() {
+    use crate::common::gamestate::{ GameState, MIN_PLAYERS_PER_GAME, MAX_PLAYERS_PER_GAME };
+
+    let presets = [BoardPreset::Sparse, BoardPreset::Standard, BoardPreset::Dense, BoardPreset::Holey];
+
+    for preset in presets.iter() {
+        let board = Board::preset(*preset);
+        assert_eq!(board, Board::preset(*preset));
+
+        for players in MIN_PLAYERS_PER_GAME ..= MAX_PLAYERS_PER_GAME {
+            let mut state = GameState::new(board.clone(), players);
+            state.place_all_zigzag();
+            assert!(state.can_any_player_move_penguin(), "{:?} with {} players", preset, players);
+        }
+    }
+
+    let holey = Board::preset(BoardPreset::Holey);
+    assert_eq!(holey.tiles.len(), 48);
+    assert!(holey.is_hole(BoardPosn::from((4, 0))));
+    assert!(holey.tiles.values().all(|tile| (1 ..= 4).contains(&tile.fish_count)));
+}
+
+// Do boards created from the same seed have the same fish on each tile,
+// with every tile holding between 1 and MAX_RANDOM_FISH fish?
+#[test]
+fn test_board_with_seed() {
+    let board = Board::with_seed(4, 5, 11);
+    assert_eq!(board.to_fish_grid(), Board::with_seed(4, 5, 11).to_fish_grid());
+    assert_ne!(board.to_fish_grid(), Board::with_seed(4, 5, 12).to_fish_grid());
+    assert_eq!(board.tile_count(), 20);
+    assert!(board.tiles.values().all(|tile| tile.fish_count >= 1 && tile.fish_count <= MAX_RANDOM_FISH));
+}
+
+// Are boards with no tiles or with too many rows or columns rejected
+// by the checked constructors?
+#[test]
+fn test_board_check_dimensions() {
+    assert_eq!(Board::check_dimensions(0, 0), Err(DimensionError::Empty));
+    assert_eq!(Board::check_dimensions(0, 3), Err(DimensionError::Empty));
+    assert_eq!(Board::check_dimensions(3, 0), Err(DimensionError::Empty));
+    assert_eq!(Board::check_dimensions(MAX_BOARD_DIMENSION + 1, 3), Err(DimensionError::TooLarge));
+    assert_eq!(Board::check_dimensions(3, u32::MAX), Err(DimensionError::TooLarge));
+    assert_eq!(Board::check_dimensions(1, 1), Ok(()));
+    assert_eq!(Board::check_dimensions(MAX_BOARD_DIMENSION, MAX_BOARD_DIMENSION), Ok(()));
+
+    assert_eq!(Board::checked_with_no_holes(0, 4, 1), Err(DimensionError::Empty));
+    assert_eq!(Board::checked_with_no_holes(3, 4, 1), Ok(Board::with_no_holes(3, 4, 1)));
+    assert_eq!(Board::checked_with_holes(u32::MAX, u32::MAX, vec![], 0), Err(DimensionError::TooLarge));
+    assert_eq!(Board::checked_with_holes(2, 2, vec![(0, 0).into()], 1),
+        Ok(Board::with_holes(2, 2, vec![(0, 0).into()], 1)));
+
+    assert_eq!(Board::checked_from_tiles(vec![]), Err(DimensionError::Empty));
+    assert_eq!(Board::checked_from_tiles(vec![vec![], vec![]]), Err(DimensionError::Empty));
+    assert_eq!(Board::checked_from_tiles(vec![vec![1]; MAX_BOARD_DIMENSION as usize + 1]), Err(DimensionError::TooLarge));
+    assert_eq!(Board::checked_from_tiles(vec![vec![1, 2], vec![3]]), Ok(Board::from_tiles(vec![vec![1, 2], vec![3]])));
+}
+
 // Can we use Board::with_holes to initialize tiles?
 // Do these tiles get arranged in the right order and
 // with the right amount of fish? Are the holes present?
@@ -255,6 +818,31 @@ fn test_board_with_holes() {
     assert_eq!(b.tiles[&TileId(4)].fish_count, 1);
 }
 
+// Does Board::from_fish_grid keep Some(0) tiles on the board
+// while turning None entries into holes?
+#[test]
+fn test_board_from_fish_grid() {
+    let b = Board::from_fish_grid(vec![
+        vec![Some(2), Some(0), None],
+        vec![Some(1), Some(1), Some(1)],
+    ]);
+    // IDs arrangement
+    // 0   2   -
+    //   1   3   5
+    assert_eq!(b.tiles.len(), 5);
+    assert_eq!(b.tiles[&TileId(0)].fish_count, 2);
+    assert_eq!(b.tiles[&TileId(2)].fish_count, 0);
+    assert_eq!(b.tiles.get(&TileId(4)), None); // hole
+
+    // The zero-fish tile is still linked to and reachable from its neighbors
+    assert_eq!(b.tiles[&TileId(3)].northwest, Some(TileId(2)));
+    assert_eq!(b.tiles[&TileId(3)].northeast, None); // hole
+
+    let reachable = b.tiles[&TileId(3)].all_reachable_tiles(&b, &std::collections::HashSet::new());
+    assert!(reachable.iter().any(|tile| tile.tile_id == TileId(2)));
+    assert!(reachable.iter().all(|tile| tile.tile_id != TileId(4)));
+}
+
 // Can we correctly compute a TileId from a board position?
 #[test]
 fn test_board_get_tile_id() {
@@ -281,6 +869,30 @@ fn test_board_get_tile_id() {
     assert_eq!(Board::compute_tile_id(4, 3, 1, 8), None);
 }
 
+// Are tile ids still correct at the largest dimension the checked constructors
+// allow, and do ids that cannot fit become None instead of wrapping around?
+#[test]
+fn test_board_tile_ids_at_max_dimension() {
+    let max = MAX_BOARD_DIMENSION;
+    let board = Board::checked_with_no_holes(max, max, 1).unwrap();
+    let last_id = TileId((max * max - 1) as usize);
+    assert_eq!(board.tiles.len(), (max * max) as usize);
+    assert_eq!(board.get_tile_id(max - 1, max - 1), Some(last_id));
+    assert_eq!(board.get_tile_position(last_id), BoardPosn { x: max - 1, y: max - 1 });
+    assert_eq!(board.get_tile_id(max, max - 1), None);
+    assert_eq!(Board::checked_with_no_holes(max + 1, max, 1), Err(DimensionError::TooLarge));
+
+    // Unchecked boards can be much larger, e.g. when deserialized
+    let huge = u32::MAX as i64;
+    assert_eq!(Board::compute_tile_id(huge, huge, huge - 1, huge - 1), None);
+    assert_eq!(Board::compute_tile_id(i64::MAX, i64::MAX, 2, 0), None);
+    assert_eq!(Board::compute_tile_id(huge, huge, 1, 2), Some(TileId(huge as usize + 2)));
+
+    let huge_board = Board { tiles: BTreeMap::new(), width: u32::MAX, height: u32::MAX };
+    assert_eq!(huge_board.get_tile(u32::MAX - 1, u32::MAX - 1), None);
+    assert_eq!(huge_board.get_tile_position(TileId(huge as usize + 2)), BoardPosn { x: 1, y: 2 });
+}
+
 // Can we correctly compute the position of a tile from its TileId?
 #[test]
 fn test_board_get_tile_position() {
@@ -296,6 +908,155 @@ fn test_board_get_tile_position() {
     assert_eq!(b.get_tile_position(TileId(5)), (2,1).into());
 }
 
+// Are the neighbors of a tile labelled with the direction they lie in?
+#[test]
+fn test_board_neighbors_with_direction() {
+    // 0     5    10
+    //    1     6    11
+    // 2     7    12
+    //    3     8    13
+    // 4     9    14
+    let mut b = Board::with_no_holes(5, 3, 1);
+    assert_eq!(b.neighbors_with_direction(TileId(7)), vec![
+        (Direction::Northeast, TileId(6)),
+        (Direction::Northwest, TileId(1)),
+        (Direction::North, TileId(5)),
+        (Direction::South, TileId(9)),
+        (Direction::Southeast, TileId(8)),
+        (Direction::Southwest, TileId(3)),
+    ]);
+
+    // Corners have fewer neighbors
+    assert_eq!(b.neighbors_with_direction(TileId(0)), vec![
+        (Direction::South, TileId(2)),
+        (Direction::Southeast, TileId(1)),
+    ]);
+
+    // Holes are not neighbors, and have no neighbors themselves
+    b.remove_tile(TileId(6));
+    assert_eq!(b.neighbors_with_direction(TileId(7)).len(), 5);
+    assert!(!b.neighbors_with_direction(TileId(7)).contains(&(Direction::Northeast, TileId(6))));
+    assert_eq!(b.neighbors_with_direction(TileId(6)), vec![]);
+}
+
+// Is each adjacency on the board listed exactly once, lower id first?
+#[test]
+fn test_board_edge_list() {
+    // This 2x3 board should look like:
+    // 0    2    4
+    //    1    3    5
+    let mut b = Board::with_no_holes(2, 3, 3);
+    let edges = vec![
+        (TileId(0), TileId(1)),
+        (TileId(1), TileId(2)),
+        (TileId(2), TileId(3)),
+        (TileId(3), TileId(4)),
+        (TileId(4), TileId(5)),
+    ];
+    assert_eq!(b.edge_list(), edges);
+
+    // Removing a tile removes its edges
+    b.remove_tile(TileId(3));
+    assert_eq!(b.edge_list(), vec![
+        (TileId(0), TileId(1)),
+        (TileId(1), TileId(2)),
+        (TileId(4), TileId(5)),
+    ]);
+}
+
+// Does every tile reachable in a straight line have the direction and path
+// that were walked to reach it?
+#[test]
+fn test_board_move_direction_and_path() {
+    // 0     7    14
+    //    1     8    15
+    // 2     9    16
+    //    3    10    17
+    // 4    11    18
+    //    5    12    19
+    // 6    13    20
+    let mut b = Board::with_no_holes(7, 3, 1);
+    let center = TileId(9);
+
+    for direction in Direction::iter() {
+        let mut walked = vec![center];
+        let mut current = b.tiles[&center].clone();
+
+        while let Some(neighbor_id) = current.get_neighbor_id(direction).copied() {
+            walked.push(neighbor_id);
+            let move_ = Move::new(center, neighbor_id);
+            assert_eq!(b.move_direction(move_), Some(direction));
+            assert_eq!(b.move_path(move_), walked);
+            current = b.tiles[&neighbor_id].clone();
+        }
+
+        assert!(walked.len() > 1, "no tiles {:?} of the center", direction);
+    }
+
+    assert_eq!(b.move_path(Move::new(TileId(9), TileId(14))), vec![TileId(9), TileId(8), TileId(14)]);
+    assert_eq!(b.move_direction(Move::new(TileId(9), TileId(14))), Some(Direction::Northeast));
+
+    // Tiles that are not in a straight line, or the same tile, have no direction or path
+    assert_eq!(b.move_direction(Move::new(TileId(9), TileId(20))), None);
+    assert_eq!(b.move_path(Move::new(TileId(9), TileId(20))), vec![]);
+    assert_eq!(b.move_direction(Move::new(TileId(9), TileId(9))), None);
+
+    // Holes block the line
+    b.remove_tile(TileId(8));
+    assert_eq!(b.move_direction(Move::new(TileId(9), TileId(14))), None);
+    assert_eq!(b.move_path(Move::new(TileId(9), TileId(14))), vec![]);
+}
+
+// Do we reject ids that are out of range or refer to holes?
+#[test]
+fn test_board_try_get_tile_position() {
+    // This 2x3 board should look like:
+    // 0    2    4
+    //    1    x    5
+    let mut b = Board::with_no_holes(2, 3, 3);
+    b.remove_tile(TileId(3));
+
+    // valid id
+    assert_eq!(b.try_get_tile_position(TileId(4)), Some((2,0).into()));
+
+    // out of range id, which get_tile_position would place in a nonexistent column
+    assert_eq!(b.get_tile_position(TileId(7)), (3,1).into());
+    assert_eq!(b.try_get_tile_position(TileId(7)), None);
+
+    // hole id
+    assert_eq!(b.try_get_tile_position(TileId(3)), None);
+}
+
+// Are holes reported at exactly the positions they were made,
+// with every position visited in row-major order?
+#[test]
+fn test_board_tiles_row_major_holes() {
+    // 0    -
+    //   1    4
+    // 2    -
+    let holes = vec![(1, 0).into(), (1, 2).into()];
+    let b = Board::with_holes(3, 2, holes, 4);
+
+    let tiles = b.tiles_row_major();
+    let positions: Vec<BoardPosn> = tiles.iter().map(|(pos, _)| *pos).collect();
+    assert_eq!(positions, vec![
+        (0, 0).into(), (1, 0).into(),
+        (0, 1).into(), (1, 1).into(),
+        (0, 2).into(), (1, 2).into(),
+    ]);
+
+    let tile_ids: Vec<Option<TileId>> = tiles.iter().map(|(_, tile)| tile.map(|tile| tile.tile_id)).collect();
+    assert_eq!(tile_ids, vec![Some(TileId(0)), None, Some(TileId(1)), Some(TileId(4)), Some(TileId(2)), None]);
+
+    for (pos, tile) in tiles {
+        assert_eq!(b.is_hole(pos), tile.is_none());
+    }
+
+    // out of bounds positions are not holes
+    assert!(!b.is_hole((2, 0).into()));
+    assert!(!b.is_hole((0, 3).into()));
+}
+
 // Can we remove a tile from a board?
 #[test]
 fn test_board_remove_tile() {
@@ -320,3 +1081,72 @@ fn test_board_remove_tile() {
     assert_eq!(tile_neighbor_se.northwest, None);
     assert_eq!(tile_neighbor_sw.northeast, None);
 }
+
+// Does toggling a tile twice return the board to its original state, including the
+// tile's fish?
+#[test]
+fn test_board_toggle_tile_twice() {
+    let original = Board::with_no_holes(3, 4, 3);
+    let mut b = original.clone();
+
+    assert!(!b.toggle_tile((1, 1).into(), 0));
+    assert_eq!(b.tiles.get(&TileId(4)), None);
+    assert_ne!(b, original);
+
+    assert!(b.toggle_tile((1, 1).into(), 3));
+    assert_eq!(b, original);
+    assert_eq!(b.to_fish_grid(), original.to_fish_grid());
+    assert_eq!(b.tiles[&TileId(4)].fish_count, 3);
+
+    // Out of bounds positions are never present
+    assert!(!b.toggle_tile((4, 0).into(), 3));
+    assert_eq!(b.to_fish_grid(), original.to_fish_grid());
+}
+
+// Does a restored tile link to its neighbors, and do they link back to it?
+#[test]
+fn test_board_toggle_tile_restores_neighbors() {
+    // 0    3    6    9
+    //   1    4    7    10
+    // 2    5    8    11
+    let original = Board::with_no_holes(3, 4, 1);
+    let mut b = original.clone();
+
+    // Make a hole at tile 3 (northwest of 4) before removing and restoring 4
+    b.toggle_tile((1, 0).into(), 1);
+    b.toggle_tile((1, 1).into(), 1);
+    assert!(b.toggle_tile((1, 1).into(), 1));
+
+    for direction in Direction::iter() {
+        let expected = original.tiles[&TileId(4)].get_neighbor_id(direction).filter(|id| id.0 != 3);
+        assert_eq!(b.tiles[&TileId(4)].get_neighbor_id(direction), expected);
+
+        if let Some(neighbor) = expected {
+            assert_eq!(b.tiles[neighbor].get_neighbor_id(direction.opposite()), Some(&TileId(4)));
+        }
+    }
+
+    assert_eq!(b.tiles[&TileId(4)].northwest, None);
+    assert_eq!(b.tiles[&TileId(4)].northeast, Some(TileId(6)));
+    assert_eq!(b.tiles[&TileId(6)].southwest, Some(TileId(4)));
+}
+
+// Is the tile with the most straight-line moves on an empty board found?
+#[test]
+fn test_board_most_connected_tile() {
+    // 0    x    10
+    //    1    6    x
+    // 2    7    12
+    //    3    8    13
+    // x    9    14
+    //
+    // Tile 7 reaches 8 tiles: 9 to the south, 6 and 10 to the northeast, 1 and 0
+    // to the northwest, 8 and 14 to the southeast, and 3 to the southwest. The
+    // hole above it blocks the north. Tiles 8 and 14 are next best with 7 each.
+    let b = Board::from_text("1 x 1\n 1 1 x\n1 1 1\n 1 1 1\nx 1 1\n").unwrap();
+    assert_eq!(b.most_connected_tile(), Some(TileId(7)));
+
+    // On a single row no tile can reach another, so all tie and the leftmost tile wins
+    assert_eq!(Board::with_no_holes(1, 3, 1).most_connected_tile(), Some(TileId(0)));
+    assert_eq!(Board::from_text("x x\n").unwrap().most_connected_tile(), None);
+}
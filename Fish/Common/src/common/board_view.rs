@@ -0,0 +1,110 @@
+//! The board_view module contains a lightweight, read-only view of a Board
+//! used to simulate removing tiles without cloning the Board's map of tiles.
+//!
+//! This is intended for lookahead searches which only need to know which tiles
+//! are reachable after some captures, rather than needing a whole new Board.
+use crate::common::board::Board;
+use crate::common::tile::{ Tile, TileId };
+use crate::common::direction::Direction;
+
+use std::collections::HashSet;
+
+/// A borrowed Board along with a set of tiles that have been "virtually"
+/// removed from it. The underlying Board is never modified; tiles in the
+/// removed set are instead treated as holes by every query on the view.
+#[derive(Clone, Debug)]
+pub struct BoardView<'a> {
+    board: &'a Board,
+    removed: HashSet<TileId>,
+}
+
+impl<'a> BoardView<'a> {
+    /// Creates a view of the given board with no tiles removed.
+    pub fn new(board: &'a Board) -> BoardView<'a> {
+        BoardView { board, removed: HashSet::new() }
+    }
+
+    /// Virtually removes the given tile from this view, as Board::remove_tile would.
+    /// Returns the amount of fish on the tile, or 0 if the tile was already a hole.
+    pub fn remove_tile(&mut self, tile_id: TileId) -> usize {
+        match self.get_tile(tile_id) {
+            Some(tile) => {
+                let fish_count = tile.get_fish_count();
+                self.removed.insert(tile_id);
+                fish_count
+            },
+            None => 0,
+        }
+    }
+
+    /// Retrieve a tile by its ID, or None if it is a hole in either
+    /// the underlying board or this view.
+    pub fn get_tile(&self, tile_id: TileId) -> Option<&'a Tile> {
+        if self.removed.contains(&tile_id) {
+            None
+        } else {
+            self.board.tiles.get(&tile_id)
+        }
+    }
+
+    /// Returns the ids of all tiles reachable via a straight line from the given tile,
+    /// in the same manner as Tile::all_reachable_tiles. The starting tile is not
+    /// considered reachable from itself, and nothing is reachable from a hole.
+    pub fn all_reachable_tiles(&self, tile_id: TileId, occupied_tiles: &HashSet<TileId>) -> Vec<TileId> {
+        let start = match self.get_tile(tile_id) {
+            Some(tile) => tile,
+            None => return vec![],
+        };
+
+        Direction::iter().flat_map(|direction| {
+            let mut reachable = vec![];
+            let mut current = start;
+
+            while let Some(neighbor) = current.get_neighbor_id(direction).and_then(|id| self.get_tile(*id)) {
+                if occupied_tiles.contains(&neighbor.tile_id) {
+                    break;
+                }
+                reachable.push(neighbor.tile_id);
+                current = neighbor;
+            }
+
+            reachable
+        }).collect()
+    }
+}
+
+// Does reachability on a view with virtually removed tiles match reachability
+// on a board where those same tiles were actually removed?
+#[test]
+fn test_board_view_matches_mutated_board() {
+    // 0     4     8    12    16
+    //    1     5     9    13    17
+    // 2     6    10    14    18
+    //    3     7    11    15    19
+    let board = Board::with_no_holes(4, 5, 2);
+    let captures = [TileId(5), TileId(10), TileId(17), TileId(2)];
+    let occupied_tiles: HashSet<_> = [TileId(9), TileId(0)].iter().copied().collect();
+
+    let mut view = BoardView::new(&board);
+    let mut mutated_board = board.clone();
+
+    for capture in captures.iter() {
+        assert_eq!(view.remove_tile(*capture), mutated_board.remove_tile(*capture));
+    }
+
+    // The underlying board is untouched
+    assert_eq!(board.tiles.len(), 20);
+    assert_eq!(view.remove_tile(TileId(5)), 0);
+
+    for id in 0 .. 20 {
+        let tile_id = TileId(id);
+        let mut expected: Vec<_> = mutated_board.tiles.get(&tile_id).map_or(vec![], |tile| {
+            tile.all_reachable_tiles(&mutated_board, &occupied_tiles).iter().map(|tile| tile.tile_id).collect()
+        });
+        let mut actual = view.all_reachable_tiles(tile_id, &occupied_tiles);
+
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected, "reachable tiles from tile {} differ", id);
+    }
+}
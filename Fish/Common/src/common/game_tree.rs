@@ -2,7 +2,9 @@
 //! during the game, in a lazily-evaluated tree structure.
 use crate::common::gamestate::GameState;
 use crate::common::action::Move;
-use std::collections::HashMap;
+use crate::server::message::serialize_game_tree;
+
+use std::collections::{ HashMap, HashSet };
 
 /// Represents an entire game of Fish, starting from the given GameState
 /// passed to GameTree::new.
@@ -110,12 +112,68 @@ impl GameTree {
         }
     }
 
+    /// Discards every evaluated subtree more than `depth` moves below this node,
+    /// turning them back into Unevaluated thunks built from their parent's state
+    /// and the move leading to them. This bounds the memory used by long-lived
+    /// trees while keeping the results of shallower evaluations.
+    ///
+    /// A depth of 0 makes all of this node's children Unevaluated.
+    pub fn prune_to_depth(&mut self, depth: usize) {
+        if let GameTree::Turn { state, valid_moves } = self {
+            for (move_, lazy_game) in valid_moves.iter_mut() {
+                if let LazyGameTree::Evaluated(game) = lazy_game {
+                    if depth == 0 {
+                        *lazy_game = LazyGameTree::from_move(move_, state);
+                    } else {
+                        game.prune_to_depth(depth - 1);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn is_game_over(&self) -> bool {
         match self {
             GameTree::Turn { .. } => false,
             GameTree::End(_) => true,
         }
     }
+
+    /// Returns the distinct states reachable from this node in exactly `plies` moves,
+    /// along with the final states of any games that end in fewer moves. States reached
+    /// through different orders of moves are only included once, as identified by
+    /// GameState::canonical_key. The states are returned in no particular order.
+    ///
+    /// This evaluates the tree to the given depth, so it grows exponentially with plies.
+    pub fn reachable_states(&mut self, plies: usize) -> Vec<GameState> {
+        let mut states = vec![];
+        self.collect_states(plies, &mut states);
+
+        let is_new: Vec<bool> = {
+            let mut seen = HashSet::new();
+            states.iter().map(|state| seen.insert(state.canonical_key())).collect()
+        };
+
+        states.into_iter().zip(is_new)
+            .filter_map(|(state, is_new)| if is_new { Some(state) } else { None })
+            .collect()
+    }
+
+    /// Pushes the state of every node `plies` moves below this one, or of the
+    /// final node of a branch if the game ends sooner, onto the given Vec.
+    fn collect_states(&mut self, plies: usize, states: &mut Vec<GameState>) {
+        if plies == 0 || self.is_game_over() {
+            states.push(self.get_state().clone());
+        } else {
+            self.map(|game| game.collect_states(plies - 1, states));
+        }
+    }
+
+    /// Exports this tree to json for external analysis, evaluating it only as far as
+    /// `depth` moves below this node. See server::message::serialize_game_tree for the format.
+    pub fn to_json(&mut self, depth: usize) -> serde_json::Value {
+        serialize_game_tree(self, depth)
+    }
 }
 
 /// A LazyGameTree is either an already evaluted GameTree or
@@ -125,7 +183,7 @@ impl GameTree {
 /// us from allocating an exponential amount of memory for every
 /// possible GameState. 
 pub enum LazyGameTree {
-    Evaluated(GameTree),
+    Evaluated(Box<GameTree>),
     Unevaluated(Box<dyn FnMut() -> GameTree>),
 }
 
@@ -137,7 +195,7 @@ impl LazyGameTree {
             LazyGameTree::Evaluated(game) => game,
             LazyGameTree::Unevaluated(thunk) => {
                 let game = thunk();
-                *self = LazyGameTree::Evaluated(game);
+                *self = LazyGameTree::Evaluated(Box::new(game));
                 self.get_evaluated()
             },
         }
@@ -145,7 +203,7 @@ impl LazyGameTree {
 
     pub fn evaluate(self) -> GameTree {
         match self {
-            LazyGameTree::Evaluated(game) => game,
+            LazyGameTree::Evaluated(game) => *game,
             LazyGameTree::Unevaluated(mut thunk) => thunk(),
         }
     }
@@ -178,15 +236,12 @@ impl std::fmt::Debug for LazyGameTree {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::server::strategy::tests::take_zigzag_placement;
 
     // Starts a game with a 3 row, 5 column board and all penguins placed.
     fn start_game() -> GameTree {
         let mut state = GameState::with_default_board(5, 3, 2);
 
-        while !state.all_penguins_are_placed() {
-            take_zigzag_placement(&mut state);
-        }
+        state.place_all_zigzag();
 
         GameTree::new(&state)
     }
@@ -238,6 +293,82 @@ mod tests {
         }
     }
 
+    /// Returns true if every child of the given game is Unevaluated
+    fn all_children_unevaluated(game: &GameTree) -> bool {
+        match game {
+            GameTree::Turn { valid_moves, .. } => valid_moves.values().all(|lazy_game| {
+                match lazy_game {
+                    LazyGameTree::Evaluated(_) => false,
+                    LazyGameTree::Unevaluated(_) => true,
+                }
+            }),
+            GameTree::End(_) => true,
+        }
+    }
+
+    #[test]
+    fn test_prune_to_depth() {
+        let mut game = start_game();
+
+        // Evaluate two levels of the tree, recording the states of the second level
+        let states_before = game.map(|child| child.map(|grandchild| grandchild.get_state().clone()));
+
+        game.prune_to_depth(1);
+
+        // Children are still evaluated, but grandchildren are not
+        assert!(!all_children_unevaluated(&game));
+        game.map(|child| assert!(all_children_unevaluated(child)));
+
+        // Re-evaluating the grandchildren produces the same states
+        let states_after = game.map(|child| child.map(|grandchild| grandchild.get_state().clone()));
+        assert_eq!(states_before, states_after);
+
+        game.prune_to_depth(0);
+        assert!(all_children_unevaluated(&game));
+    }
+
+    /// Returns the number of distinct sequences of moves of the given length from the given
+    /// game, counting sequences cut short by the game ending
+    fn count_paths(game: &mut GameTree, plies: usize) -> usize {
+        if plies == 0 || game.is_game_over() {
+            1
+        } else {
+            game.map(|child| count_paths(child, plies - 1)).values().sum()
+        }
+    }
+
+    #[test]
+    fn test_reachable_states() {
+        let mut game = start_game();
+        let valid_move_count = game.get_state().get_valid_moves().len();
+
+        let states = game.reachable_states(0);
+        assert_eq!(states.len(), 1);
+        assert!(states[0] == *game.get_state());
+
+        // Every move leads to a different state
+        assert_eq!(game.reachable_states(1).len(), valid_move_count);
+        assert_eq!(count_paths(&mut game, 1), valid_move_count);
+
+        // Within 3 plies the first player can move two different penguins in either
+        // order, reaching the same state through more than one sequence of moves
+        let states = game.reachable_states(3);
+        assert!(states.len() < count_paths(&mut game, 3));
+
+        let keys: HashSet<_> = states.iter().map(|state| state.canonical_key()).collect();
+        assert_eq!(keys.len(), states.len());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut game = start_game();
+        let valid_move_count = game.get_state().get_valid_moves().len();
+
+        let json = game.to_json(1);
+        assert_eq!(json["children"].as_array().unwrap().len(), valid_move_count);
+        assert_eq!(json, serialize_game_tree(&mut game, 1));
+    }
+
     #[test]
     fn test_get_game_after_move() {
         let mut initial_game = start_game();
@@ -3,7 +3,7 @@
 use crate::common::gamestate::GameState;
 use crate::common::game_tree::GameTree;
 use crate::common::player::PlayerId;
-use crate::common::action::Move;
+use crate::common::action::{ Action, Move, ActionKind };
 
 /// Represents the step of the Fish game protocol the game is on currently.
 /// This struct is necessary because it allows us to represent a Game
@@ -30,6 +30,19 @@ pub enum GamePhase {
     Done(GameState),
 }
 
+/// What happened during a single call to GamePhase::step
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The current player's action was valid and has been made
+    Applied(Action),
+
+    /// The given player gave no action or an invalid one, and was removed from the game
+    Kicked(PlayerId),
+
+    /// The game is not in progress, so no action was requested
+    GameOver,
+}
+
 impl GamePhase {
     /// Is this GamePhase Done?
     pub fn is_game_over(&self) -> bool {
@@ -72,6 +85,17 @@ impl GamePhase {
         }
     }
 
+    /// Returns which kind of action the current player is expected to take,
+    /// or None if no actions should be taken (the game is Starting or Done).
+    pub fn expected_action(&self) -> Option<ActionKind> {
+        match self {
+            GamePhase::Starting => None,
+            GamePhase::PlacingPenguins(_) => Some(ActionKind::Placement),
+            GamePhase::MovingPenguins(_) => Some(ActionKind::Move),
+            GamePhase::Done(_) => None,
+        }
+    }
+
     /// Returns whose turn it currently is for the current GamePhase.
     /// This will panic if the game has not started yet.
     pub fn current_turn(&self) -> PlayerId {
@@ -111,6 +135,72 @@ impl GamePhase {
         };
     }
 
+    /// Advances this game by a single turn: the given function is called with the current
+    /// state to get the current player's action, which is then made if it is valid. If the
+    /// function returns None, or an invalid action or one of the wrong kind for this phase,
+    /// the current player is removed from the game instead. Afterward this phase moves on
+    /// to the next phase if needed, see update_phase_if_needed.
+    ///
+    /// The referee plays each turn through this, adding clients, networking, and timeouts
+    /// around it. Without those, games can also be driven directly by scripts, GUIs, or tests.
+    /// Starting and Done phases are left unchanged and the function is not called.
+    pub fn step<F>(&mut self, get_action: F) -> StepOutcome
+        where F: FnOnce(&GameState) -> Option<Action>
+    {
+        if self.expected_action().is_none() {
+            return StepOutcome::GameOver;
+        }
+
+        let player = self.current_turn();
+        let applied = get_action(self.get_state()).and_then(|action| {
+            match (action, &mut *self) {
+                (Action::PlacePenguin(placement), GamePhase::PlacingPenguins(state)) => {
+                    state.place_avatar_for_current_player(placement)
+                },
+                (Action::MovePenguin(move_), GamePhase::MovingPenguins(_)) => self.try_do_move(move_),
+                _ => None,
+            }.map(|_| action)
+        });
+
+        let outcome = match applied {
+            Some(action) => StepOutcome::Applied(action),
+            None => {
+                let mut state = self.get_state().clone();
+                state.remove_player(player);
+                if state.players.is_empty() {
+                    *self = GamePhase::Done(state);
+                } else {
+                    self.update_from_gamestate(state);
+                }
+                StepOutcome::Kicked(player)
+            },
+        };
+
+        self.update_phase_if_needed();
+        outcome
+    }
+
+    /// Moves on from the PlacingPenguins phase once every penguin is placed, and to
+    /// the Done phase once the game ends. If the board fills up before every penguin
+    /// is placed then no penguin can move either, so the game is ended immediately
+    /// rather than waiting on a placement that cannot exist.
+    pub fn update_phase_if_needed(&mut self) {
+        if let GamePhase::PlacingPenguins(state) = self {
+            if state.all_penguins_are_placed() {
+                *self = GamePhase::MovingPenguins(GameTree::new(state));
+            } else if !state.can_place() {
+                state.end_game();
+                *self = GamePhase::Done(state.clone());
+            }
+        }
+
+        // Test if MovingPenguins is finished even after testing the above in case we
+        // start a game after placing penguins where immediately no penguin can move.
+        if let GamePhase::MovingPenguins(GameTree::End(state)) = self {
+            *self = GamePhase::Done(state.clone());
+        }
+    }
+
     /// "Perform" a move, mutating the current game phase to the game phase after
     /// the given move. If the given move is not valid, no mutation will be done
     /// and None will be returned instead.
@@ -140,18 +230,78 @@ impl GamePhase {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::server::strategy::tests::take_zigzag_placement;
+    use crate::common::board::Board;
+    use crate::common::tile::TileId;
+    use crate::server::strategy::find_zigzag_placement;
+
+    #[test]
+    fn test_expected_action() {
+        assert_eq!(GamePhase::Starting.expected_action(), None);
 
-    fn place_penguins(state: &mut GameState) {
-        while !state.all_penguins_are_placed() {
-            take_zigzag_placement(state);
+        let mut state = GameState::with_default_board(3, 4, 3);
+        assert_eq!(GamePhase::PlacingPenguins(state.clone()).expected_action(), Some(ActionKind::Placement));
+
+        state.place_all_zigzag();
+        assert_eq!(GamePhase::MovingPenguins(GameTree::new(&state)).expected_action(), Some(ActionKind::Move));
+
+        assert_eq!(GamePhase::Done(state).expected_action(), None);
+    }
+
+    /// Drives a full game through GamePhase::step, with player 1 making an illegal
+    /// move on their first turn and every other action chosen from the valid ones.
+    #[test]
+    fn test_step_full_game() {
+        let state = GameState::with_default_board(4, 4, 3);
+        let mut phase = GamePhase::PlacingPenguins(state);
+        let mut outcomes = vec![];
+
+        loop {
+            let outcome = phase.step(|state| {
+                if !state.all_penguins_are_placed() {
+                    Some(Action::PlacePenguin(find_zigzag_placement(state)))
+                } else if state.current_turn == PlayerId(1) {
+                    Some(Action::MovePenguin(Move::new(TileId(0), TileId(0))))
+                } else {
+                    let mut moves = state.get_valid_moves();
+                    moves.sort();
+                    Some(Action::MovePenguin(moves[0]))
+                }
+            });
+
+            if outcome == StepOutcome::GameOver {
+                break;
+            }
+            outcomes.push(outcome);
         }
+
+        // Each of the 3 players places 3 penguins before any moves are made
+        assert!(outcomes[..9].iter().all(|outcome| matches!(outcome, StepOutcome::Applied(Action::PlacePenguin(_)))));
+        assert!(matches!(outcomes[9], StepOutcome::Applied(Action::MovePenguin(_))));
+        assert_eq!(outcomes[10], StepOutcome::Kicked(PlayerId(1)));
+        assert!(outcomes[11..].iter().all(|outcome| matches!(outcome, StepOutcome::Applied(Action::MovePenguin(_)))));
+
+        assert!(phase.is_game_over());
+        assert!(!phase.get_state().has_player(PlayerId(1)));
+        assert!(phase.get_state().winning_players.is_some());
+        assert_eq!(phase.step(|_| unreachable!("No action should be requested once the game is over")), StepOutcome::GameOver);
+    }
+
+    #[test]
+    fn test_step_wrong_action_kind() {
+        let mut phase = GamePhase::PlacingPenguins(GameState::with_default_board(4, 4, 2));
+        let move_ = Action::MovePenguin(Move::new(TileId(0), TileId(1)));
+        assert_eq!(phase.step(|_| Some(move_)), StepOutcome::Kicked(PlayerId(0)));
+        assert_eq!(phase.step(|_| None), StepOutcome::Kicked(PlayerId(1)));
+
+        // With every player removed the game is over
+        assert!(phase.is_game_over());
+        assert_eq!(GamePhase::Starting.step(|_| None), StepOutcome::GameOver);
     }
 
     #[test]
     fn test_update_from_gamestate() {
         let mut state1 = GameState::with_default_board(3, 4, 3);
-        place_penguins(&mut state1);
+        state1.place_all_zigzag();
 
         // create expected state after first move
         let mut state1_after_move = state1.clone();
@@ -166,4 +316,43 @@ mod tests {
 
         assert_eq!(actual_phase.get_state(), expected_phase.get_state());
     }
+
+    /// When a player whose penguin blocks a lane is removed mid-game, the tiles it
+    /// occupied should be free for the remaining players to move through.
+    #[test]
+    fn test_update_from_gamestate_after_remove_player() {
+        // A single column of tiles. Tiles 0, 2, 4, and 6 form a north-south lane,
+        // and tile 1 is southeast of tile 0.
+        let board = Board::with_no_holes(7, 1, 1);
+        let mut state = GameState::new(board, 3);
+
+        // Give each player a single penguin: player 1 blocks player 0's lane
+        for (player_id, tile_id) in [(0, 0), (1, 2), (2, 5)].iter() {
+            let player = state.players.get_mut(&PlayerId(*player_id)).unwrap();
+            player.penguins.truncate(1);
+            player.penguins[0].tile_id = Some(TileId(*tile_id));
+        }
+
+        let mut phase = GamePhase::MovingPenguins(GameTree::new(&state));
+        assert_eq!(phase.current_turn(), PlayerId(0));
+        assert_eq!(phase.get_state().get_valid_moves(), vec![Move::new(TileId(0), TileId(1))]);
+
+        // As the referee does when kicking a player
+        let mut state = phase.get_state().clone();
+        state.remove_player(PlayerId(1));
+        phase.update_from_gamestate(state);
+
+        let mut moves = phase.get_state().get_valid_moves();
+        moves.sort();
+        assert_eq!(moves, vec![
+            Move::new(TileId(0), TileId(1)),
+            Move::new(TileId(0), TileId(2)),
+            Move::new(TileId(0), TileId(4)),
+            Move::new(TileId(0), TileId(6)),
+        ]);
+
+        // Moving through the freed tile is now valid in the game tree
+        assert!(phase.try_do_move(Move::new(TileId(0), TileId(4))).is_some());
+        assert_eq!(phase.get_state().get_occupied_tiles().len(), 2);
+    }
 }
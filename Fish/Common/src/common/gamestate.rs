@@ -7,9 +7,10 @@
 use crate::common::board::Board;
 use crate::common::tile::{ TileId, Tile };
 use crate::common::player::{ Player, PlayerId, PlayerColor };
-use crate::common::penguin::Penguin;
-use crate::common::action::{ Move, Placement };
+use crate::common::penguin::{ Penguin, PenguinId };
+use crate::common::action::{ Action, Move, MoveError, Placement, PlacementError };
 use crate::common::boardposn::BoardPosn;
+use crate::common::replay::PenguinTrails;
 use crate::common::util;
 
 use std::collections::HashSet;
@@ -17,6 +18,7 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::hash::{ Hash, Hasher };
 
 use serde::{ Serialize, Deserialize };
 
@@ -34,6 +36,19 @@ pub const PENGUIN_FACTOR: usize = 6;
 /// callback and maintain a copy to overwrite with server updates as well.
 pub type SharedGameState = Rc<RefCell<GameState>>;
 
+/// What happens to the tile a penguin leaves when it moves.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TileRule {
+    /// The standard rule: the tile is removed and the player collects all of its fish
+    #[default]
+    Remove,
+
+    /// A house variant: the player collects a single fish from the tile, which is
+    /// only removed once it has no fish left. See GameState::deplete_tile.
+    Deplete,
+}
+
+
 /// The GameState contains the entirety of the current state
 /// of the game. It is meant to be serialized into json from the server
 /// and sent to each client to deserialize to receive the updated game
@@ -55,13 +70,56 @@ pub type SharedGameState = Rc<RefCell<GameState>>;
 /// - The GameState's current_turn player should never be stuck, unless
 ///   the game is over, i.e. current_player should always have moves.
 ///   Players' turns will be skipped in turn_order if they cannot move anymore.
-#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// - The tile_rule decides whether a tile a penguin moves off of is removed
+///   or merely loses a fish, and defaults to TileRule::Remove.
+/// - The turn_number counts the placements and moves made so far. It is ignored
+///   when comparing or hashing GameStates so that the same position reached
+///   after a different number of turns is still considered the same state.
+/// - The captures record the position and fish of every tile each player has
+///   taken fish from, in the order they were taken. Like turn_number these are
+///   ignored when comparing or hashing GameStates, and are not serialized.
+/// - The trails record every tile each penguin has occupied, in order, see
+///   PenguinTrails. Like the captures these are ignored when comparing or hashing
+///   GameStates, and are not serialized.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameState {
     pub board: Board,
     pub players: BTreeMap<PlayerId, Player>,
     pub turn_order: Vec<PlayerId>, // INVARIANT: turn_order never changes for a given game, unless a player is kicked
     pub current_turn: PlayerId,
     pub winning_players: Option<Vec<PlayerId>>, // will be None until the game ends
+    #[serde(default)]
+    pub tile_rule: TileRule,
+    #[serde(default)]
+    turn_number: usize,
+    #[serde(skip)]
+    captures: BTreeMap<PlayerId, Vec<(BoardPosn, usize)>>,
+    #[serde(skip)]
+    trails: PenguinTrails,
+}
+
+impl PartialEq for GameState {
+    fn eq(&self, other: &GameState) -> bool {
+        self.board == other.board
+            && self.players == other.players
+            && self.turn_order == other.turn_order
+            && self.current_turn == other.current_turn
+            && self.winning_players == other.winning_players
+            && self.tile_rule == other.tile_rule
+    }
+}
+
+impl Eq for GameState {}
+
+impl Hash for GameState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.board.hash(state);
+        self.players.hash(state);
+        self.turn_order.hash(state);
+        self.current_turn.hash(state);
+        self.winning_players.hash(state);
+        self.tile_rule.hash(state);
+    }
 }
 
 impl fmt::Debug for GameState {
@@ -140,6 +198,10 @@ impl GameState {
             turn_order,
             current_turn,
             winning_players: None,
+            tile_rule: TileRule::default(),
+            turn_number: 0,
+            captures: BTreeMap::new(),
+            trails: PenguinTrails::new(),
         }
     }
 
@@ -156,6 +218,8 @@ impl GameState {
     /// 1. Placement on an invalid position (either out of bounds or a hole)
     /// 2. Placement when the players' avatars are already placed
     /// 3. Placement of a penguin that doesn't belong to the current player
+    ///
+    /// See validate_placement_for_player for the reason a given placement is invalid.
     pub fn place_avatar_for_player(&mut self, player: PlayerId, tile: TileId) -> Option<()> {
         self.place_avatar_without_changing_turn(player, tile)?;
         self.advance_turn();
@@ -164,14 +228,38 @@ impl GameState {
 
     /// Place a player's avatar but don't change whose turn it is.
     /// This is useful to more easily place avatars in bulk during testing.
+    ///
+    /// See validate_placement_for_player for the reason a given placement is invalid.
     pub fn place_avatar_without_changing_turn(&mut self, player: PlayerId, tile: TileId) -> Option<()> {
-        let occupied_tiles = self.get_occupied_tiles();
+        self.validate_placement_for_player(player, Placement::new(tile)).ok()?;
+
+        let player = self.players.get_mut(&player)?; 
+        player.place_penguin(tile, &self.board)?;
+        self.trails.record(Action::PlacePenguin(Placement::new(tile)));
+        self.turn_number += 1;
+        Some(())
+    }
+
+    /// Checks whether the player whose turn it currently is can make the given placement,
+    /// without making it. Returns the reason the placement is invalid if it cannot be made.
+    pub fn validate_placement(&self, placement: Placement) -> Result<(), PlacementError> {
+        self.validate_placement_for_player(self.current_turn, placement)
+    }
+
+    /// Checks whether the given player can make the given placement, without making it.
+    /// Returns the reason the placement is invalid if it cannot be made.
+    pub fn validate_placement_for_player(&self, player: PlayerId, placement: Placement) -> Result<(), PlacementError> {
+        let player = self.players.get(&player).ok_or(PlacementError::UnknownPlayer)?;
+        if !player.has_unplaced_penguins() {
+            return Err(PlacementError::NoUnplacedPenguin);
+        }
 
-        if occupied_tiles.contains(&tile) {
-            None
+        self.get_tile(placement.tile_id).ok_or(PlacementError::NoTile)?;
+
+        if self.get_occupied_tiles().contains(&placement.tile_id) {
+            Err(PlacementError::Occupied)
         } else {
-            let player = self.players.get_mut(&player)?; 
-            player.place_penguin(tile, &self.board)
+            Ok(())
         }
     }
 
@@ -188,7 +276,7 @@ impl GameState {
     }
 
     /// Moves a placed avatar from one position to another on the board,
-    /// removes the tile that penguin was on, and advances the turn.
+    /// removes (or depletes, see TileRule) the tile that penguin was on, and advances the turn.
     /// Returns Some(()) on success, or None if the player makes an invalid move.
     /// An invalid move is one of:
     /// 1. Move to an invalid position (either out of bounds or hole)
@@ -196,14 +284,78 @@ impl GameState {
     /// 3. Move to a tile that is not accessible within a straight line
     ///    of the current tile, with no holes in between.
     /// 4. Move of a penguin that doesn't belong to the player
+    /// 5. Move from a tile to itself
+    ///
+    /// See validate_move for the reason a given move is invalid.
     pub fn move_avatar_for_player_without_changing_turn(&mut self, player: PlayerId, penguin_start_tile: TileId, destination: TileId) -> Option<()> {
         let occupied = &self.get_occupied_tiles();
-        let player = self.players.get_mut(&player)?;
-        player.move_penguin(penguin_start_tile, destination, &self.board, occupied)?;
-        player.score += self.board.remove_tile(penguin_start_tile);
+        self.validate_move_with_occupied_tiles(player, Move::new(penguin_start_tile, destination), occupied).ok()?;
+        self.players.get_mut(&player)?.move_penguin(penguin_start_tile, destination, &self.board, occupied)?;
+
+        let start_position = self.board.get_tile_position(penguin_start_tile);
+        let fish = match self.tile_rule {
+            TileRule::Remove => self.board.remove_tile(penguin_start_tile),
+            TileRule::Deplete => self.deplete_tile(penguin_start_tile),
+        };
+        self.players.get_mut(&player)?.score += fish;
+        self.captures.entry(player).or_default().push((start_position, fish));
+        self.trails.record(Action::MovePenguin(Move::new(penguin_start_tile, destination)));
+        self.turn_number += 1;
         Some(())
     }
 
+    /// Takes a single fish from the given tile, removing the tile from the board
+    /// once it has no fish left. Tiles with fish remaining stay on the board so
+    /// reachability only changes when a tile is actually removed.
+    /// Returns the amount of fish taken, 1, or 0 if there is no such tile.
+    pub fn deplete_tile(&mut self, tile_id: TileId) -> usize {
+        match self.board.tiles.get_mut(&tile_id) {
+            Some(tile) if tile.fish_count > 1 => {
+                tile.fish_count -= 1;
+                1
+            },
+            Some(_) => self.board.remove_tile(tile_id),
+            None => 0,
+        }
+    }
+
+    /// Checks whether the given player can make the given move, without making it.
+    /// Returns the reason the move is invalid if it cannot be made.
+    ///
+    /// Moves from a tile to itself are rejected early with MoveError::NoMovement
+    /// rather than relying on a tile never being reachable from itself. Likewise
+    /// moves onto a penguin are rejected with MoveError::DestinationOccupied rather
+    /// than relying on occupied tiles never being reachable.
+    pub fn validate_move(&self, player: PlayerId, move_: Move) -> Result<(), MoveError> {
+        self.validate_move_with_occupied_tiles(player, move_, &self.get_occupied_tiles())
+    }
+
+    /// Checks the given move as in validate_move, given the tiles every penguin
+    /// is currently on so callers which already have them need not find them again.
+    fn validate_move_with_occupied_tiles(&self, player: PlayerId, move_: Move,
+        occupied_tiles: &HashSet<TileId>) -> Result<(), MoveError>
+    {
+        if move_.from == move_.to {
+            return Err(MoveError::NoMovement);
+        }
+
+        let player = self.players.get(&player).ok_or(MoveError::UnknownPlayer)?;
+        player.find_penguin(move_.from).ok_or(MoveError::NoPenguin)?;
+
+        let from_tile = self.get_tile(move_.from).ok_or(MoveError::NoPenguin)?;
+        let to_tile = self.get_tile(move_.to).ok_or(MoveError::Unreachable)?;
+
+        if occupied_tiles.contains(&move_.to) {
+            return Err(MoveError::DestinationOccupied);
+        }
+
+        if from_tile.can_reach(&self.board, to_tile, occupied_tiles) {
+            Ok(())
+        } else {
+            Err(MoveError::Unreachable)
+        }
+    }
+
     /// Helper function which moves an avatar for the player whose turn it currently is.
     pub fn move_avatar_for_current_player(&mut self, move_: Move) -> Option<()> {
         self.move_avatar_for_player_without_changing_turn(self.current_turn, move_.from, move_.to)?;
@@ -211,6 +363,16 @@ impl GameState {
         Some(())
     }
 
+    /// Applies the given Action for the player whose turn it currently is, by
+    /// dispatching to place_avatar_for_current_player or move_avatar_for_current_player.
+    /// Returns Some(()) on success, or None if the placement or move is invalid.
+    pub fn apply_action(&mut self, action: Action) -> Option<()> {
+        match action {
+            Action::PlacePenguin(placement) => self.place_avatar_for_current_player(placement),
+            Action::MovePenguin(move_) => self.move_avatar_for_current_player(move_),
+        }
+    }
+
     /// Retrieve a tile by its ID. Will return None if the id
     /// does not reference any existing tile. This can happen
     /// if the tile was removed and has become a hole in the board.
@@ -231,6 +393,14 @@ impl GameState {
         })
     }
 
+    /// Returns the id of the player whose penguin is on the given tile,
+    /// or None if there is no penguin on that tile
+    pub fn owner_of_tile(&self, tile_id: TileId) -> Option<PlayerId> {
+        self.players.iter()
+            .find(|(_, player)| player.find_penguin(tile_id).is_some())
+            .map(|(player_id, _)| *player_id)
+    }
+
     /// Returns true if any player has a penguin they can move,
     /// false if not (and the game is thus over)
     pub fn can_any_player_move_penguin(&self) -> bool {
@@ -238,12 +408,86 @@ impl GameState {
         self.players.iter().any(|(_, player)| player.can_move_a_penguin(&self.board, &occupied_tiles))
     }
 
+    /// Returns true if the game is truly stuck: every penguin has been placed and
+    /// none of them can move. Since tiles are never added back to the board during
+    /// a game, no penguin will ever be able to move again once this is true.
+    ///
+    /// Penguins still waiting to be placed may yet be placed somewhere they can move
+    /// from, so this is always false during the placement phase.
+    pub fn no_player_can_ever_move(&self) -> bool {
+        self.all_penguins_are_placed() && !self.can_any_player_move_penguin()
+    }
+
     /// Returns true if the given player can move a penguin
     pub fn can_player_move(&self, player: PlayerId) -> bool {
         self.players.get(&player).map_or(false, |player|
             player.can_move_a_penguin(&self.board, &self.get_occupied_tiles()))
     }
 
+    /// Returns a key identifying this state by only the fields relevant to how the game
+    /// plays out from here: the board, each player's penguin positions and score, and
+    /// whose turn it is. Fields describing how the state was reached, such as turn_number,
+    /// are ignored, as is the order of each player's penguins. Searches that cache results
+    /// per state should key on this rather than on the whole GameState.
+    pub fn canonical_key(&self) -> impl Hash + Eq + '_ {
+        let players: Vec<_> = self.players.iter().map(|(player_id, player)| {
+            let mut positions: Vec<_> = player.penguins.iter().map(|penguin| penguin.tile_id).collect();
+            positions.sort();
+            (*player_id, positions, player.score)
+        }).collect();
+
+        (&self.board, players, self.current_turn)
+    }
+
+    /// Formats this GameState like its Debug output, but annotates each tile and penguin
+    /// with its (col, row) BoardPosn. Tiles are written as "id(x,y)", followed by the
+    /// owner of the penguin on them if any, and holes as "x(x,y)". Useful when debugging
+    /// anything that depends on positions rather than tile ids, like strategy tie-breaks.
+    pub fn debug_with_coords(&self) -> String {
+        let cells: Vec<Vec<String>> = util::make_n(self.board.height as usize, |y| {
+            util::make_n(self.board.width as usize, |x| {
+                let (x, y) = (x as u32, y as u32);
+                match self.board.get_tile_id(x, y).filter(|id| self.board.tiles.contains_key(id)) {
+                    Some(id) => match self.owner_of_tile(id) {
+                        Some(owner) => format!("{}({},{}) P{}", id.0, x, y, owner.0),
+                        None => format!("{}({},{})", id.0, x, y),
+                    },
+                    None => format!("x({},{})", x, y),
+                }
+            })
+        });
+
+        let cell_width = cells.iter().flatten().map(|cell| cell.len()).max().unwrap_or(0) + 2;
+        let mut string = String::new();
+
+        for (y, row) in cells.iter().enumerate() {
+            let indent = if y % 2 == 1 { cell_width / 2 } else { 0 };
+            let row_string: String = row.iter().map(|cell| format!("{:width$}", cell, width = cell_width)).collect();
+            string.push_str(format!("{}{}", " ".repeat(indent), row_string).trim_end());
+            string.push('\n');
+        }
+        string.push('\n');
+
+        for (player_id, player) in self.players.iter() {
+            let current_player_str = if self.current_turn == *player_id { " <- current turn" } else { "" };
+
+            let penguins = util::map_slice(&player.penguins, |penguin| {
+                match penguin.tile_id {
+                    Some(id) => {
+                        let posn = self.board.get_tile_position(id);
+                        format!("{}({},{})", id.0, posn.x, posn.y)
+                    },
+                    None => "unplaced".to_string(),
+                }
+            }).join(", ");
+
+            string.push_str(&format!("Player {} - {:?} - score: {} - penguins: [{}]{}\n",
+                player_id.0, player.color, player.score, penguins, current_player_str));
+        }
+
+        string
+    }
+
     /// Returns the set of tiles on this gamestate's board which have a penguin on them
     pub fn get_occupied_tiles(&self) -> HashSet<TileId> {
         self.players.iter()
@@ -268,6 +512,62 @@ impl GameState {
         }).collect()
     }
 
+    /// Returns the number of moves each player could make if it were their turn, with
+    /// every penguin on the board blocking the way. Unlike get_valid_moves this covers
+    /// every player rather than only the current one, which is useful for judging how
+    /// balanced a position is. Penguins that have yet to be placed have no moves.
+    pub fn mobility_per_player(&self) -> BTreeMap<PlayerId, usize> {
+        let occupied_tiles = self.get_occupied_tiles();
+
+        self.players.iter().map(|(player_id, player)| {
+            let moves = player.penguins.iter()
+                .filter_map(|penguin| self.get_tile(penguin.tile_id?))
+                .map(|tile| tile.all_reachable_tiles(&self.board, &occupied_tiles).len())
+                .sum();
+            (*player_id, moves)
+        }).collect()
+    }
+
+    /// Returns an upper bound on the score the given player can still gain this game,
+    /// ignoring whatever their opponents do: the fish on every tile the player's penguins
+    /// are on or could reach through any number of moves. Under TileRule::Remove, tiles
+    /// are only ever removed and a penguin never leaves a tile without removing it, so
+    /// every penguin on the board blocks the way for good. This makes the bound admissible
+    /// for pruning searches such as alpha-beta. Penguins that have yet to be placed are
+    /// not counted, and players who are not in the game have a ceiling of 0.
+    ///
+    /// Returns None under TileRule::Deplete, where penguins can return to tiles they left
+    /// and collect from them again, so no such bound holds.
+    pub fn score_ceiling(&self, player: PlayerId) -> Option<usize> {
+        if self.tile_rule != TileRule::Remove {
+            return None;
+        }
+
+        let occupied_tiles = self.get_occupied_tiles();
+        let mut reachable: HashSet<TileId> = self.players.get(&player)
+            .map(|player| player.penguins.iter().filter_map(|penguin| penguin.tile_id).collect())
+            .unwrap_or_default();
+
+        let mut frontier: Vec<TileId> = reachable.iter().copied().collect();
+        while let Some(tile_id) = frontier.pop() {
+            let tile = match self.get_tile(tile_id) {
+                Some(tile) => tile,
+                None => continue,
+            };
+
+            for destination in tile.all_reachable_tiles(&self.board, &occupied_tiles) {
+                if reachable.insert(destination.tile_id) {
+                    frontier.push(destination.tile_id);
+                }
+            }
+        }
+
+        Some(reachable.into_iter()
+            .filter_map(|tile_id| self.get_tile(tile_id))
+            .map(|tile| tile.get_fish_count())
+            .sum())
+    }
+
     /// Get a penguin at a position, None if no penguin at that position
     #[allow(dead_code)]
     pub fn find_penguin_at_position(&self, posn: BoardPosn) -> Option<&Penguin> {
@@ -285,6 +585,56 @@ impl GameState {
         })
     }
 
+    /// Returns each player in this game following turn_order rather than
+    /// the PlayerId order of the players map.
+    pub fn players_in_turn_order(&self) -> impl Iterator<Item = &Player> {
+        self.turn_order.iter().map(move |id| &self.players[id])
+    }
+
+    /// Returns the number of placements and moves made so far in this game.
+    /// Turns skipped because a player could not move are not counted.
+    pub fn turn_number(&self) -> usize {
+        self.turn_number
+    }
+
+    /// Returns the position of each tile the given player has taken fish from along with
+    /// the amount of fish taken, in the order they were taken. Since captured tiles are
+    /// removed from the board, this is the only record of where a player's score came from.
+    pub fn captures_of(&self, player: PlayerId) -> Vec<(BoardPosn, usize)> {
+        self.captures.get(&player).cloned().unwrap_or_default()
+    }
+
+    /// Returns the id of the penguin currently on the given tile, or None if there is no
+    /// penguin on it. Penguin ids are assigned in the order penguins are placed.
+    pub fn penguin_id_at(&self, tile: TileId) -> Option<PenguinId> {
+        self.owner_of_tile(tile)?;
+        self.trails.penguin_at(tile)
+    }
+
+    /// Returns every tile the given penguin has occupied this game in the order it occupied
+    /// them, starting with the tile it was placed on. Returns an empty Vec for penguins that
+    /// have not been placed. Penguins removed from the game keep their trail.
+    pub fn penguin_trail(&self, penguin: PenguinId) -> Vec<TileId> {
+        self.trails.trail(penguin).to_vec()
+    }
+
+    /// Returns true if the given player is still in this game
+    pub fn has_player(&self, player: PlayerId) -> bool {
+        self.players.contains_key(&player)
+    }
+
+    /// Returns the number of players still in this game. This starts as the number
+    /// of players the game was created with and decreases as players are kicked.
+    pub fn player_count(&self) -> usize {
+        self.turn_order.len()
+    }
+
+    /// Returns the index of the given player in turn_order, or None if they are not
+    /// in the game. Indices shift down as players earlier in the order are removed.
+    pub fn turn_index_of(&self, player: PlayerId) -> Option<usize> {
+        self.turn_order.iter().position(|id| *id == player)
+    }
+
     /// Returns the player whose turn it currently is
     pub fn current_player(&self) -> &Player {
         self.players.get(&self.current_turn).unwrap()
@@ -309,39 +659,60 @@ impl GameState {
     /// Note that this will skip the turn of any player who cannot
     /// move any penguins. It is an invalid game state for the current
     /// turn to be a player who cannot move any penguins.
-    pub fn advance_turn(&mut self) {
+    ///
+    /// Returns the players whose turns were skipped, in the order they were skipped.
+    /// This is normally empty. If no player can move the game is ended and every
+    /// player is returned.
+    pub fn advance_turn(&mut self) -> Vec<PlayerId> {
         self.advance_turn_index();
 
-        for _ in 0 .. self.players.len() {
+        let mut skipped = vec![];
+        for _ in 0 .. self.player_count() {
             if !self.current_player().has_unplaced_penguins() && self.get_valid_moves().is_empty() {
+                skipped.push(self.current_turn);
                 self.advance_turn_index()
             } else {
-                return;
+                return skipped;
             }
         }
 
-        // No players have any moves left, find the winning players by those with the maximum score
-        self.winning_players = Some(util::all_max_by_key(self.players.iter(), |(_, player)| player.score)
-            .map(|(id, _)| *id).collect());
+        // No players have any moves left
+        self.end_game();
+        skipped
+    }
+
+    /// Ends this game immediately, setting the winning players to those with the maximum score.
+    pub fn end_game(&mut self) {
+        self.winning_players = Some(self.compute_winners().unwrap_or_default());
+    }
+
+    /// Returns the players who would win if this game ended now: those with the maximum score.
+    /// Unlike winning_players, which is only set once the game actually ends, this can be
+    /// called at any point in the game. Returns None if there are no players left.
+    pub fn compute_winners(&self) -> Option<Vec<PlayerId>> {
+        if self.players.is_empty() {
+            return None;
+        }
+        Some(util::all_max_by_key(self.players.iter(), |(_, player)| player.score)
+            .map(|(id, _)| *id).collect())
     }
 
     /// Sets the turn of this game to the next player in order
     fn advance_turn_index(&mut self) {
         if !self.turn_order.is_empty() {
-            let current_turn_index = self.turn_order.iter().position(|id| id == &self.current_turn).unwrap();
-            let next_turn_index = (current_turn_index + 1) % self.turn_order.len();
+            let current_turn_index = self.turn_index_of(self.current_turn).unwrap();
+            let next_turn_index = (current_turn_index + 1) % self.player_count();
             self.current_turn = self.turn_order[next_turn_index];
         }
     }
 
     /// Sets the turn of the game to the previous player's turn, used when removing a player.
     fn previous_turn_index(&mut self) {
-        let current_turn_index = self.turn_order.iter()
-            .position(|id| id == &self.current_turn).unwrap();
+        let current_turn_index = self.turn_index_of(self.current_turn).unwrap();
         let prev_turn_index = if current_turn_index == 0 {
-            self.turn_order.len().saturating_sub(1)
+            self.player_count().saturating_sub(1)
         } else {
-            (current_turn_index - 1) % self.turn_order.len()
+            (current_turn_index - 1) % self.player_count()
         };
         self.current_turn = self.turn_order[prev_turn_index];
     }
@@ -353,10 +724,40 @@ impl GameState {
     /// Returns true if all penguins have a concrete position on the board.
     /// If this is false then we are still in the PlacePenguins phase of the game.
     pub fn all_penguins_are_placed(&self) -> bool {
-        self.players.iter().all(|(_, player)| !player.has_unplaced_penguins())
+        self.remaining_placements_needed() == 0
+    }
+
+    /// Runs the rest of the placement phase, placing each player's remaining penguins
+    /// in turn on the first free tile found by strategy::find_zigzag_placement. This is
+    /// mostly useful for setting up games in tests, where the placements are irrelevant.
+    pub fn place_all_zigzag(&mut self) {
+        while !self.all_penguins_are_placed() {
+            let placement = crate::server::strategy::find_zigzag_placement(self);
+            self.place_avatar_for_current_player(placement)
+                .expect("There are no free tiles left to place the remaining penguins on");
+        }
+    }
+
+    /// Returns the total number of penguins across all players that have yet to be placed.
+    pub fn remaining_placements_needed(&self) -> usize {
+        self.players.values().map(|player| player.unplaced_count()).sum()
+    }
+
+    /// Returns true if the current player has an unplaced penguin and there
+    /// is at least one unoccupied tile on the board to place it on.
+    pub fn can_place(&self) -> bool {
+        let occupied_tiles = self.get_occupied_tiles();
+        self.current_player().has_unplaced_penguins()
+            && self.board.tiles.keys().any(|tile_id| !occupied_tiles.contains(tile_id))
     }
 
-    /// Removes a player and its penguins from this game
+    /// Removes a player and its penguins from this game.
+    ///
+    /// Occupied tiles are always computed from the remaining players' penguins, so
+    /// the tiles the removed player's penguins were on become free immediately, and
+    /// can be moved onto or through by the remaining players on their next turn.
+    /// Anything caching valid moves for this state (e.g. a GameTree) must be rebuilt,
+    /// see GamePhase::update_from_gamestate.
     pub fn remove_player(&mut self, player_id: PlayerId) {
         if !self.is_game_over() {
             let should_advance_turn = self.current_turn == player_id;
@@ -377,6 +778,81 @@ impl GameState {
             }
         }
     }
+
+    /// Runs the given function on this game's board, then repairs the game so that it is
+    /// still consistent with the new board. Any penguin left on a hole is removed from the
+    /// game entirely, and once every penguin is placed the turn is advanced if the current
+    /// player can no longer move, ending the game if no player can.
+    ///
+    /// Prefer this over mutating the board field directly, which can leave penguins on holes.
+    pub fn with_board_mut<F: FnOnce(&mut Board)>(&mut self, f: F) {
+        f(&mut self.board);
+
+        let board = &self.board;
+        for player in self.players.values_mut() {
+            player.penguins.retain(|penguin| match penguin.tile_id {
+                Some(tile_id) => board.tiles.contains_key(&tile_id),
+                None => true,
+            });
+        }
+
+        if !self.is_game_over() && self.all_penguins_are_placed() && !self.can_player_move(self.current_turn) {
+            self.advance_turn();
+        }
+    }
+
+    /// Searches every remaining move of a two-player game to its end, returning the current
+    /// player's optimal move along with the margin it guarantees: the current player's final
+    /// score minus their opponent's, assuming the opponent also plays optimally. Unlike the
+    /// depth-limited searches in strategy.rs, the result is exact.
+    ///
+    /// Returns None if this is not a two-player game in the movement phase, or if solving it
+    /// would require visiting more than max_nodes game states. This is only practical once few
+    /// tiles remain, so callers should fall back to a heuristic search when None is returned.
+    pub fn solve_endgame(&self, max_nodes: usize) -> Option<(Move, i64)> {
+        if self.player_count() != 2 || self.is_game_over() || !self.all_penguins_are_placed() {
+            return None;
+        }
+
+        let mut nodes_remaining = max_nodes;
+        let (move_, margin) = self.solve_endgame_rec(self.current_turn, &mut nodes_remaining)?;
+        Some((move_?, margin))
+    }
+
+    /// Returns the optimal move for the current player, if they have one, and the final score
+    /// margin for the given player. Returns None once nodes_remaining runs out.
+    fn solve_endgame_rec(&self, player: PlayerId, nodes_remaining: &mut usize) -> Option<(Option<Move>, i64)> {
+        *nodes_remaining = nodes_remaining.checked_sub(1)?;
+
+        let valid_moves = if self.is_game_over() { vec![] } else { self.get_valid_moves() };
+        if valid_moves.is_empty() {
+            let margin = self.players.values()
+                .map(|other| if other.player_id == player { other.score as i64 } else { -(other.score as i64) })
+                .sum();
+            return Some((None, margin));
+        }
+
+        let maximizing = self.current_turn == player;
+        let mut best: Option<(Move, i64)> = None;
+
+        for move_ in valid_moves {
+            let mut child = self.clone();
+            child.move_avatar_for_current_player(move_)?;
+            let (_, margin) = child.solve_endgame_rec(player, nodes_remaining)?;
+
+            let is_better = match best {
+                Some((_, best_margin)) if maximizing => margin > best_margin,
+                Some((_, best_margin)) => margin < best_margin,
+                None => true,
+            };
+
+            if is_better {
+                best = Some((move_, margin));
+            }
+        }
+
+        best.map(|(move_, margin)| (Some(move_), margin))
+    }
 }
 
 #[cfg(test)]
@@ -384,6 +860,17 @@ pub mod tests {
     use super::*;
     use crate::common::boardposn::BoardPosn;
 
+    /// Creates a game on the given two-row board where each player has a single penguin.
+    /// A two-row board is a single zigzag path (tile k is adjacent to tiles k - 1 and k + 1)
+    /// along which penguins can only move one tile at a time.
+    pub fn single_penguin_path_game(board_text: &str, players: usize) -> GameState {
+        let mut gamestate = GameState::new(Board::from_text(board_text).unwrap(), players);
+        for player in gamestate.players.values_mut() {
+            player.penguins.truncate(1);
+        }
+        gamestate
+    }
+
     #[test]
     fn test_new() {
         let board = Board::with_no_holes(3, 3, 3);
@@ -481,6 +968,469 @@ pub mod tests {
         assert_eq!(penguin_pos, Some(reachable_tile));
     }
 
+    #[test]
+    fn test_validate_move() {
+        let mut gamestate = GameState::with_default_board(3, 3, 2);
+        let player_id = gamestate.turn_order[0];
+        let other_player_id = gamestate.turn_order[1];
+
+        // Reachable tiles from 0 are [2, 1, 5]
+        let tile_0 = TileId(0);
+        gamestate.place_avatar_without_changing_turn(player_id, tile_0);
+
+        // A move to the same tile is its own distinct error
+        assert_eq!(gamestate.validate_move(player_id, Move::new(tile_0, tile_0)), Err(MoveError::NoMovement));
+        assert_eq!(gamestate.move_avatar_for_player_without_changing_turn(player_id, tile_0, tile_0), None);
+
+        assert_eq!(gamestate.validate_move(player_id, Move::new(tile_0, TileId(3))), Err(MoveError::Unreachable));
+        assert_eq!(gamestate.validate_move(player_id, Move::new(tile_0, TileId(10))), Err(MoveError::Unreachable));
+        assert_eq!(gamestate.validate_move(player_id, Move::new(TileId(1), TileId(2))), Err(MoveError::NoPenguin));
+        assert_eq!(gamestate.validate_move(other_player_id, Move::new(tile_0, TileId(5))), Err(MoveError::NoPenguin));
+        assert_eq!(gamestate.validate_move(PlayerId(7), Move::new(tile_0, TileId(5))), Err(MoveError::UnknownPlayer));
+        assert_eq!(gamestate.validate_move(player_id, Move::new(tile_0, TileId(5))), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_move_destination_occupied() {
+        // 0   3   6
+        //   1   4   7
+        // 2   5   8
+        let mut gamestate = GameState::with_default_board(3, 3, 2);
+        let player_id = gamestate.turn_order[0];
+        let other_player_id = gamestate.turn_order[1];
+        gamestate.place_avatar_without_changing_turn(player_id, TileId(0));
+        gamestate.place_avatar_without_changing_turn(player_id, TileId(2));
+        gamestate.place_avatar_without_changing_turn(other_player_id, TileId(1));
+
+        // Moving onto either player's adjacent penguin is rejected, and leaves the board unchanged
+        assert_eq!(gamestate.validate_move(player_id, Move::new(TileId(0), TileId(1))), Err(MoveError::DestinationOccupied));
+        assert_eq!(gamestate.validate_move(player_id, Move::new(TileId(0), TileId(2))), Err(MoveError::DestinationOccupied));
+        assert_eq!(gamestate.move_avatar_for_player_without_changing_turn(player_id, TileId(0), TileId(1)), None);
+        assert_eq!(gamestate.owner_of_tile(TileId(1)), Some(other_player_id));
+        assert!(gamestate.get_tile(TileId(0)).is_some());
+
+        // Tiles beyond a penguin are still blocked by it rather than occupied
+        assert_eq!(gamestate.validate_move(other_player_id, Move::new(TileId(1), TileId(5))), Ok(()));
+        assert_eq!(gamestate.validate_move(player_id, Move::new(TileId(0), TileId(5))), Err(MoveError::Unreachable));
+    }
+
+    #[test]
+    fn test_with_board_mut() {
+        let mut gamestate = GameState::with_default_board(4, 4, 2);
+        gamestate.place_avatar_for_current_player(Placement::new(TileId(0))).unwrap();
+        gamestate.place_avatar_for_current_player(Placement::new(TileId(5))).unwrap();
+
+        let player_0 = PlayerId(0);
+        assert_eq!(gamestate.players[&player_0].penguins.len(), 4);
+
+        // Removing an empty tile leaves every penguin alone
+        gamestate.with_board_mut(|board| { board.remove_tile(TileId(10)); });
+        assert_eq!(gamestate.players[&player_0].penguins.len(), 4);
+        assert_eq!(gamestate.get_occupied_tiles().len(), 2);
+
+        // Removing the tile under player 0's penguin removes the penguin
+        gamestate.with_board_mut(|board| { board.remove_tile(TileId(0)); });
+        assert_eq!(gamestate.players[&player_0].penguins.len(), 3);
+        assert_eq!(gamestate.players[&player_0].placed_count(), 0);
+        assert_eq!(gamestate.get_occupied_tiles().into_iter().collect::<Vec<_>>(), vec![TileId(5)]);
+        assert_eq!(gamestate.find_penguin(TileId(0)), None);
+    }
+
+    #[test]
+    fn test_debug_with_coords() {
+        // 0     2
+        //    1     3
+        let mut gamestate = GameState::with_default_board(2, 2, 2);
+        gamestate.board.remove_tile(TileId(2));
+        gamestate.place_avatar_for_current_player(Placement::new(TileId(1))).unwrap();
+
+        assert_eq!(gamestate.debug_with_coords(), concat!(
+            "0(0,0)     x(1,0)\n",
+            "     1(0,1) P0  3(1,1)\n",
+            "\n",
+            "Player 0 - red - score: 0 - penguins: [1(0,1), unplaced, unplaced, unplaced]\n",
+            "Player 1 - white - score: 0 - penguins: [unplaced, unplaced, unplaced, unplaced] <- current turn\n",
+        ));
+    }
+
+    #[test]
+    fn test_compute_winners() {
+        let mut gamestate = GameState::with_default_board(4, 4, 2);
+        gamestate.place_all_zigzag();
+
+        // Nobody has scored yet so everyone is tied for the lead
+        assert_eq!(gamestate.compute_winners(), Some(vec![PlayerId(0), PlayerId(1)]));
+
+        let move_ = gamestate.get_valid_moves()[0];
+        gamestate.move_avatar_for_current_player(move_).unwrap();
+        assert_eq!(gamestate.compute_winners(), Some(vec![PlayerId(0)]));
+        assert_eq!(gamestate.winning_players, None);
+
+        while !gamestate.is_game_over() {
+            let move_ = gamestate.get_valid_moves()[0];
+            gamestate.move_avatar_for_current_player(move_).unwrap();
+        }
+
+        // Once the game is over the leaders are the winners
+        assert!(gamestate.winning_players.is_some());
+        assert_eq!(gamestate.compute_winners(), gamestate.winning_players);
+
+        gamestate.players.clear();
+        assert_eq!(gamestate.compute_winners(), None);
+    }
+
+    #[test]
+    fn test_remaining_placements_needed() {
+        // Each of the 2 players has 4 penguins
+        let mut gamestate = GameState::with_default_board(4, 4, 2);
+        assert_eq!(gamestate.remaining_placements_needed(), 8);
+
+        for (placements_made, tile_id) in [0, 4, 8].iter().enumerate() {
+            assert_eq!(gamestate.remaining_placements_needed(), 8 - placements_made);
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(*tile_id))).unwrap();
+        }
+        assert_eq!(gamestate.remaining_placements_needed(), 5);
+        assert!(!gamestate.all_penguins_are_placed());
+
+        // An invalid placement does not change the count
+        assert!(gamestate.place_avatar_for_current_player(Placement::new(TileId(0))).is_none());
+        assert_eq!(gamestate.remaining_placements_needed(), 5);
+
+        for tile_id in [1, 5, 9, 12, 13].iter() {
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(*tile_id))).unwrap();
+        }
+        assert_eq!(gamestate.remaining_placements_needed(), 0);
+        assert!(gamestate.all_penguins_are_placed());
+    }
+
+    #[test]
+    fn test_can_place() {
+        // 2 players with 4 penguins each, but only 4 tiles to place them on
+        let mut gamestate = GameState::with_default_board(2, 2, 2);
+
+        for tile_id in 0 .. 4 {
+            assert!(gamestate.can_place());
+            let player = gamestate.current_turn;
+            gamestate.place_avatar_for_player(player, TileId(tile_id));
+        }
+
+        assert!(!gamestate.all_penguins_are_placed());
+        assert!(!gamestate.can_place());
+    }
+
+    /// Bounce a penguin back and forth between the only two tiles on a board
+    /// with 3 fish each. With the Deplete rule, tile 0 should lose a fish the
+    /// first two times the penguin leaves it and only be removed the third time.
+    #[test]
+    fn test_deplete_tile_rule() {
+        // 0
+        //    1
+        let board = Board::with_no_holes(2, 1, 3);
+        let mut gamestate = GameState::new(board, 2);
+        gamestate.tile_rule = TileRule::Deplete;
+
+        let player_id = gamestate.turn_order[0];
+        gamestate.place_avatar_without_changing_turn(player_id, TileId(0));
+
+        for expected_fish in &[2, 1] {
+            assert_eq!(gamestate.move_avatar_for_player_without_changing_turn(player_id, TileId(0), TileId(1)), Some(()));
+            assert_eq!(gamestate.get_tile(TileId(0)).map(|tile| tile.fish_count), Some(*expected_fish));
+            assert_eq!(gamestate.move_avatar_for_player_without_changing_turn(player_id, TileId(1), TileId(0)), Some(()));
+        }
+
+        assert_eq!(gamestate.move_avatar_for_player_without_changing_turn(player_id, TileId(0), TileId(1)), Some(()));
+        assert!(gamestate.get_tile(TileId(0)).is_none());
+
+        // one fish for each of the 5 moves
+        assert_eq!(gamestate.player_score(player_id), 5);
+    }
+
+    #[test]
+    fn test_players_in_turn_order() {
+        let board = Board::with_no_holes(3, 3, 3);
+        let turn_order = vec![PlayerId(3), PlayerId(0), PlayerId(2)];
+        let gamestate = GameState::with_players(board, turn_order.clone());
+
+        // The players map iterates by id, which differs from the turn order
+        let ids_by_map: Vec<_> = gamestate.players.keys().copied().collect();
+        assert_eq!(ids_by_map, vec![PlayerId(0), PlayerId(2), PlayerId(3)]);
+
+        let ids_in_turn_order: Vec<_> = gamestate.players_in_turn_order().map(|player| player.player_id).collect();
+        assert_eq!(ids_in_turn_order, turn_order);
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let mut gamestate = GameState::with_default_board(4, 3, 2);
+        for tile_id in 0 .. 8 {
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(tile_id)));
+        }
+
+        // States differing only in how they were reached share a key
+        let mut other = gamestate.clone();
+        other.turn_number += 10;
+        other.players.get_mut(&PlayerId(0)).unwrap().penguins.reverse();
+        assert!(gamestate.canonical_key() == other.canonical_key());
+
+        let hash = |state: &GameState| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            state.canonical_key().hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&gamestate), hash(&other));
+
+        // Any difference in score, position, or turn is a different key
+        let mut scored = gamestate.clone();
+        scored.players.get_mut(&PlayerId(1)).unwrap().score += 1;
+        assert!(gamestate.canonical_key() != scored.canonical_key());
+
+        let mut moved = gamestate.clone();
+        moved.move_avatar_for_current_player(moved.get_valid_moves()[0]);
+        assert!(gamestate.canonical_key() != moved.canonical_key());
+
+        let mut other_turn = gamestate.clone();
+        other_turn.current_turn = if gamestate.current_turn == PlayerId(0) { PlayerId(1) } else { PlayerId(0) };
+        assert!(gamestate.canonical_key() != other_turn.canonical_key());
+    }
+
+    #[test]
+    fn test_no_player_can_ever_move() {
+        // 0   4
+        //   1   5
+        // 2   6
+        //   3   7
+        let mut gamestate = GameState::with_default_board(4, 2, 2);
+        assert!(!gamestate.no_player_can_ever_move());
+
+        // Fill every tile with a penguin so that each one is blocked by the others.
+        // Until the last penguin is placed there is still an unplaced penguin.
+        for tile_id in 0 .. 8 {
+            assert!(!gamestate.no_player_can_ever_move());
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(tile_id)));
+        }
+
+        assert!(gamestate.all_penguins_are_placed());
+        assert!(gamestate.no_player_can_ever_move());
+        assert!(gamestate.is_game_over());
+
+        // A game with a possible move is not stuck
+        let mut gamestate = GameState::with_default_board(3, 3, 2);
+        for tile_id in 0 .. 8 {
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(tile_id)));
+        }
+        assert!(gamestate.can_any_player_move_penguin());
+        assert!(!gamestate.no_player_can_ever_move());
+    }
+
+    #[test]
+    fn test_apply_action() {
+        // 0   3   6
+        //   1   4   7
+        // 2   5   8
+        let mut gamestate = GameState::with_default_board(3, 3, 2);
+        let mut expected = gamestate.clone();
+
+        let placement = Placement::new(TileId(0));
+        assert_eq!(gamestate.apply_action(Action::PlacePenguin(placement)), Some(()));
+        expected.place_avatar_for_current_player(placement);
+        assert!(gamestate == expected);
+
+        // Placing on an occupied tile is rejected without changing the state
+        assert_eq!(gamestate.apply_action(Action::PlacePenguin(placement)), None);
+        assert!(gamestate == expected);
+
+        for tile_id in 1 .. 8 {
+            gamestate.apply_action(Action::PlacePenguin(Placement::new(TileId(tile_id))));
+            expected.place_avatar_for_current_player(Placement::new(TileId(tile_id)));
+        }
+        assert!(gamestate.all_penguins_are_placed());
+
+        let move_ = gamestate.get_valid_moves()[0];
+        assert_eq!(gamestate.apply_action(Action::MovePenguin(move_)), Some(()));
+        expected.move_avatar_for_current_player(move_);
+        assert!(gamestate == expected);
+
+        // Moving the same penguin again from its old tile is rejected
+        assert_eq!(gamestate.apply_action(Action::MovePenguin(move_)), None);
+        assert!(gamestate == expected);
+    }
+
+    #[test]
+    fn test_owner_of_tile() {
+        let mut gamestate = GameState::with_default_board(3, 4, 3);
+        for tile_id in 0 .. 9 {
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(tile_id)));
+        }
+
+        // Players take turns placing in order, so tile n belongs to player n % 3
+        for tile_id in 0 .. 9 {
+            let owner = gamestate.owner_of_tile(TileId(tile_id));
+            assert_eq!(owner, Some(PlayerId(tile_id % 3)));
+            assert_eq!(gamestate.get_color_on_tile(TileId(tile_id)), Some(gamestate.players[&owner.unwrap()].color));
+        }
+
+        // Empty tiles, holes, and out of bounds tiles have no owner
+        assert_eq!(gamestate.owner_of_tile(TileId(10)), None);
+        gamestate.board.remove_tile(TileId(11));
+        assert_eq!(gamestate.owner_of_tile(TileId(11)), None);
+        assert_eq!(gamestate.owner_of_tile(TileId(100)), None);
+    }
+
+    #[test]
+    fn test_place_too_many_penguins() {
+        // Each of the 3 players has 3 penguins to place
+        let mut gamestate = GameState::with_default_board(4, 4, 3);
+        let player = gamestate.turn_order[0];
+        for tile_id in 0 .. 3 {
+            assert_eq!(gamestate.place_avatar_for_player(player, TileId(tile_id)), Some(()));
+        }
+
+        // Running out of penguins is reported before any problem with the tile itself
+        let before = gamestate.clone();
+        assert_eq!(gamestate.validate_placement_for_player(player, Placement::new(TileId(3))),
+            Err(PlacementError::NoUnplacedPenguin));
+        assert_eq!(gamestate.validate_placement_for_player(player, Placement::new(TileId(0))),
+            Err(PlacementError::NoUnplacedPenguin));
+        assert_eq!(gamestate.validate_placement_for_player(player, Placement::new(TileId(100))),
+            Err(PlacementError::NoUnplacedPenguin));
+
+        assert_eq!(gamestate.place_avatar_for_player(player, TileId(3)), None);
+        assert!(gamestate == before);
+        assert_eq!(gamestate.players[&player].penguins.len(), 3);
+
+        // Other players can still place onto the same tile
+        let other_player = gamestate.turn_order[1];
+        assert_eq!(gamestate.validate_placement_for_player(other_player, Placement::new(TileId(3))), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_placement() {
+        // 0   2   4
+        //   1   3   5
+        let mut gamestate = GameState::with_default_board(2, 3, 4);
+        gamestate.board.remove_tile(TileId(5));
+        assert_eq!(gamestate.validate_placement(Placement::new(TileId(0))), Ok(()));
+
+        gamestate.place_avatar_for_current_player(Placement::new(TileId(0)));
+        assert_eq!(gamestate.validate_placement(Placement::new(TileId(0))), Err(PlacementError::Occupied));
+        assert_eq!(gamestate.validate_placement(Placement::new(TileId(5))), Err(PlacementError::NoTile));
+        assert_eq!(gamestate.validate_placement(Placement::new(TileId(6))), Err(PlacementError::NoTile));
+
+        // Each of the 4 players has 2 penguins to place
+        let placed = gamestate.current_turn;
+        gamestate.place_avatar_without_changing_turn(placed, TileId(1));
+        gamestate.place_avatar_without_changing_turn(placed, TileId(2));
+        assert_eq!(gamestate.validate_placement_for_player(placed, Placement::new(TileId(3))),
+            Err(PlacementError::NoUnplacedPenguin));
+
+        assert_eq!(gamestate.validate_placement_for_player(PlayerId(10), Placement::new(TileId(3))),
+            Err(PlacementError::UnknownPlayer));
+
+        // Invalid placements are not made
+        let before = gamestate.clone();
+        assert_eq!(gamestate.place_avatar_without_changing_turn(placed, TileId(3)), None);
+        assert_eq!(gamestate.place_avatar_for_current_player(Placement::new(TileId(5))), None);
+        assert!(gamestate == before);
+    }
+
+    #[test]
+    fn test_captures_of() {
+        // 0   4   8   12
+        //   1   5   9   13
+        // 2   6   10  14
+        //   3   7   11  15
+        let mut gamestate = GameState::new(Board::with_no_holes(4, 4, 2), 2);
+        for tile_id in [0, 12, 1, 13, 2, 14, 3, 15].iter() {
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(*tile_id)));
+        }
+
+        let first_move = gamestate.get_valid_moves()[0];
+        let first_mover = gamestate.current_turn;
+        let first_position = gamestate.board.get_tile_position(first_move.from);
+
+        while !gamestate.is_game_over() {
+            let move_ = gamestate.get_valid_moves()[0];
+            gamestate.move_avatar_for_current_player(move_);
+        }
+
+        assert_eq!(gamestate.captures_of(first_mover)[0], (first_position, 2));
+
+        for (player_id, player) in gamestate.players.iter() {
+            let captured_fish: usize = gamestate.captures_of(*player_id).iter().map(|(_, fish)| fish).sum();
+            assert_eq!(captured_fish, player.score);
+            assert!(player.score > 0);
+        }
+
+        assert_eq!(gamestate.captures_of(PlayerId(7)), vec![]);
+    }
+
+    #[test]
+    fn test_penguin_trail() {
+        // 0   4   8   12
+        //   1   5   9   13
+        // 2   6   10  14
+        //   3   7   11  15
+        let mut gamestate = GameState::new(Board::with_no_holes(4, 4, 2), 2);
+        for tile_id in [0, 12, 1, 13, 2, 14, 3, 15].iter() {
+            gamestate.place_avatar_for_current_player(Placement::new(TileId(*tile_id)));
+        }
+
+        // Penguins are identified in the order they were placed
+        assert_eq!(gamestate.penguin_id_at(TileId(0)), Some(PenguinId(0)));
+        assert_eq!(gamestate.penguin_id_at(TileId(15)), Some(PenguinId(7)));
+        assert_eq!(gamestate.penguin_id_at(TileId(4)), None);
+        assert_eq!(gamestate.penguin_trail(PenguinId(1)), vec![TileId(12)]);
+        assert_eq!(gamestate.penguin_trail(PenguinId(8)), vec![]);
+
+        // Player 0's penguin on tile 1 moves twice
+        gamestate.move_avatar_for_current_player(Move::new(TileId(1), TileId(4))).unwrap();
+        gamestate.move_avatar_for_current_player(Move::new(TileId(12), TileId(9))).unwrap();
+        gamestate.move_avatar_for_current_player(Move::new(TileId(4), TileId(5))).unwrap();
+
+        assert_eq!(gamestate.penguin_trail(PenguinId(2)), vec![TileId(1), TileId(4), TileId(5)]);
+        assert_eq!(gamestate.penguin_trail(PenguinId(1)), vec![TileId(12), TileId(9)]);
+        assert_eq!(gamestate.penguin_trail(PenguinId(0)), vec![TileId(0)]);
+        assert_eq!(gamestate.penguin_id_at(TileId(5)), Some(PenguinId(2)));
+        assert_eq!(gamestate.penguin_id_at(TileId(1)), None);
+    }
+
+    #[test]
+    fn test_turn_number() {
+        let mut gamestate = GameState::with_default_board(4, 4, 2);
+        assert_eq!(gamestate.turn_number(), 0);
+
+        // Failed placements are not counted
+        assert_eq!(gamestate.place_avatar_for_player(gamestate.current_turn, TileId(20)), None);
+        assert_eq!(gamestate.turn_number(), 0);
+
+        // Each player places their penguins down a single column
+        for (i, tile_id) in [0, 12, 1, 13, 2, 14, 3, 15].iter().enumerate() {
+            gamestate.place_avatar_for_player(gamestate.current_turn, TileId(*tile_id));
+            assert_eq!(gamestate.turn_number(), i + 1);
+        }
+
+        // Skipped turns (where the same player moves twice in a row) are not counted
+        let mut moves = 0;
+        let mut skips = 0;
+        while !gamestate.is_game_over() {
+            let mover = gamestate.current_turn;
+            let move_ = gamestate.get_valid_moves()[0];
+            gamestate.move_avatar_for_current_player(move_).unwrap();
+            moves += 1;
+            assert_eq!(gamestate.turn_number(), 8 + moves);
+
+            if gamestate.current_turn == mover && !gamestate.is_game_over() {
+                skips += 1;
+            }
+        }
+        assert_eq!(skips, 1);
+
+        // The same position reached after a different number of turns is still the same state
+        let mut other = gamestate.clone();
+        other.turn_number = 0;
+        assert!(other == gamestate);
+    }
+
     #[test]
     fn test_advance_turn() {
         let mut gamestate = GameState::with_default_board(3, 3, 4);
@@ -493,4 +1443,173 @@ pub mod tests {
         // check that advancing the turn on the last player makes the gamestate look at the first player in the order again
         assert_eq!(gamestate.current_turn, gamestate.turn_order[0]);
     }
+
+    #[test]
+    fn test_advance_turn_skipped_players() {
+        // Tile 1 is a hole, so a penguin on tile 0 can never move
+        let mut gamestate = single_penguin_path_game("
+            1 1 1 1 1
+             x 1 1 1 1
+        ", 3);
+
+        let (first, stuck, last) = (PlayerId(0), PlayerId(1), PlayerId(2));
+        gamestate.place_avatar_for_player(first, TileId(2)).unwrap();
+        gamestate.place_avatar_for_player(stuck, TileId(0)).unwrap();
+        gamestate.place_avatar_for_player(last, TileId(9)).unwrap();
+        assert_eq!(gamestate.current_turn, first);
+
+        let mut skipped = vec![];
+        for (player, from, to) in [(first, 2, 3), (last, 9, 8), (first, 3, 4), (last, 8, 7)] {
+            gamestate.move_avatar_for_player_without_changing_turn(player, TileId(from), TileId(to)).unwrap();
+            let skipped_this_turn = gamestate.advance_turn();
+            assert_eq!(skipped_this_turn.is_empty(), player == last);
+            skipped.extend(skipped_this_turn);
+        }
+
+        // The stuck player is skipped once in each of the two rounds played
+        assert_eq!(skipped, vec![stuck, stuck]);
+        assert_eq!(gamestate.current_turn, first);
+        assert!(!gamestate.is_game_over());
+    }
+
+    #[test]
+    fn test_score_ceiling() {
+        // Tiles 0 to 3 have 2, 1, 3, and 0 fish and are cut off from the rest by the hole
+        // at tile 4. Tiles 5 and 6 are cut off from tiles 8 and 9 by the hole at tile 7.
+        let board_text = "
+            2 3 x 1 1
+             1 0 1 x 1
+        ";
+        let mut gamestate = single_penguin_path_game(board_text, 2);
+
+        let (walker, other) = (PlayerId(0), PlayerId(1));
+        assert_eq!(gamestate.score_ceiling(walker), Some(0));
+        assert_eq!(gamestate.score_ceiling(PlayerId(2)), Some(0));
+
+        gamestate.place_avatar_for_player(walker, TileId(0)).unwrap();
+        gamestate.place_avatar_for_player(other, TileId(5)).unwrap();
+
+        // The walker's best is to walk the whole path, collecting every fish on it since
+        // the tile they finish on has none. The other player can only take 1 of their 2.
+        let ceilings = (gamestate.score_ceiling(walker), gamestate.score_ceiling(other));
+        assert_eq!(ceilings, (Some(6), Some(2)));
+
+        while !gamestate.is_game_over() {
+            let move_ = gamestate.get_valid_moves()[0];
+            gamestate.move_avatar_for_current_player(move_).unwrap();
+        }
+
+        assert_eq!(Some(gamestate.players[&walker].score), ceilings.0);
+        assert!(Some(gamestate.players[&other].score) <= ceilings.1);
+        assert_eq!(gamestate.score_ceiling(walker), Some(0));
+
+        // A penguin in the way blocks the rest of the path for good
+        let mut gamestate = single_penguin_path_game(board_text, 2);
+        gamestate.place_avatar_for_player(walker, TileId(0)).unwrap();
+        gamestate.place_avatar_for_player(other, TileId(2)).unwrap();
+        assert_eq!(gamestate.score_ceiling(walker), Some(2 + 1));
+
+        // Penguins can collect from the same tile more than once when tiles are depleted
+        gamestate.tile_rule = TileRule::Deplete;
+        assert_eq!(gamestate.score_ceiling(walker), None);
+    }
+
+    #[test]
+    fn test_place_all_zigzag() {
+        for players in MIN_PLAYERS_PER_GAME ..= MAX_PLAYERS_PER_GAME {
+            let mut gamestate = GameState::with_default_board(5, 5, players);
+            gamestate.place_all_zigzag();
+
+            assert!(gamestate.all_penguins_are_placed());
+            assert_eq!(gamestate.current_turn, PlayerId(0));
+            assert!(gamestate.can_player_move(PlayerId(0)));
+        }
+
+        // Placing the remaining penguins of a partially placed game
+        let mut gamestate = GameState::with_default_board(5, 5, 2);
+        gamestate.place_avatar_for_current_player(Placement::new(TileId(24))).unwrap();
+        gamestate.place_all_zigzag();
+        assert!(gamestate.all_penguins_are_placed());
+        assert!(gamestate.find_penguin(TileId(24)).is_some());
+        assert_eq!(gamestate.current_turn, PlayerId(0));
+    }
+
+    #[test]
+    fn test_turn_index_of() {
+        let mut gamestate = GameState::with_default_board(5, 5, 4);
+
+        for id in 0 .. 4 {
+            assert!(gamestate.has_player(PlayerId(id)));
+            assert_eq!(gamestate.turn_index_of(PlayerId(id)), Some(id));
+        }
+        assert!(!gamestate.has_player(PlayerId(4)));
+        assert_eq!(gamestate.turn_index_of(PlayerId(4)), None);
+
+        // Players after a removed player move up in the turn order
+        gamestate.remove_player(PlayerId(1));
+        assert!(!gamestate.has_player(PlayerId(1)));
+        assert_eq!(gamestate.turn_index_of(PlayerId(1)), None);
+        assert_eq!(gamestate.turn_index_of(PlayerId(0)), Some(0));
+        assert_eq!(gamestate.turn_index_of(PlayerId(2)), Some(1));
+        assert_eq!(gamestate.turn_index_of(PlayerId(3)), Some(2));
+    }
+
+    #[test]
+    fn test_mobility_per_player() {
+        let mut gamestate = GameState::with_default_board(5, 5, 3);
+
+        // No penguins are placed yet, so no one can move
+        assert!(gamestate.mobility_per_player().values().all(|moves| *moves == 0));
+
+        gamestate.place_all_zigzag();
+        gamestate.move_avatar_for_current_player(gamestate.get_valid_moves()[0]).unwrap();
+
+        // Each player's mobility is the number of valid moves they would have on their turn
+        let expected: BTreeMap<_, _> = gamestate.turn_order.iter().map(|player_id| {
+            let mut turn = gamestate.clone();
+            turn.current_turn = *player_id;
+            (*player_id, turn.get_valid_moves().len())
+        }).collect();
+
+        assert_eq!(gamestate.mobility_per_player(), expected);
+        assert_eq!(expected.len(), 3);
+        assert!(expected.values().all(|moves| *moves > 0));
+    }
+
+    #[test]
+    fn test_player_count() {
+        let mut gamestate = GameState::with_default_board(5, 5, 3);
+        assert_eq!(gamestate.player_count(), 3);
+
+        gamestate.remove_player(PlayerId(1));
+        assert_eq!(gamestate.player_count(), 2);
+
+        // Removing a player who already left changes nothing
+        gamestate.remove_player(PlayerId(1));
+        assert_eq!(gamestate.player_count(), 2);
+        assert_eq!(gamestate.player_count(), gamestate.players.len());
+    }
+
+    #[test]
+    fn test_solve_endgame() {
+        let mut gamestate = single_penguin_path_game("
+            1 3 5 2
+             2 1 1 1
+        ", 2);
+        gamestate.place_avatar_for_player(PlayerId(0), TileId(3)).unwrap();
+        gamestate.place_avatar_for_player(PlayerId(1), TileId(5)).unwrap();
+        assert_eq!(gamestate.current_turn, PlayerId(0));
+
+        // Moving toward the 5 fish tile traps player 0 there before they can collect it,
+        // giving a margin of 1 - (1 + 2). Moving away instead collects 1 + 3 + 2 while
+        // player 1's best response collects 1 + 2, for a margin of 3.
+        assert_eq!(gamestate.solve_endgame(1000), Some((Move::new(TileId(3), TileId(2)), 3)));
+
+        // Too few nodes to search every line of play
+        assert_eq!(gamestate.solve_endgame(3), None);
+
+        // Only two-player games in the movement phase can be solved
+        assert_eq!(GameState::with_default_board(2, 4, 2).solve_endgame(1000), None);
+        assert_eq!(GameState::with_default_board(5, 5, 3).solve_endgame(1000), None);
+    }
 }
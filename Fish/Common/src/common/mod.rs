@@ -6,12 +6,14 @@
 //! serialized to be sent over the network from the server to each client (TBD).
 pub mod action;
 pub mod board;
+pub mod board_view;
 pub mod boardposn;
 pub mod direction;
 pub mod gamestate;
 pub mod gamephase;
 pub mod penguin;
 pub mod player;
+pub mod replay;
 pub mod tile;
 pub mod util;
 pub mod game_tree;
\ No newline at end of file
@@ -6,8 +6,14 @@ use std::collections::HashSet;
 
 use serde::{ Serialize, Deserialize };
 
+/// Identifies a penguin within a single game by the order it was placed in,
+/// starting at 0 for the first penguin placed. Penguins are otherwise anonymous,
+/// so ids are derived from a game's actions, see GameState::penguin_id_at.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PenguinId(pub usize);
+
 /// Represents a single Penguin in the Fish game, including its position
-/// on the board and a unique ID. Its position can be None, meaning
+/// on the board. Its position can be None, meaning
 /// it is not placed yet, or Some(BoardPosn), meaning it's placed at
 /// the BoardPosn on the game board.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -18,9 +24,8 @@ pub struct Penguin {
 }
 
 impl Penguin {
-    /// Creates a new penguin with a unique PenguinId, starting at 1.
-    /// The penguin is initially unplaced, represented with None
-    /// as its BoardPosn.
+    /// Creates a new penguin. The penguin is initially unplaced,
+    /// represented with None as its BoardPosn.
     pub fn new() -> Penguin {
         Penguin { tile_id: None }
     }
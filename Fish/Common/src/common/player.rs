@@ -105,6 +105,16 @@ impl Player {
     pub fn has_unplaced_penguins(&self) -> bool {
         self.penguins.iter().any(|penguin| !penguin.is_placed())
     }
+
+    /// Returns the number of this player's penguins that are on the board.
+    pub fn placed_count(&self) -> usize {
+        self.penguins.iter().filter(|penguin| penguin.is_placed()).count()
+    }
+
+    /// Returns the number of this player's penguins that have yet to be placed.
+    pub fn unplaced_count(&self) -> usize {
+        self.penguins.len() - self.placed_count()
+    }
 }
 
 #[cfg(test)]
@@ -152,6 +162,29 @@ mod tests {
         assert_eq!(player.place_penguin(TileId(5), &board), None);
     }
 
+    #[test]
+    fn test_placed_and_unplaced_count() {
+        let board = Board::with_no_holes(3, 3, 3);
+        let mut player = Player::new(PlayerId(0), PlayerColor::red, 4);
+
+        assert_eq!(player.placed_count(), 0);
+        assert_eq!(player.unplaced_count(), 4);
+
+        player.place_penguin(TileId(0), &board);
+        player.place_penguin(TileId(4), &board);
+
+        assert_eq!(player.placed_count(), 2);
+        assert_eq!(player.unplaced_count(), 2);
+        assert!(player.has_unplaced_penguins());
+
+        player.place_penguin(TileId(6), &board);
+        player.place_penguin(TileId(8), &board);
+
+        assert_eq!(player.placed_count(), 4);
+        assert_eq!(player.unplaced_count(), 0);
+        assert!(!player.has_unplaced_penguins());
+    }
+
     #[test]
     fn test_move_penguin() {
         // 0   3   6
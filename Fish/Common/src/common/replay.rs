@@ -0,0 +1,239 @@
+//! This file contains Replay, which steps through a game that was already
+//! played one action at a time, e.g. so the GUI can show it being replayed.
+use crate::common::action::Action;
+use crate::common::gamestate::{ GameState, SharedGameState };
+use crate::common::penguin::PenguinId;
+use crate::common::tile::TileId;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A game being replayed from its initial GameState and each Action taken during it,
+/// in the order they were taken. The game is kept in a SharedGameState so that it
+/// can be drawn as it is replayed, see client::replay_ui.
+pub struct Replay {
+    state: SharedGameState,
+    actions: Vec<Action>,
+
+    /// The index within actions of the next action to apply
+    next_action: usize,
+
+    /// The trail of each penguin through the actions applied so far
+    trails: PenguinTrails,
+}
+
+impl Replay {
+    pub fn new(initial_state: GameState, actions: Vec<Action>) -> Replay {
+        Replay { state: Rc::new(RefCell::new(initial_state)), actions, next_action: 0, trails: PenguinTrails::new() }
+    }
+
+    /// Returns the state of the game being replayed, which is updated by each call to step
+    pub fn shared_state(&self) -> SharedGameState {
+        self.state.clone()
+    }
+
+    /// Applies the next action to the shared state for the player whose turn it is.
+    /// Returns whether an action was applied, which is false once every action has been.
+    ///
+    /// An action that is invalid for the replayed game also returns false, leaving the
+    /// shared state unchanged: none of the actions after it could be applied either.
+    pub fn step(&mut self) -> bool {
+        let action = match self.actions.get(self.next_action) {
+            Some(action) => *action,
+            None => return false,
+        };
+
+        let mut state = self.state.borrow_mut();
+        let mut next_state = state.clone();
+        if next_state.apply_action(action).is_none() {
+            return false;
+        }
+
+        *state = next_state;
+        self.trails.record(action);
+        self.next_action += 1;
+        true
+    }
+
+    /// The trail of each penguin through the actions applied so far, e.g. to follow
+    /// a specific penguin as the game is replayed
+    pub fn trails(&self) -> &PenguinTrails {
+        &self.trails
+    }
+
+    /// The number of actions applied by step so far
+    pub fn actions_applied(&self) -> usize {
+        self.next_action
+    }
+
+    /// Returns true if every action has been applied
+    pub fn is_finished(&self) -> bool {
+        self.next_action == self.actions.len()
+    }
+}
+
+/// Records every tile each penguin occupied during a game, in order, by following the
+/// game's actions. Penguins are identified by the order they were placed in, see PenguinId.
+///
+/// Each GameState keeps the trails of its own game, see GameState::penguin_trail. Since they are
+/// not serialized, a Replay keeps its own trails in case its initial state was received as json.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PenguinTrails {
+    /// The tiles each penguin occupied, indexed by PenguinId
+    trails: Vec<Vec<TileId>>,
+
+    /// The penguin on each occupied tile
+    penguins: HashMap<TileId, PenguinId>,
+}
+
+impl PenguinTrails {
+    pub fn new() -> PenguinTrails {
+        PenguinTrails::default()
+    }
+
+    /// Returns the trails of the penguins in a game after the given valid actions are taken
+    pub fn from_actions(actions: &[Action]) -> PenguinTrails {
+        let mut trails = PenguinTrails::new();
+        for action in actions {
+            trails.record(*action);
+        }
+        trails
+    }
+
+    /// Extends the trails with the given action, which must be valid in the game so far.
+    /// A placement starts a new trail and a move extends the trail of the moved penguin.
+    pub fn record(&mut self, action: Action) {
+        match action {
+            Action::PlacePenguin(placement) => {
+                self.penguins.insert(placement.tile_id, PenguinId(self.trails.len()));
+                self.trails.push(vec![placement.tile_id]);
+            },
+            Action::MovePenguin(move_) => {
+                if let Some(penguin) = self.penguins.remove(&move_.from) {
+                    self.trails[penguin.0].push(move_.to);
+                    self.penguins.insert(move_.to, penguin);
+                }
+            },
+        }
+    }
+
+    /// Returns the id of the penguin most recently moved to or placed on the given tile,
+    /// or None if no penguin has been there since the last one left it.
+    pub fn penguin_at(&self, tile: TileId) -> Option<PenguinId> {
+        self.penguins.get(&tile).copied()
+    }
+
+    /// Returns every tile the given penguin has occupied in the order it occupied
+    /// them, starting with the tile it was placed on. Returns an empty slice for
+    /// penguins that have not been placed.
+    pub fn trail(&self, penguin: PenguinId) -> &[TileId] {
+        self.trails.get(penguin.0).map_or(&[], |trail| trail.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::action::{ Move, Placement };
+    use crate::common::board::Board;
+    use crate::server::strategy::find_zigzag_placement;
+
+    /// Plays a full game on the given state, placing penguins in a zigzag and then making
+    /// the first valid move each turn. Returns every action taken along with the final state.
+    fn play_game(mut state: GameState) -> (Vec<Action>, GameState) {
+        let mut actions = vec![];
+        while !state.is_game_over() {
+            let action = if state.all_penguins_are_placed() {
+                Action::MovePenguin(state.get_valid_moves()[0])
+            } else {
+                Action::PlacePenguin(find_zigzag_placement(&state))
+            };
+            state.apply_action(action).unwrap();
+            actions.push(action);
+        }
+        (actions, state)
+    }
+
+    #[test]
+    fn test_replay_step() {
+        let initial_state = GameState::with_default_board(4, 4, 2);
+        let (actions, final_state) = play_game(initial_state.clone());
+        let total_actions = actions.len();
+
+        let mut replay = Replay::new(initial_state, actions);
+        let shared_state = replay.shared_state();
+
+        // Each step applies exactly one action
+        for ticks in 1 ..= 10 {
+            assert!(replay.step());
+            assert_eq!(replay.actions_applied(), ticks);
+            assert_eq!(shared_state.borrow().turn_number(), ticks);
+        }
+
+        while replay.step() {}
+        assert!(replay.is_finished());
+        assert_eq!(replay.actions_applied(), total_actions);
+        assert!(!replay.step());
+
+        let shared_state = shared_state.borrow();
+        assert_eq!(shared_state.board, final_state.board);
+        assert_eq!(shared_state.turn_number(), total_actions);
+        assert!(shared_state.is_game_over());
+    }
+
+    #[test]
+    fn test_penguin_trails() {
+        // 0   4   8   12
+        //   1   5   9   13
+        // 2   6   10  14
+        //   3   7   11  15
+        let placements = [0, 12, 1, 13, 2, 14, 3, 15].iter()
+            .map(|tile_id| Action::PlacePenguin(Placement::new(TileId(*tile_id))));
+
+        // Player 0's penguin on tile 1 moves twice
+        let moves = [(1, 4), (12, 9), (4, 5)].iter()
+            .map(|(from, to)| Action::MovePenguin(Move::new(TileId(*from), TileId(*to))));
+
+        let initial_state = GameState::new(Board::with_no_holes(4, 4, 2), 2);
+        let mut replay = Replay::new(initial_state, placements.chain(moves).collect());
+        for _ in 0 .. 8 {
+            assert!(replay.step());
+        }
+
+        // Penguins are identified in the order they were placed
+        let trails = replay.trails();
+        assert_eq!(trails.penguin_at(TileId(0)), Some(PenguinId(0)));
+        assert_eq!(trails.penguin_at(TileId(15)), Some(PenguinId(7)));
+        assert_eq!(trails.penguin_at(TileId(4)), None);
+        assert_eq!(trails.trail(PenguinId(1)), &[TileId(12)]);
+        assert!(trails.trail(PenguinId(8)).is_empty());
+
+        while replay.step() {}
+        let trails = replay.trails();
+        assert_eq!(trails.trail(PenguinId(2)), &[TileId(1), TileId(4), TileId(5)]);
+        assert_eq!(trails.trail(PenguinId(1)), &[TileId(12), TileId(9)]);
+        assert_eq!(trails.trail(PenguinId(0)), &[TileId(0)]);
+        assert_eq!(trails.penguin_at(TileId(5)), Some(PenguinId(2)));
+        assert_eq!(trails.penguin_at(TileId(1)), None);
+        assert_eq!(*trails, PenguinTrails::from_actions(&replay.actions));
+    }
+
+    #[test]
+    fn test_replay_stops_at_invalid_action() {
+        let initial_state = GameState::with_default_board(4, 4, 2);
+        let actions = vec![
+            Action::PlacePenguin(Placement::new(TileId(0))),
+            Action::PlacePenguin(Placement::new(TileId(0))),
+            Action::PlacePenguin(Placement::new(TileId(1))),
+        ];
+
+        let mut replay = Replay::new(initial_state, actions);
+        assert!(replay.step());
+        assert!(!replay.step());
+        assert!(!replay.step());
+        assert_eq!(replay.actions_applied(), 1);
+        assert!(!replay.is_finished());
+        assert_eq!(replay.shared_state().borrow().get_occupied_tiles().len(), 1);
+    }
+}
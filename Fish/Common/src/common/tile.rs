@@ -101,7 +101,7 @@ impl Tile {
 
     /// Mutable version of the above function. This returns a mutable reference to the Option itself
     /// rather than an Option<&mut TileId> so that the neighbor can be set to None when removing a tile.
-    fn get_neighbor_id_mut(&mut self, direction: Direction) -> &mut Option<TileId> {
+    pub fn get_neighbor_id_mut(&mut self, direction: Direction) -> &mut Option<TileId> {
         match direction {
             Direction::Northeast => &mut self.northeast,
             Direction::Northwest => &mut self.northwest,
@@ -64,21 +64,163 @@ pub fn all_max_by_key<I, T, K, F>(iter: I, mut f: F) -> std::vec::IntoIter<T> wh
     results.into_iter()
 }
 
+/// The longest try_with_timeout will sleep between attempts
+pub const MAX_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The first interval try_with_backoff sleeps for after a failed attempt
+const MIN_RETRY_INTERVAL: Duration = Duration::from_micros(100);
+
 /// Keep retrying the given function until it returns a Some(value).
 /// If such a value wasn't returned within the given timeout, return None.
+/// Waits up to MAX_RETRY_INTERVAL between attempts, see try_with_backoff.
 ///
 /// This expects the function to complete in a relatively short time. If
 /// the function runs for a long time, try_with_timeout will potentially
 /// block for longer than `timeout`
-pub fn try_with_timeout<F, U>(timeout: Duration, mut f: F) -> Option<U>
+pub fn try_with_timeout<F, U>(timeout: Duration, f: F) -> Option<U>
+    where F: FnMut() -> Option<U>
+{
+    try_with_backoff(timeout, MAX_RETRY_INTERVAL, f)
+}
+
+/// Keep retrying the given function until it returns a Some(value), returning
+/// None if no value was returned within the given timeout.
+///
+/// Rather than retrying in a busy loop, this sleeps between attempts. The sleep
+/// starts short so that values which arrive quickly are still returned promptly,
+/// then doubles after each failed attempt up to max_interval. It never sleeps
+/// past the timeout.
+pub fn try_with_backoff<F, U>(timeout: Duration, max_interval: Duration, mut f: F) -> Option<U>
     where F: FnMut() -> Option<U>
 {
     let start_time = Instant::now();
+    let mut interval = std::cmp::min(MIN_RETRY_INTERVAL, max_interval);
     loop {
-        match f() {
-            Some(value) => return Some(value),
-            None if start_time.elapsed() < timeout => continue,
-            _ => return None,
+        if let Some(value) = f() {
+            return Some(value);
+        }
+
+        let elapsed = start_time.elapsed();
+        if elapsed >= timeout {
+            return None;
+        }
+
+        std::thread::sleep(std::cmp::min(interval, timeout - elapsed));
+        interval = std::cmp::min(interval * 2, max_interval);
+    }
+}
+
+/// Advance the given seed state and return the next pseudo-random number from it.
+/// This is the splitmix64 generator: it is not cryptographically secure but is
+/// fast, small, and always yields the same sequence for the same starting seed.
+pub fn next_random(seed_state: &mut u64) -> u64 {
+    *seed_state = seed_state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *seed_state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Return a pseudo-random number in the range 0 .. bound, advancing seed_state.
+/// Calling this repeatedly with the same starting seed_state always yields the
+/// same sequence of numbers. Returns 0 if bound is 0.
+pub fn random_range(seed_state: &mut u64, bound: usize) -> usize {
+    if bound == 0 {
+        return 0;
+    }
+    (next_random(seed_state) % bound as u64) as usize
+}
+
+/// Shuffle the given slice in place. The same seed always produces the
+/// same permutation for slices of the same length.
+pub fn shuffle_with_seed<T>(items: &mut [T], seed: u64) {
+    let mut seed_state = seed;
+    for i in (1 .. items.len()).rev() {
+        let j = random_range(&mut seed_state, i + 1);
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_range() {
+        let mut seed_state = 42;
+        for _ in 0 .. 100 {
+            assert!(random_range(&mut seed_state, 7) < 7);
         }
+        assert_eq!(random_range(&mut seed_state, 0), 0);
+
+        let mut first = 5;
+        let mut second = 5;
+        let first_numbers: Vec<usize> = make_n(10, |_| random_range(&mut first, 1000));
+        let second_numbers: Vec<usize> = make_n(10, |_| random_range(&mut second, 1000));
+        assert_eq!(first_numbers, second_numbers);
+    }
+
+    #[test]
+    fn test_try_with_timeout_backs_off() {
+        let start_time = Instant::now();
+        let mut calls = 0;
+        let result = try_with_timeout(Duration::from_secs(1), || {
+            calls += 1;
+            if start_time.elapsed() >= Duration::from_millis(50) { Some(calls) } else { None }
+        });
+
+        // Returns within a retry interval or so of the value being ready
+        assert_eq!(result, Some(calls));
+        assert!(start_time.elapsed() < Duration::from_millis(50) + MAX_RETRY_INTERVAL * 5);
+
+        // A busy loop would have called f many thousands of times by now
+        assert!(calls < 100, "f was called {} times", calls);
+    }
+
+    #[test]
+    fn test_try_with_backoff_timeout() {
+        let start_time = Instant::now();
+        let result: Option<()> = try_with_backoff(Duration::from_millis(30), Duration::from_millis(5), || None);
+        assert_eq!(result, None);
+
+        let elapsed = start_time.elapsed();
+        assert!(elapsed >= Duration::from_millis(30));
+        assert!(elapsed < Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_shuffle_with_seed() {
+        let original: Vec<usize> = make_n(20, |i| i);
+
+        let mut first = original.clone();
+        let mut second = original.clone();
+        shuffle_with_seed(&mut first, 1234);
+        shuffle_with_seed(&mut second, 1234);
+        assert_eq!(first, second);
+
+        // Shuffling only reorders the elements
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, original);
+
+        // Different seeds generally give different permutations
+        let permutations: Vec<Vec<usize>> = make_n(10, |seed| {
+            let mut items = original.clone();
+            shuffle_with_seed(&mut items, seed as u64);
+            items
+        });
+        for i in 0 .. permutations.len() {
+            for j in i + 1 .. permutations.len() {
+                assert_ne!(permutations[i], permutations[j]);
+            }
+        }
+
+        // Empty and single element slices are left alone
+        let mut empty: Vec<usize> = vec![];
+        shuffle_with_seed(&mut empty, 1);
+        assert!(empty.is_empty());
+        let mut single = vec![3];
+        shuffle_with_seed(&mut single, 1);
+        assert_eq!(single, vec![3]);
     }
 }
@@ -3,9 +3,10 @@ mod common;
 mod server;
 
 use common::board::Board;
+use common::gamestate::GameState;
 use server::ai_client::AIClient;
-use server::referee::run_game;
-use server::client::Client;
+use server::referee::LiveGame;
+use server::client::{ Client, ClientWithId };
 
 use std::rc::Rc;
 use std::cell::RefCell;
@@ -15,10 +16,15 @@ fn main() {
         Box::new(AIClient::with_zigzag_minmax_strategy()) as Box<dyn Client>,
         Box::new(AIClient::with_zigzag_minmax_strategy()),
     ];
+    let clients: Vec<_> = players.into_iter().enumerate()
+        .map(|(id, player)| ClientWithId::new(id, player)).collect();
 
+    // The GUI draws the game as the referee plays it, one turn at a time
     let board = Board::with_no_holes(5, 3, 1);
-    let result = run_game(players, Some(board));
+    let state = Rc::new(RefCell::new(GameState::new(board.clone(), clients.len())));
+    let mut game = LiveGame::new(&clients, Some(board), state.clone());
 
-    let state = Rc::new(RefCell::new(result.final_state));
-    client::show_ui(state);
+    if let Err(error) = client::show_live_ui(state, None, &client::default_assets_dir(), move || game.do_turn()) {
+        eprintln!("Could not show the game: {:?}", error);
+    }
 }
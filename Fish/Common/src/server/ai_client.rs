@@ -4,26 +4,45 @@ use crate::common::gamestate::GameState;
 use crate::common::game_tree::GameTree;
 use crate::common::player::PlayerColor;
 use crate::common::action::{ Placement, Move, PlayerMove};
-use crate::server::strategy::{ Strategy, ZigZagMinMaxStrategy };
+use crate::server::strategy::{ Strategy, ZigZagMinMaxStrategy, AdaptiveDepthStrategy };
 use crate::server::client::Client;
 
+use std::io::Write;
 
 /// Represents the in-house AI client for the Fish game.
 /// This client is responsible for using their strategy
 /// to determine what action to take on their turn.
 pub struct AIClient {
     strategy: Box<dyn Strategy>,
+
+    /// Where each chosen move and its score is written when thinking aloud,
+    /// or None to choose moves silently. See AIClient::with_verbose.
+    verbose: Option<Box<dyn Write>>,
 }
 
 impl AIClient {
     /// Creates a new AI client using the given streams.
     pub fn new(strategy: Box<dyn Strategy>) -> AIClient {
-        AIClient { strategy }
+        AIClient { strategy, verbose: None }
     }
 
     /// Helper to create a client with the zigzag minmax strategy.
     pub fn with_zigzag_minmax_strategy() -> AIClient {
-        AIClient { strategy: Box::new(ZigZagMinMaxStrategy) }
+        AIClient::new(Box::new(ZigZagMinMaxStrategy))
+    }
+
+    /// Helper to create a client with the zigzag placement strategy whose minmax
+    /// lookahead deepens as the board empties. See strategy::adaptive_lookahead.
+    pub fn with_adaptive_depth() -> AIClient {
+        AIClient::new(Box::new(AdaptiveDepthStrategy))
+    }
+
+    /// Creates a client that "thinks aloud", writing a line to the given sink with each
+    /// move it chooses and the score its strategy gave that move (see Strategy::find_move_scored).
+    /// Useful for tutorials and for debugging strategies. Failing to write to the sink
+    /// does not affect the moves chosen.
+    pub fn with_verbose<W: Write + 'static>(strategy: Box<dyn Strategy>, sink: W) -> AIClient {
+        AIClient { strategy, verbose: Some(Box::new(sink)) }
     }
 }
 
@@ -46,7 +65,22 @@ impl Client for AIClient {
 
     fn get_move(&mut self, gamestate: &GameState, _previous: &[PlayerMove]) -> Option<Move> {
         let mut gametree = GameTree::new(gamestate);
-        Some(self.strategy.find_move(&mut gametree))
+
+        match &mut self.verbose {
+            Some(sink) => {
+                let (move_, score) = self.strategy.find_move_scored(&mut gametree);
+                match score {
+                    Some(score) => writeln!(sink, "chose {:?} with score {}", move_, score).ok(),
+                    None => writeln!(sink, "chose {:?}", move_).ok(),
+                };
+                Some(move_)
+            },
+            None => Some(self.strategy.find_move(&mut gametree)),
+        }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.strategy.reseed(seed)
     }
 }
 
@@ -57,7 +91,8 @@ mod tests {
     use crate::common::tile::TileId;
     use crate::common::action::Placement;
     use crate::common::gamestate::GameState;
-    use crate::server::strategy::{ tests::take_zigzag_placement, ZigZagMinMaxStrategy };
+    use crate::server::strategy::{ ZigZagMinMaxStrategy, DeadEndAvoidingStrategy, find_minmax_move_scored };
+    use crate::server::client::tests::SharedLog;
 
     #[test]
     fn test_take_turn_placement() {
@@ -73,11 +108,41 @@ mod tests {
 
         let mut state = GameState::with_default_board(3, 5, 2);
 
-        while !state.all_penguins_are_placed() {
-            take_zigzag_placement(&mut state);
-        }
+        state.place_all_zigzag();
 
         let action = player.get_move(&state, &[]);
         assert_eq!(action.unwrap().to, TileId(2));
     }
+
+    #[test]
+    fn test_verbose_move() {
+        let log = SharedLog::default();
+        let mut player = AIClient::with_verbose(Box::new(ZigZagMinMaxStrategy), log.clone());
+
+        let mut state = GameState::with_default_board(3, 5, 2);
+        state.place_all_zigzag();
+
+        let action = player.get_move(&state, &[]).unwrap();
+        let (expected, score) = find_minmax_move_scored(&mut GameTree::new(&state), 2);
+        assert_eq!(action, expected);
+        assert_eq!(log.contents(), format!("chose {:?} with score {}\n", action, score));
+
+        // Strategies that don't score their moves only report the move
+        let log = SharedLog::default();
+        let mut player = AIClient::with_verbose(Box::new(DeadEndAvoidingStrategy { lookahead: 1 }), log.clone());
+        let action = player.get_move(&state, &[]).unwrap();
+        assert_eq!(log.contents(), format!("chose {:?}\n", action));
+    }
+
+    #[test]
+    fn test_adaptive_depth_takes_valid_move() {
+        let mut player = AIClient::with_adaptive_depth();
+
+        let mut state = GameState::with_default_board(3, 5, 2);
+
+        state.place_all_zigzag();
+
+        let action = player.get_move(&state, &[]).unwrap();
+        assert!(state.get_valid_moves().contains(&action));
+    }
 }
@@ -1,8 +1,10 @@
 use crate::common::action::{ Placement, Move, PlayerMove };
 use crate::common::gamestate::GameState;
 use crate::common::player::{ PlayerColor, PlayerId };
+use crate::server::referee::KickReason;
 
 use std::cell::RefCell;
+use std::io::Write;
 use std::rc::Rc;
 
 /// Represents a Client that can interact with a Fish tournament and play in Fish games.
@@ -20,6 +22,23 @@ pub trait Client {
     fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement>;
     /// Gets a move from a client
     fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move>;
+
+    /// Returns why the most recent call to this client returned None, if the client
+    /// can tell. The referee reports this as the reason the client was kicked, falling
+    /// back to KickReason::NoResponse when None is returned here.
+    fn last_failure(&self) -> Option<KickReason> {
+        None
+    }
+
+    /// Called before get_move when the server listed the moves it considers valid
+    /// for this turn, so that clients can avoid making an illegal move if their own
+    /// view of the game has diverged from the server's.
+    fn receive_valid_moves(&mut self, _valid_moves: &[Move]) {}
+
+    /// Called before a seeded game starts with a seed derived from the game's seed, see
+    /// referee::run_game_with_seed. Clients that make random choices should make them
+    /// from this seed so that the game can be reproduced. Other clients ignore it.
+    fn reseed(&mut self, _seed: u64) {}
 }
 
 /// Represents the client's connection info along with an
@@ -68,4 +87,146 @@ impl Client for Box<dyn Client> {
     fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
         self.as_mut().get_move(gamestate, previous)
     }
+
+    fn last_failure(&self) -> Option<KickReason> {
+        self.as_ref().last_failure()
+    }
+
+    fn receive_valid_moves(&mut self, valid_moves: &[Move]) {
+        self.as_mut().receive_valid_moves(valid_moves)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.as_mut().reseed(seed)
+    }
+}
+
+/// Wraps another Client, writing a line to the given log for each call made to
+/// it along with the inner client's result. Useful for seeing exactly what the
+/// referee or tournament manager asked of a misbehaving client.
+///
+/// Failing to write to the log does not affect the inner client's results.
+pub struct LoggingClient<C: Client, W: Write> {
+    inner: C,
+    log: W,
+}
+
+impl<C: Client, W: Write> LoggingClient<C, W> {
+    pub fn new(inner: C, log: W) -> LoggingClient<C, W> {
+        LoggingClient { inner, log }
+    }
+
+    /// Unwraps this LoggingClient, returning the inner client and log
+    pub fn into_inner(self) -> (C, W) {
+        (self.inner, self.log)
+    }
+}
+
+impl<C: Client, W: Write> Client for LoggingClient<C, W> {
+    fn tournament_starting(&mut self) -> Option<()> {
+        let result = self.inner.tournament_starting();
+        writeln!(self.log, "tournament_starting -> {:?}", result).ok();
+        result
+    }
+
+    fn tournament_ending(&mut self, won: bool) -> Option<()> {
+        let result = self.inner.tournament_ending(won);
+        writeln!(self.log, "tournament_ending(won: {}) -> {:?}", won, result).ok();
+        result
+    }
+
+    fn initialize_game(&mut self, initial_gamestate: &GameState, player_color: PlayerColor) -> Option<()> {
+        let result = self.inner.initialize_game(initial_gamestate, player_color);
+        writeln!(self.log, "initialize_game(color: {:?}) -> {:?}", player_color, result).ok();
+        result
+    }
+
+    fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
+        let result = self.inner.get_placement(gamestate);
+        writeln!(self.log, "get_placement -> {:?}", result).ok();
+        result
+    }
+
+    fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
+        let result = self.inner.get_move(gamestate, previous);
+        writeln!(self.log, "get_move(previous moves: {}) -> {:?}", previous.len(), result).ok();
+        result
+    }
+
+    fn last_failure(&self) -> Option<KickReason> {
+        self.inner.last_failure()
+    }
+
+    fn receive_valid_moves(&mut self, valid_moves: &[Move]) {
+        writeln!(self.log, "receive_valid_moves(count: {})", valid_moves.len()).ok();
+        self.inner.receive_valid_moves(valid_moves)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        writeln!(self.log, "reseed(seed: {})", seed).ok();
+        self.inner.reseed(seed)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::common::board::Board;
+    use crate::server::ai_client::AIClient;
+    use crate::server::referee::run_game;
+
+    /// A log that can still be read after the client writing to it is moved into a game
+    #[derive(Clone, Default)]
+    pub struct SharedLog(pub Rc<RefCell<Vec<u8>>>);
+
+    impl SharedLog {
+        /// Returns everything written to this log so far
+        pub fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_logging_client() {
+        let log = SharedLog::default();
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(LoggingClient::new(AIClient::with_zigzag_minmax_strategy(), log.clone())),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let board = Board::with_no_holes(3, 4, 1);
+        let result = run_game(players, Some(board));
+        assert!(result.final_state.is_game_over());
+
+        let log = log.contents();
+        assert_eq!(log.lines().collect::<Vec<_>>(), vec![
+            "initialize_game(color: red) -> Some(())",
+            "get_placement -> Some(Placement { tile_id: TileId(0) })",
+            "get_placement -> Some(Placement { tile_id: TileId(6) })",
+            "get_placement -> Some(Placement { tile_id: TileId(1) })",
+            "get_placement -> Some(Placement { tile_id: TileId(7) })",
+            "get_move(previous moves: 0) -> Some(Move { from: TileId(0), to: TileId(2) })",
+            "get_move(previous moves: 1) -> Some(Move { from: TileId(6), to: TileId(8) })",
+        ]);
+    }
+
+    #[test]
+    fn test_logging_client_tournament() {
+        let mut client = LoggingClient::new(AIClient::with_zigzag_minmax_strategy(), vec![]);
+        assert_eq!(client.tournament_starting(), Some(()));
+        assert_eq!(client.tournament_ending(true), Some(()));
+
+        let (_, log) = client.into_inner();
+        assert_eq!(String::from_utf8(log).unwrap(), "tournament_starting -> Some(())\ntournament_ending(won: true) -> Some(())\n");
+    }
 }
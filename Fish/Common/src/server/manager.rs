@@ -1,20 +1,106 @@
 //! This file contains the interface for the Tournament Manager,
 //! which sets up games for and runs an entire tournament.
 use crate::server::referee;
-use crate::server::referee::ClientStatus;
+use crate::server::referee::{ ClientStatus, GameResult, GameSeeds };
 use crate::server::client::{ Client, ClientWithId };
 use crate::common::gamestate;
+use crate::common::gamestate::GameState;
 use crate::common::board::Board;
 use crate::common::util;
 use crate::common::player::PlayerId;
 
-use std::collections::BTreeMap;
+use std::collections::{ BTreeMap, BTreeSet };
 
 /// Represents a single game within a bracket, with each client in the Vec
 /// being a client in the game. The order of this grouping will be the same
-/// as the turn order in the resulting game.
+/// as the turn order in the resulting game, rotated by the round number.
+/// See run_round.
 type PlayerGrouping = Vec<ClientWithId>;
 
+/// The minimum and maximum number of players in each game of a tournament.
+/// Defaults to gamestate::MIN_PLAYERS_PER_GAME and gamestate::MAX_PLAYERS_PER_GAME.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlayersPerGame {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Default for PlayersPerGame {
+    fn default() -> PlayersPerGame {
+        PlayersPerGame { min: gamestate::MIN_PLAYERS_PER_GAME, max: gamestate::MAX_PLAYERS_PER_GAME }
+    }
+}
+
+/// The outcome of a tournament for each client, in the same order as the given clients list.
+/// See run_tournament_with_fish_totals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TournamentResult {
+    /// Whether each client Won, Lost, or was Kicked, as returned by run_tournament
+    pub statuses: Vec<ClientStatus>,
+
+    /// The number of fish each client collected across every game they played
+    /// without being kicked
+    pub fish_totals: Vec<usize>,
+}
+
+impl TournamentResult {
+    /// Picks a single champion from the clients who Won, see pick_champion.
+    /// Returns the index of the champion in statuses, or None if no client Won.
+    pub fn champion(&self, tie_break: TieBreak) -> Option<usize> {
+        pick_champion(&self.statuses, &self.fish_totals, tie_break)
+    }
+}
+
+/// The outcome of a tournament run by run_seeded_tournament, along with
+/// everything needed to reproduce each of its games.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentRecord {
+    /// The status and fish collected of each client, as in run_tournament_with_fish_totals
+    pub result: TournamentResult,
+
+    /// The seed every game's seeds were drawn from
+    pub seed: u64,
+
+    /// Every game played, in the order they were played
+    pub games: Vec<GameRecord>,
+}
+
+/// A single game of a tournament run by run_seeded_tournament. Running the clients with the
+/// given players' ids in the same order with referee::run_game_with_seeds, the tournament's board
+/// size, and these seeds reproduces final_state as long as the clients are deterministic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    /// The tournament round this game was played in, counting from 0
+    pub round: usize,
+
+    /// The players of this game in the order they were given to the referee,
+    /// before the turn order was shuffled
+    pub players: Vec<PlayerId>,
+
+    /// The seeds the game's board and turn order were created from
+    pub seeds: GameSeeds,
+
+    /// The state of the game once it finished
+    pub final_state: GameState,
+}
+
+/// How run_knockout_with_tie_break and pick_champion choose a single champion
+/// between players who are still tied once playing on would not separate them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The tied player who signed up first wins. This is the default used by run_knockout.
+    #[default]
+    OldestPlayer,
+
+    /// The tied player who collected the most fish across every game they played
+    /// in the tournament wins, falling back to the oldest of them if that is tied too.
+    MostFish,
+
+    /// A tied player is chosen pseudo-randomly with the given seed. The same seed
+    /// always picks the same player from the same tied players.
+    Seeded(u64),
+}
+
 /// Represents one round of Games, either a Round containing one PlayerGrouping
 /// per Fish game to play, or an End, which represents the end of the whole tournament.
 enum Bracket {
@@ -43,25 +129,219 @@ enum Bracket {
 ///
 /// It is assumed that the given list of players should not have any
 /// Kicked clients.
+///
+/// These tournaments use no randomness: brackets are formed in the order clients
+/// are given and every game is played on the same board. See run_game. To vary the
+/// board between rounds, see run_tournament_with_board_for_round. To play on random
+/// boards in random turn orders while recording the seeds of each game, see
+/// run_seeded_tournament. Knockout tournaments may break ties with a seed instead,
+/// see run_knockout_with_tie_break and TieBreak::Seeded.
 pub fn run_tournament(clients: Vec<Box<dyn Client>>, board: Option<Board>) -> Vec<ClientStatus> {
+    run_tournament_with_players_per_game(clients, board, PlayersPerGame::default())
+}
+
+/// Runs a complete tournament as in run_tournament, additionally returning the fish each client
+/// collected across the tournament so that e.g. TieBreak::MostFish can pick a single champion
+/// from the winners with TournamentResult::champion.
+pub fn run_tournament_with_fish_totals(clients: Vec<Box<dyn Client>>, board: Option<Board>) -> TournamentResult {
+    run_tournament_with_boards(clients, &|_| board.clone(), PlayersPerGame::default())
+}
+
+/// Runs a complete tournament as in run_tournament, except every game has between
+/// players_per_game.min and players_per_game.max players, e.g. a minimum and maximum
+/// of 3 for a tournament of 3 player games wherever possible.
+///
+/// Panics if the minimum is greater than the maximum, or if either is outside the
+/// range of gamestate::MIN_PLAYERS_PER_GAME to gamestate::MAX_PLAYERS_PER_GAME.
+/// If a round's players cannot be divided into games of the allowed sizes, e.g. 4 players
+/// when every game must have exactly 3, some games of that round will have fewer or more
+/// players instead. See create_player_groupings.
+pub fn run_tournament_with_players_per_game(clients: Vec<Box<dyn Client>>, board: Option<Board>,
+    players_per_game: PlayersPerGame) -> Vec<ClientStatus>
+{
+    run_tournament_with_boards(clients, &|_| board.clone(), players_per_game).statuses
+}
+
+/// Runs a complete tournament as in run_tournament, except every game of each round
+/// is played on the board returned by board_for_round for that round, counting from 0.
+/// This allows e.g. shrinking the board as the field of players narrows. Each client
+/// sees its game's board in the state it is sent when the game starts.
+pub fn run_tournament_with_board_for_round(clients: Vec<Box<dyn Client>>,
+    board_for_round: impl Fn(usize) -> Board) -> Vec<ClientStatus>
+{
+    run_tournament_with_boards(clients, &|round| Some(board_for_round(round)), PlayersPerGame::default()).statuses
+}
+
+/// Runs a complete tournament as in run_tournament, except every game is played on a board
+/// with the given number of rows and columns created by Board::with_seed, in a turn order
+/// shuffled as in referee::run_game_with_seed. The seeds of each game are drawn from the given
+/// seed, so running the same deterministic clients with the same seed reproduces the tournament.
+///
+/// Returns the statuses and fish totals of each client along with the seeds and final state
+/// of every game, so that any single game may be reproduced on its own. See GameRecord.
+pub fn run_seeded_tournament(clients: Vec<Box<dyn Client>>, rows: u32, columns: u32, seed: u64) -> TournamentRecord {
     let mut results = BTreeMap::new();
+    let mut fish_totals = BTreeMap::new();
+    let mut games = vec![];
+    let mut seed_state = seed;
+    let clients = start_tournament(clients, &mut results);
+
+    run_tournament_rec(&clients, &mut |players, round| {
+        let seeds = GameSeeds { board: util::next_random(&mut seed_state), turn_order: util::next_random(&mut seed_state) };
+        let result = referee::run_game_with_seeds(players, rows, columns, seeds);
+        let players = players.iter().map(|client| client.id).collect();
+        games.push(GameRecord { round, players, seeds, final_state: result.final_state.clone() });
+        result
+    }, None, 0, PlayersPerGame::default(), &mut results, &mut fish_totals);
+
+    let result = finish_tournament(clients, &results, &fish_totals);
+    TournamentRecord { result, seed, games }
+}
 
-    let mut clients = clients.into_iter().enumerate().map(|(id, client)| {
-        // Clients win by default until they lose a game or are kicked.
-        // This means for the tournament of a single player, they win by default
-        // even though they played 0 games
-        results.insert(PlayerId(id), ClientStatus::Won);
-        ClientWithId::new(id, client)
-    }).collect::<Vec<_>>();
+/// Runs a complete tournament, playing each round on the board returned by board_for_round
+/// or the referee's default board if it returns None. See run_tournament_with_players_per_game.
+fn run_tournament_with_boards(clients: Vec<Box<dyn Client>>, board_for_round: &dyn Fn(usize) -> Option<Board>,
+    players_per_game: PlayersPerGame) -> TournamentResult
+{
+    let PlayersPerGame { min, max } = players_per_game;
+    assert!(gamestate::MIN_PLAYERS_PER_GAME <= min && min <= max && max <= gamestate::MAX_PLAYERS_PER_GAME,
+        "Invalid players per game: {:?}", players_per_game);
 
-    let clients = notify_tournament_started(&mut clients, &mut results);
+    let mut results = BTreeMap::new();
+    let mut fish_totals = BTreeMap::new();
+    let clients = start_tournament(clients, &mut results);
 
-    run_tournament_rec(&clients, board, None, &mut results);
-    let statuses = results.values().copied().collect();
+    run_tournament_rec(&clients, &mut |players, round| referee::run_game_shared(players, board_for_round(round)),
+        None, 0, players_per_game, &mut results, &mut fish_totals);
+
+    finish_tournament(clients, &results, &fish_totals)
+}
 
+/// Notifies the given clients that the tournament has finished, see notify_tournament_finished,
+/// and returns the final status and fish total of every client in the order they signed up.
+fn finish_tournament(clients: Vec<ClientWithId>, results: &BTreeMap<PlayerId, ClientStatus>,
+    fish_totals: &BTreeMap<PlayerId, usize>) -> TournamentResult
+{
+    // Results are keyed by id rather than by position in any game's turn order, and each
+    // client's id is its index in the given list, so they are already in the input order.
+    let statuses = notify_tournament_finished(clients, results.values().copied().collect());
+    let fish_totals = results.keys().map(|id| fish_totals.get(id).copied().unwrap_or(0)).collect();
+
+    TournamentResult { statuses, fish_totals }
+}
+
+/// Runs a complete knockout tournament with the given clients which, unlike
+/// run_tournament, keeps running rounds until a single champion remains.
+///
+/// Players are divided into games each round in the same way as run_tournament.
+/// If the final game of a round ends in a tie, the tied winners play each other
+/// in a playoff game. If a round makes no progress because every player in it
+/// won (e.g. a playoff that ties again), the tie is broken in favor of the
+/// oldest remaining player since playing the same players again would repeat
+/// the same outcome. See run_knockout_with_tie_break to break ties another way.
+///
+/// Returns the list of statuses for each client in the same order as the given
+/// clients list. Exactly one client will have Won unless every client was
+/// kicked or the champion failed to accept the tournament ending message.
+pub fn run_knockout(clients: Vec<Box<dyn Client>>, board: Option<Board>) -> Vec<ClientStatus> {
+    run_knockout_with_tie_break(clients, board, TieBreak::default())
+}
+
+/// Runs a complete knockout tournament as in run_knockout, except a round where every
+/// player won is decided by the given TieBreak rather than by age.
+pub fn run_knockout_with_tie_break(clients: Vec<Box<dyn Client>>, board: Option<Board>, tie_break: TieBreak) -> Vec<ClientStatus> {
+    let mut results = BTreeMap::new();
+    let mut fish_totals = BTreeMap::new();
+    let clients = start_tournament(clients, &mut results);
+    let mut remaining = clients.clone();
+    let mut round = 0;
+
+    while remaining.len() >= gamestate::MIN_PLAYERS_PER_GAME {
+        let groups = create_player_groupings(&remaining, PlayersPerGame::default());
+        let winners = run_round(groups, round, &mut |players, _| referee::run_game_shared(players, board.clone()),
+            &mut results, &mut fish_totals);
+        round += 1;
+
+        if winners.len() == remaining.len() {
+            let champion = break_tie(&player_ids(&winners), tie_break, &fish_totals);
+            for tied_player in winners.iter().filter(|player| Some(player.id) != champion) {
+                results.insert(tied_player.id, ClientStatus::Lost);
+            }
+            remaining = winners.into_iter().filter(|player| Some(player.id) == champion).collect();
+        } else {
+            remaining = winners;
+        }
+    }
+
+    let statuses = results.values().copied().collect();
     notify_tournament_finished(clients, statuses)
 }
 
+/// Picks a single champion from a tournament's statuses, e.g. to report one winner of a
+/// run_tournament that ended with several. fish_totals is the number of fish each client
+/// collected across the tournament, in the same order as statuses; a missing entry
+/// counts as 0 fish. Only clients who Won are considered, and ties between them are
+/// broken with the given TieBreak.
+///
+/// Returns the index of the champion in statuses, or None if no client Won.
+pub fn pick_champion(statuses: &[ClientStatus], fish_totals: &[usize], tie_break: TieBreak) -> Option<usize> {
+    let winners = statuses.iter().enumerate()
+        .filter(|(_, status)| **status == ClientStatus::Won)
+        .map(|(index, _)| PlayerId(index))
+        .collect();
+
+    let fish_totals = fish_totals.iter().enumerate()
+        .map(|(index, fish)| (PlayerId(index), *fish))
+        .collect();
+
+    break_tie(&winners, tie_break, &fish_totals).map(|PlayerId(index)| index)
+}
+
+/// Chooses one of the given tied players using the given TieBreak. Player ids are
+/// assigned in signup order, so the smallest id is the oldest player.
+/// Returns None if there are no tied players.
+fn break_tie(tied: &BTreeSet<PlayerId>, tie_break: TieBreak, fish_totals: &BTreeMap<PlayerId, usize>) -> Option<PlayerId> {
+    match tie_break {
+        TieBreak::OldestPlayer => tied.iter().next().copied(),
+        TieBreak::MostFish => {
+            let fish = |id: &PlayerId| fish_totals.get(id).copied().unwrap_or(0);
+            util::all_max_by_key(tied.iter().copied(), fish).next()
+        },
+        TieBreak::Seeded(seed) => {
+            let mut tied: Vec<_> = tied.iter().copied().collect();
+            util::shuffle_with_seed(&mut tied, seed);
+            tied.first().copied()
+        },
+    }
+}
+
+/// Compares the statuses of two tournaments run with the same clients, e.g. before and
+/// after changing a strategy, returning (index, status in a, status in b) for each client
+/// whose outcome differs. The index is the client's position in both lists.
+///
+/// If one list is longer, the extra clients are ignored since there is nothing to compare them to.
+pub fn diff_results(a: &[ClientStatus], b: &[ClientStatus]) -> Vec<(usize, ClientStatus, ClientStatus)> {
+    a.iter().zip(b.iter()).enumerate()
+        .filter(|(_, (status_a, status_b))| status_a != status_b)
+        .map(|(index, (status_a, status_b))| (index, *status_a, *status_b))
+        .collect()
+}
+
+/// Assigns each of the given clients an id in the order they are given, then
+/// notifies each that the tournament has started. Every client is recorded in the
+/// results as having Won until they lose a game or are kicked. This means for the
+/// tournament of a single player, they win by default even though they played 0 games.
+///
+/// Returns the clients that successfully accepted the starting message.
+fn start_tournament(clients: Vec<Box<dyn Client>>, results: &mut BTreeMap<PlayerId, ClientStatus>) -> Vec<ClientWithId> {
+    let clients = clients.into_iter().enumerate().map(|(id, client)| {
+        results.insert(PlayerId(id), ClientStatus::Won);
+        ClientWithId::new(id, client)
+    }).collect::<Vec<_>>();
+
+    notify_tournament_started(&clients, results)
+}
+
 /// Notify the given clients that the tournament has started. If a client fails to accept the message,
 /// then their status is changed to Kicked. The players that successfully accepted the starting
 /// message are returned in the same order.
@@ -92,16 +372,18 @@ fn notify_tournament_finished(clients: Vec<ClientWithId>, mut statuses: Vec<Clie
     statuses
 }
 
-/// Performs the recursion for run_tournament, keeping track of the number of winners
-/// of the previous game which is used to end the game early if it is ever equal to the
-/// number of players who won the most recent game.
-fn run_tournament_rec(clients: &[ClientWithId], board: Option<Board>,
-    previous_winner_count: Option<usize>, results: &mut BTreeMap<PlayerId, ClientStatus>)
+/// Performs the recursion for run_tournament, keeping track of the players of the
+/// previous round which is used to end the game early if they are ever the same as
+/// the players who won the most recent round. Each game is played by play_game,
+/// see run_round.
+fn run_tournament_rec(clients: &[ClientWithId], play_game: &mut dyn FnMut(&[ClientWithId], usize) -> GameResult,
+    previous_players: Option<&[ClientWithId]>, round: usize, players_per_game: PlayersPerGame,
+    results: &mut BTreeMap<PlayerId, ClientStatus>, fish_totals: &mut BTreeMap<PlayerId, usize>)
 {
-    match next_bracket(clients, previous_winner_count) {
+    match next_bracket(clients, previous_players, players_per_game) {
         Bracket::Round { games } => {
-            let winners = run_round(games, board.clone(), results);
-            run_tournament_rec(&winners, board, Some(clients.len()), results);
+            let winners = run_round(games, round, play_game, results, fish_totals);
+            run_tournament_rec(&winners, play_game, Some(clients), round + 1, players_per_game, results, fish_totals);
         },
         Bracket::End => (),
     }
@@ -109,17 +391,33 @@ fn run_tournament_rec(clients: &[ClientWithId], board: Option<Board>,
 
 /// Runs a single tournament round, returning the winning players.
 /// The ordering of players returned does not change - save for the
-/// players that were removed because they lost or cheated.
-fn run_round(groups: Vec<PlayerGrouping>, board: Option<Board>,
-    results: &mut BTreeMap<PlayerId, ClientStatus>) -> Vec<ClientWithId>
+/// players that were removed because they lost or cheated. The fish each
+/// player collected this round are added to their entry in fish_totals.
+///
+/// Going first can be an advantage, so each game's turn order is its grouping
+/// rotated left by the round number (counting from 0). This way the same player
+/// is not always first when they play in multiple rounds. Each game is played by
+/// calling play_game with the clients in this turn order and the round number.
+fn run_round(groups: Vec<PlayerGrouping>, round: usize, play_game: &mut dyn FnMut(&[ClientWithId], usize) -> GameResult,
+    results: &mut BTreeMap<PlayerId, ClientStatus>, fish_totals: &mut BTreeMap<PlayerId, usize>) -> Vec<ClientWithId>
 {
     let mut winners = vec![];
     for group in groups {
-        let game_results = referee::run_game_shared(&group, board.clone());
+        let turn_order = rotate_turn_order(&group, round);
+        let game_results = play_game(&turn_order, round);
+        let statuses: BTreeMap<_, _> = turn_order.iter().map(|client| client.id)
+            .zip(game_results.final_statuses)
+            .collect();
+
+        // Kicked players are removed from the game, losing the fish they collected
+        for (id, player) in game_results.final_state.players.iter() {
+            *fish_totals.entry(*id).or_default() += player.score;
+        }
 
         // Iterate through the result (Won | Lost | Kicked) of each client in the finished game
         // to update their overall tournament status
-        for (client, status) in group.iter().zip(game_results.final_statuses.into_iter()) {
+        for client in group.iter() {
+            let status = statuses[&client.id];
             results.insert(client.id, status);
             if status == ClientStatus::Won {
                 winners.push(client.clone());
@@ -129,6 +427,22 @@ fn run_round(groups: Vec<PlayerGrouping>, board: Option<Board>,
     winners
 }
 
+/// Returns the turn order for the given grouping in the given round: the grouping
+/// rotated left by the round number, wrapping around for rounds past the group size.
+fn rotate_turn_order(group: &[ClientWithId], round: usize) -> PlayerGrouping {
+    let mut turn_order = group.to_vec();
+    if !turn_order.is_empty() {
+        let len = turn_order.len();
+        turn_order.rotate_left(round % len);
+    }
+    turn_order
+}
+
+/// Returns the set of ids of the given clients.
+fn player_ids(clients: &[ClientWithId]) -> BTreeSet<PlayerId> {
+    clients.iter().map(|client| client.id).collect()
+}
+
 /// Allocate players to games and return a bracket representing the tournament round to be run.
 /// The allocation will assign players to games with the maximum number of players allowed for
 /// an individual game. In the case of remaining players, the list of allocated games will
@@ -137,60 +451,81 @@ fn run_round(groups: Vec<PlayerGrouping>, board: Option<Board>,
 ///
 /// It is assumed that the given slice of players is sorted in ascending order of age. If the number
 /// of player initially given is too small to create a game, Bracket::End is returned.
-fn next_bracket(clients: &[ClientWithId], previous_player_count: Option<usize>) -> Bracket {
-    if clients.len() < gamestate::MIN_PLAYERS_PER_GAME {
+fn next_bracket(clients: &[ClientWithId], previous_players: Option<&[ClientWithId]>, players_per_game: PlayersPerGame) -> Bracket {
+    if clients.len() < players_per_game.min {
         return Bracket::End;
     }
 
     // End the game if the winners are the same 2 rounds in a row. The only way this can
-    // happen is if all players tie. In this case, the number of winning players is equal
-    // to the length of clients list.
-    if previous_player_count.map_or(false, |count| count == clients.len()) {
+    // happen is if all players tie, in which case every player of the previous round is
+    // still in the clients list. Compare the players themselves rather than just the counts
+    // so that two different groups of the same size are never mistaken for one another.
+    if previous_players.map_or(false, |previous| player_ids(previous) == player_ids(clients)) {
         return Bracket::End;
     }
 
     // If we only have enough players for one game, that game should be the final tournament round.
-    if previous_player_count.map_or(false, |count| count <= gamestate::MAX_PLAYERS_PER_GAME) {
+    if previous_players.map_or(false, |previous| previous.len() <= players_per_game.max) {
         return Bracket::End;
     }
 
-    Bracket::Round { games: create_player_groupings(clients) }
+    let games = create_player_groupings(clients, players_per_game);
+    if games.is_empty() {
+        return Bracket::End;
+    }
+
+    Bracket::Round { games }
 }
 
 /// Create a list of player groupings to be used in a bracket. Players will be grouped into groups
-/// of size gamestate::MAX_PLAYERS_PER_GAME. This function will also handle the case where there are remaining
-/// players that cannot form a group of gamestate::MIN_PLAYERS_PER_GAME or more, in which case the allocated games
+/// of size players_per_game.max. This function will also handle the case where there are remaining
+/// players that cannot form a group of players_per_game.min or more, in which case the allocated games
 /// will be backtracked and players will be removed, one-by-one, to form games of size one less than the maximal
 /// number. This will occur until all players are assigned.
 ///
-/// The given list of players is assumed to be sorted in ascending age order. This function will panic if the initial list of players
-/// does not contain enough players to form a single game.
-fn create_player_groupings(clients: &[ClientWithId]) -> Vec<PlayerGrouping> {
+/// If the players cannot be divided into games of the allowed sizes at all, e.g. 7 players when every
+/// game must have exactly 3, the last games fall back to as few as gamestate::MIN_PLAYERS_PER_GAME players
+/// and, failing that, to any size a game allows so that a round is never abandoned halfway through.
+///
+/// The given list of players is assumed to be sorted in ascending age order. Returns no groupings if the
+/// list of players does not contain enough players to form a single game.
+fn create_player_groupings(clients: &[ClientWithId], players_per_game: PlayersPerGame) -> Vec<PlayerGrouping> {
+    let smaller_games = PlayersPerGame { min: gamestate::MIN_PLAYERS_PER_GAME, ..players_per_game };
+
+    try_create_player_groupings(clients, players_per_game)
+        .or_else(|| try_create_player_groupings(clients, smaller_games))
+        .or_else(|| try_create_player_groupings(clients, PlayersPerGame::default()))
+        .unwrap_or_default()
+}
+
+/// Groups players as in create_player_groupings, returning None if they
+/// cannot be divided into games of exactly the allowed sizes.
+fn try_create_player_groupings(clients: &[ClientWithId], players_per_game: PlayersPerGame) -> Option<Vec<PlayerGrouping>> {
     let mut groups = vec![];
-    let mut clients_per_game = gamestate::MAX_PLAYERS_PER_GAME;
+    let mut clients_per_game = players_per_game.max;
     let mut clients = clients.to_vec();
 
     while !clients.is_empty() {
         if clients.len() < clients_per_game {
-            if clients.len() >= gamestate::MIN_PLAYERS_PER_GAME {
+            if clients.len() >= players_per_game.min {
                 // Enough clients for one more game, push them all
                 groups.push(clients);
                 clients = vec![];
-            } else if !groups.is_empty() && clients_per_game > gamestate::MIN_PLAYERS_PER_GAME {
+            } else if !groups.is_empty() && clients_per_game > players_per_game.min {
                 // backtrack
                 clients.append(&mut groups.pop().unwrap());
                 clients_per_game -= 1;
             } else {
                 // Can't backtrack - not enough clients to form a single game or we're already
                 // at the minimum number of players
-                panic!("Not enough players to create 1 more group: #groups = {}, #remaining-players = {}", groups.len(), clients.len());
+                return None;
             }
         } else {
             groups.push(util::make_n(clients_per_game, |_| clients.remove(0)));
         }
     }
 
-    groups
+    Some(groups)
 }
 
 #[cfg(test)]
@@ -200,14 +535,17 @@ mod tests {
     use crate::common::tile::TileId;
     use crate::common::boardposn::BoardPosn;
     use crate::common::game_tree::GameTree;
-    use crate::common::action::{Placement, Move};
+    use crate::common::action::{ Placement, Move, PlayerMove };
+    use crate::common::player::PlayerColor;
     use crate::server::strategy::{ Strategy, find_minmax_move, find_zigzag_placement };
     use crate::server::referee::ClientStatus::*;
     use crate::server::{ ai_client::AIClient, remote_client::RemoteClient };
     use crate::server::signup;
     use crate::client::client_to_server_proxy::ClientToServerProxy;
 
+    use std::cell::RefCell;
     use std::net::{ TcpListener, TcpStream, Shutdown };
+    use std::rc::Rc;
     use std::time::Duration;
 
     /// A simple strategy for testing that works similarly to ZigZagMinMaxStrategy, except only has a lookahead of 1
@@ -258,7 +596,7 @@ mod tests {
         let board = Board::with_holes(3, 4, holes, 1);
         let statuses = run_tournament(players, Some(board));
         let mut winners = vec![Lost; 8];
-        winners[0] = Won;
+        winners[4] = Won;
         assert_eq!(statuses, winners);
     }
 
@@ -276,7 +614,8 @@ mod tests {
     ///
     /// Player 1 of each individual game will be the winner. This will correspond to players 1 and 5 of the tournament.
     ///
-    /// After the placement phase, the board at round 2 looks as follows:
+    /// In round 2 the turn order is rotated by 1, so player 5 of the tournament goes first as p1
+    /// and player 1 goes second as p2. After the placement phase, the board at round 2 looks as follows:
     /// p1    p2    p1    p2
     ///    p1    p2    p1    p2
     /// 1     x     x     x
@@ -286,7 +625,7 @@ mod tests {
     ///    x     p2    p1    p2
     /// p1     x     x     x
     ///
-    /// Thus, player 1 of the tournament will be the winner.
+    /// Thus, player 5 of the tournament will be the winner.
     ///
     /// Each player uses a simple strategy, with a min-max lookahead of 1.
     #[test]
@@ -322,6 +661,11 @@ mod tests {
     /// Test the running of a single tournament round. The round is the same as the first round of
     /// `test_run_tournament`. As such, players with IDs 0 and 4 (i.e. the first player of each individual
     /// Fish game) will win, and all other players will lose.
+    /// Returns a function for run_round which plays every game on the given board
+    fn play_on(board: Board) -> impl FnMut(&[ClientWithId], usize) -> GameResult {
+        move |players, _| referee::run_game_shared(players, Some(board.clone()))
+    }
+
     #[test]
     fn test_run_round() {
         let player_grouping = vec![
@@ -333,13 +677,41 @@ mod tests {
         let board = Board::with_holes(3, 4, holes, 1);
         let mut results = BTreeMap::new();
 
-        let winners = run_round(player_grouping, Some(board), &mut results);
+        let winners = run_round(player_grouping, 0, &mut play_on(board), &mut results, &mut BTreeMap::new());
 
         assert_eq!(winners.len(), 2);
         assert_eq!(winners[0].id.0, 0);
         assert_eq!(winners[1].id.0, 4);
     }
 
+    /// Runs the same round as `test_run_round` as the second round of a tournament. Each game's
+    /// turn order is rotated by 1, so the second player of each grouping goes first and wins instead.
+    #[test]
+    fn test_run_round_rotates_turn_order() {
+        let player_grouping = vec![
+            util::make_n(4, |id| ClientWithId::new(id, make_simple_strategy_player())),
+            util::make_n(4, |id| ClientWithId::new(id + 4, make_simple_strategy_player())),
+        ];
+
+        let holes = vec![BoardPosn::from((1, 2)), BoardPosn::from((2, 2)), BoardPosn::from((3, 2))];
+        let board = Board::with_holes(3, 4, holes, 1);
+        let mut results = BTreeMap::new();
+
+        let winners = run_round(player_grouping, 1, &mut play_on(board), &mut results, &mut BTreeMap::new());
+
+        assert_eq!(winners.len(), 2);
+        assert_eq!(winners[0].id.0, 1);
+        assert_eq!(winners[1].id.0, 5);
+        assert_eq!(results[&PlayerId(0)], Lost);
+        assert_eq!(results[&PlayerId(4)], Lost);
+
+        let group: Vec<_> = util::make_n(3, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        let ids = |round| util::map_slice(&rotate_turn_order(&group, round), |client| client.id.0);
+        assert_eq!(ids(0), vec![0, 1, 2]);
+        assert_eq!(ids(1), vec![1, 2, 0]);
+        assert_eq!(ids(4), vec![1, 2, 0]);
+    }
+
     // Test that tournament clients can be notified of the tournament starting at the beginning of a
     // tournament. This test checks that players that fail to respond to the starting message will
     // have their status updated to be kicked from the tournament.
@@ -487,6 +859,262 @@ mod tests {
         assert_eq!(statuses, winners);
     }
 
+    /// Rerunning the same tournament should produce no differences, while a single
+    /// changed outcome is reported along with its index.
+    #[test]
+    fn test_diff_results() {
+        let board = Board::with_no_holes(5, 3, 1);
+        let first = run_tournament(util::make_n(4, |_| make_simple_strategy_player()), Some(board.clone()));
+        let second = run_tournament(util::make_n(4, |_| make_simple_strategy_player()), Some(board));
+        assert_eq!(first, vec![Won, Lost, Won, Won]);
+        assert_eq!(diff_results(&first, &second), vec![]);
+
+        let changed = vec![Won, Lost, Kicked, Won];
+        assert_eq!(diff_results(&first, &changed), vec![(2, Won, Kicked)]);
+        assert_eq!(diff_results(&changed, &first), vec![(2, Kicked, Won)]);
+
+        // Clients without a counterpart in the other list are ignored
+        assert_eq!(diff_results(&[Won, Lost], &[Lost]), vec![(0, Won, Lost)]);
+        assert_eq!(diff_results(&[], &[Won]), vec![]);
+    }
+
+    /// Wraps a SimpleStrategy player, recording the board it is given at the start of each game.
+    struct BoardRecordingClient {
+        inner: Box<dyn Client>,
+        boards: Rc<RefCell<Vec<Board>>>,
+    }
+
+    impl Client for BoardRecordingClient {
+        fn tournament_starting(&mut self) -> Option<()> {
+            self.inner.tournament_starting()
+        }
+
+        fn tournament_ending(&mut self, won: bool) -> Option<()> {
+            self.inner.tournament_ending(won)
+        }
+
+        fn initialize_game(&mut self, initial_gamestate: &GameState, player_color: PlayerColor) -> Option<()> {
+            self.boards.borrow_mut().push(initial_gamestate.board.clone());
+            self.inner.initialize_game(initial_gamestate, player_color)
+        }
+
+        fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
+            self.inner.get_placement(gamestate)
+        }
+
+        fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
+            self.inner.get_move(gamestate, previous)
+        }
+    }
+
+    /// What a StatusRecordingClient was told over the course of a tournament
+    #[derive(Default)]
+    struct ClientRecord {
+        games_played: usize,
+        told_won: Option<bool>,
+    }
+
+    /// Wraps another client, recording how many games it played and what it was
+    /// told when the tournament ended.
+    struct StatusRecordingClient {
+        inner: Box<dyn Client>,
+        record: Rc<RefCell<ClientRecord>>,
+    }
+
+    impl Client for StatusRecordingClient {
+        fn tournament_starting(&mut self) -> Option<()> {
+            self.inner.tournament_starting()
+        }
+
+        fn tournament_ending(&mut self, won: bool) -> Option<()> {
+            self.record.borrow_mut().told_won = Some(won);
+            self.inner.tournament_ending(won)
+        }
+
+        fn initialize_game(&mut self, initial_gamestate: &GameState, player_color: PlayerColor) -> Option<()> {
+            self.record.borrow_mut().games_played += 1;
+            self.inner.initialize_game(initial_gamestate, player_color)
+        }
+
+        fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
+            self.inner.get_placement(gamestate)
+        }
+
+        fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
+            self.inner.get_move(gamestate, previous)
+        }
+    }
+
+    /// Runs a tournament of 9 players, which cannot be divided into games of 4 and so is
+    /// backtracked into games of 4, 3, and 2 players. Each game's turn order is also rotated
+    /// every round. Despite the reshuffling, each status returned must belong to the client
+    /// at the same index: the cheaters at indices 2 and 7 are the only ones Kicked, and every
+    /// client was told it won exactly when its status is Won.
+    #[test]
+    fn test_statuses_match_input_order_after_backtracking() {
+        let cheaters = [2, 7];
+        let records: Vec<Rc<RefCell<ClientRecord>>> = util::make_n(9, |_| Default::default());
+        let players = util::make_n(9, |index| -> Box<dyn Client> {
+            let inner = if cheaters.contains(&index) { make_cheating_player() } else { make_simple_strategy_player() };
+            Box::new(StatusRecordingClient { inner, record: records[index].clone() })
+        });
+
+        let clients: Vec<_> = util::make_n(9, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        let sizes = util::map_slice(&create_player_groupings(&clients, PlayersPerGame::default()), |game| game.len());
+        assert_eq!(sizes, vec![4, 3, 2]);
+
+        let statuses = run_tournament(players, Some(Board::with_no_holes(4, 5, 1)));
+        assert_eq!(statuses.len(), 9);
+
+        for (index, (status, record)) in statuses.iter().zip(records.iter()).enumerate() {
+            let record = record.borrow();
+            assert_eq!(*status == Kicked, cheaters.contains(&index), "client {} was {:?}", index, status);
+            assert_eq!(record.told_won, Some(*status == Won), "client {} was {:?}", index, status);
+        }
+
+        // More than one round was played, with some players playing in several
+        assert!(statuses.contains(&Won));
+        assert!(records.iter().any(|record| record.borrow().games_played > 1));
+    }
+
+    /// Groupings need not be in id order, e.g. after backtracking moves players between
+    /// games, and turn orders are rotated each round. Results must still be recorded
+    /// under each client's own id.
+    #[test]
+    fn test_run_round_records_results_by_id() {
+        let grouping = vec![
+            ClientWithId::new(5, make_simple_strategy_player()),
+            ClientWithId::new(1, make_cheating_player()),
+            ClientWithId::new(3, make_simple_strategy_player()),
+        ];
+
+        for round in 0 .. 3 {
+            let mut results = BTreeMap::new();
+            let winners = run_round(vec![grouping.clone()], round, &mut play_on(Board::with_no_holes(4, 5, 1)), &mut results, &mut BTreeMap::new());
+
+            assert_eq!(results.keys().copied().collect::<Vec<_>>(), vec![PlayerId(1), PlayerId(3), PlayerId(5)]);
+            assert_eq!(results[&PlayerId(1)], Kicked);
+            assert!(winners.iter().all(|winner| results[&winner.id] == Won));
+            assert!(!winners.is_empty());
+        }
+    }
+
+    /// Runs the same 8 player tournament as run_tournament_with_players, except the final
+    /// round is played on a smaller board without holes. All 8 players start on the first
+    /// round's board and the 2 finalists then receive the smaller board.
+    #[test]
+    fn test_run_tournament_with_board_for_round() {
+        let boards = Rc::new(RefCell::new(vec![]));
+        let players = util::make_n(8, |_| -> Box<dyn Client> {
+            Box::new(BoardRecordingClient { inner: make_simple_strategy_player(), boards: boards.clone() })
+        });
+
+        let holes = vec![BoardPosn::from((1, 2)), BoardPosn::from((2, 2)), BoardPosn::from((3, 2))];
+        let first_board = Board::with_holes(3, 4, holes, 1);
+        let final_board = Board::with_no_holes(3, 3, 1);
+
+        let statuses = run_tournament_with_board_for_round(players, |round| {
+            if round == 0 { first_board.clone() } else { final_board.clone() }
+        });
+        assert_eq!(statuses.len(), 8);
+
+        let boards = util::map_slice(&boards.borrow(), |board: &Board| board.to_fish_grid());
+        let mut expected = vec![first_board.to_fish_grid(); 8];
+        expected.extend(vec![final_board.to_fish_grid(); 2]);
+        assert_eq!(boards, expected);
+    }
+
+    /// Run a knockout tournament where the first game ties between players 1 and 4. The tied
+    /// players then play a playoff game on the same board. Being the second round, the playoff's
+    /// turn order is rotated so player 4 goes first, and wins outright.
+    #[test]
+    fn test_knockout_playoff_decides_champion() {
+        let players = util::make_n(4, |_| make_simple_strategy_player());
+
+        let board = Board::with_no_holes(5, 5, 1);
+        let statuses = run_knockout(players, Some(board));
+        assert_eq!(statuses, vec![Lost, Lost, Lost, Won]);
+    }
+
+    /// Run a knockout tournament on the same board as
+    /// `test_tournament_ends_when_partipant_count_is_small_enough_to_have_one_final_game`, where 3 players
+    /// tie in the first game. The playoff between them ties again, so the oldest player is crowned champion.
+    #[test]
+    fn test_knockout_breaks_repeated_tie_by_age() {
+        let players = util::make_n(4, |_| make_simple_strategy_player());
+
+        let board = Board::with_no_holes(5, 3, 1);
+        let statuses = run_knockout(players, Some(board));
+        assert_eq!(statuses, vec![Won, Lost, Lost, Lost]);
+    }
+
+    /// Run a knockout tournament where every player ties in the first round: 8 players
+    /// place all 8 penguins on a board of 8 tiles, so no one can move. A seeded tie-break
+    /// crowns the same champion every time, the first of the player ids shuffled by the seed.
+    #[test]
+    fn test_knockout_seeded_tie_break() {
+        let run = |tie_break| {
+            let players = util::make_n(8, |_| make_simple_strategy_player());
+            run_knockout_with_tie_break(players, Some(Board::with_no_holes(2, 4, 1)), tie_break)
+        };
+
+        for seed in [1, 2, 3] {
+            let mut ids: Vec<usize> = (0 .. 8).collect();
+            util::shuffle_with_seed(&mut ids, seed);
+
+            let mut expected = vec![Lost; 8];
+            expected[ids[0]] = Won;
+            assert_eq!(run(TieBreak::Seeded(seed)), expected);
+            assert_eq!(run(TieBreak::Seeded(seed)), expected);
+        }
+
+        // No one collected any fish, so MostFish falls back to the oldest player
+        let mut expected = vec![Lost; 8];
+        expected[0] = Won;
+        assert_eq!(run(TieBreak::MostFish), expected);
+        assert_eq!(run(TieBreak::OldestPlayer), expected);
+    }
+
+    #[test]
+    fn test_pick_champion() {
+        let statuses = [Won, Lost, Won, Kicked, Won];
+        assert_eq!(pick_champion(&statuses, &[1, 9, 3, 9, 3], TieBreak::OldestPlayer), Some(0));
+        assert_eq!(pick_champion(&statuses, &[1, 9, 3, 9, 3], TieBreak::MostFish), Some(2));
+        assert_eq!(pick_champion(&statuses, &[1, 9, 3, 9, 4], TieBreak::MostFish), Some(4));
+        assert_eq!(pick_champion(&statuses, &[], TieBreak::MostFish), Some(0));
+
+        let seeded = pick_champion(&statuses, &[], TieBreak::Seeded(42));
+        assert!(matches!(seeded, Some(0) | Some(2) | Some(4)));
+        assert_eq!(pick_champion(&statuses, &[], TieBreak::Seeded(42)), seeded);
+
+        assert_eq!(pick_champion(&[Lost, Kicked], &[5, 5], TieBreak::MostFish), None);
+    }
+
+    /// The fish totals of a tournament should let MostFish pick a single champion among its winners
+    #[test]
+    fn test_run_tournament_with_fish_totals() {
+        let board = Board::with_no_holes(4, 5, 2);
+        let result = run_tournament_with_fish_totals(util::make_n(6, |_| make_simple_strategy_player()), Some(board.clone()));
+        assert_eq!(result.statuses, run_tournament(util::make_n(6, |_| make_simple_strategy_player()), Some(board)));
+        assert_eq!(result.fish_totals.len(), 6);
+        assert!(result.fish_totals.iter().any(|fish| *fish > 0));
+
+        let champion = result.champion(TieBreak::MostFish).unwrap();
+        assert_eq!(result.statuses[champion], Won);
+        for (status, fish) in result.statuses.iter().zip(result.fish_totals.iter()) {
+            if *status == Won {
+                assert!(*fish <= result.fish_totals[champion]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_knockout_no_players() {
+        let board = Board::with_no_holes(2, 4, 1);
+        let statuses = run_knockout(vec![], Some(board));
+        assert_eq!(statuses, vec![]);
+    }
+
     /// Test a tournament where players need to be reallocated in order to ensure that
     /// there are enough players in each game. Assume a list of players [1, 2, 3, 4, 5].
     /// The final allocation of the games should be [1, 2, 3] and [4, 5].
@@ -495,7 +1123,7 @@ mod tests {
         // set up players
         let clients: Vec<_> = util::make_n(5, |id| ClientWithId::new(id, make_simple_strategy_player()));
 
-        match next_bracket(&clients, None) {
+        match next_bracket(&clients, None, PlayersPerGame::default()) {
             Bracket::Round { games } => {
                 assert_eq!(games.len(), 2);
                 assert_eq!(games[0].len(), 3);
@@ -507,18 +1135,83 @@ mod tests {
         }
     }
 
+    /// Running a seeded tournament again with the same seed should play the same games, and
+    /// each recorded game should be reproducible on its own from its recorded seeds.
+    #[test]
+    fn test_run_seeded_tournament_is_reproducible() {
+        let make_players = || util::make_n(6, |_| make_simple_strategy_player());
+        let record = run_seeded_tournament(make_players(), 3, 5, 42);
+        assert_eq!(record.seed, 42);
+        assert_eq!(record.result.statuses.len(), 6);
+        assert!(record.games.len() >= 2);
+        assert_eq!(record, run_seeded_tournament(make_players(), 3, 5, 42));
+
+        for game in record.games.iter() {
+            let clients: Vec<_> = game.players.iter().map(|id| ClientWithId::new(id.0, make_simple_strategy_player())).collect();
+            let result = referee::run_game_with_seeds(&clients, 3, 5, game.seeds);
+            assert_eq!(result.board_seed, Some(game.seeds.board));
+            assert_eq!(result.turn_order_seed, Some(game.seeds.turn_order));
+            assert!(result.final_state == game.final_state);
+            assert_eq!(result.final_state.board.to_fish_grid(), game.final_state.board.to_fish_grid());
+        }
+
+        let other = run_seeded_tournament(make_players(), 3, 5, 43);
+        assert_ne!(other.games[0].seeds, record.games[0].seeds);
+    }
+
+    /// With at most 3 players per game, 8 players are divided into games of 3, 3, and 2
+    /// rather than 2 games of 4.
+    #[test]
+    fn test_allocate_with_max_players_per_game() {
+        let clients: Vec<_> = util::make_n(8, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        let players_per_game = PlayersPerGame { min: 2, max: 3 };
+
+        match next_bracket(&clients, None, players_per_game) {
+            Bracket::Round { games } => {
+                let sizes = util::map_slice(&games, |game| game.len());
+                assert_eq!(sizes, vec![3, 3, 2]);
+            },
+            Bracket::End => panic!("Expected next_bracket to return Bracket::Round, found Bracket::End"),
+        }
+
+        // With exactly 3 players per game, 7 players can't be divided evenly
+        // so the last games fall back to 2 players each
+        let clients: Vec<_> = util::make_n(7, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        let sizes = util::map_slice(&create_player_groupings(&clients, PlayersPerGame { min: 3, max: 3 }), |game| game.len());
+        assert_eq!(sizes, vec![3, 2, 2]);
+
+        // With exactly 2 players per game, an odd number of players falls back to any game size
+        let clients: Vec<_> = util::make_n(5, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        let sizes = util::map_slice(&create_player_groupings(&clients, PlayersPerGame { min: 2, max: 2 }), |game| game.len());
+        assert_eq!(sizes, vec![3, 2]);
+
+        // 7 players can't play 3 player games, but the tournament still finishes
+        let board = Board::with_no_holes(3, 5, 1);
+        let players = util::make_n(7, |_| make_simple_strategy_player());
+        let statuses = run_tournament_with_players_per_game(players, Some(board), PlayersPerGame { min: 3, max: 3 });
+        assert_eq!(statuses.len(), 7);
+        assert!(statuses.contains(&Won));
+
+        // A whole tournament of 3 player games
+        let board = Board::with_no_holes(3, 5, 1);
+        let players = util::make_n(6, |_| make_simple_strategy_player());
+        let statuses = run_tournament_with_players_per_game(players, Some(board), PlayersPerGame { min: 3, max: 3 });
+        assert_eq!(statuses.len(), 6);
+        assert!(statuses.contains(&Won));
+    }
+
     #[test]
     fn test_allocate_ends_when_too_few_players_for_single_game() {
         let clients = vec![ClientWithId::new(0, make_simple_strategy_player())];
 
         // next_bracket of 1 player
-        match next_bracket(&clients, None) {
+        match next_bracket(&clients, None, PlayersPerGame::default()) {
             Bracket::Round { .. } => panic!("Expected next_bracket to return Bracket::End, found Bracket::Round"),
             Bracket::End => (),
         }
 
         // next_bracket of 0 players
-        match next_bracket(&[], None) {
+        match next_bracket(&[], None, PlayersPerGame::default()) {
             Bracket::Round { .. } => panic!("Expected next_bracket to return Bracket::End, found Bracket::Round"),
             Bracket::End => (),
         }
@@ -533,14 +1226,35 @@ mod tests {
         ];
 
         // First game with 3 players
-        match next_bracket(&clients, None) {
+        match next_bracket(&clients, None, PlayersPerGame::default()) {
             Bracket::Round { .. } => (),
             Bracket::End => panic!("Expected next_bracket to return Bracket::Round, found Bracket::End"),
         }
 
         // New round with 3 players, previous game had 4 total players.
         // Need to end the game because the previous round had enough players for only 1 final game.
-        match next_bracket(&clients, Some(4)) {
+        let previous_clients: Vec<_> = util::make_n(4, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        match next_bracket(&clients, Some(&previous_clients), PlayersPerGame::default()) {
+            Bracket::Round { .. } => panic!("Expected next_bracket to return Bracket::End, found Bracket::Round"),
+            Bracket::End => (),
+        }
+    }
+
+    /// The tournament should only end early when the exact same players make it
+    /// through two rounds in a row, not whenever two rounds have the same number of players.
+    #[test]
+    fn test_allocate_continues_when_same_count_but_different_winners() {
+        let previous_clients: Vec<_> = util::make_n(5, |id| ClientWithId::new(id, make_simple_strategy_player()));
+        let clients: Vec<_> = util::make_n(5, |id| ClientWithId::new(id + 1, make_simple_strategy_player()));
+
+        // Same number of players, but player 0 was replaced by player 5
+        match next_bracket(&clients, Some(&previous_clients), PlayersPerGame::default()) {
+            Bracket::Round { .. } => (),
+            Bracket::End => panic!("Expected next_bracket to return Bracket::Round, found Bracket::End"),
+        }
+
+        // The exact same players as the previous round
+        match next_bracket(&previous_clients, Some(&previous_clients), PlayersPerGame::default()) {
             Bracket::Round { .. } => panic!("Expected next_bracket to return Bracket::End, found Bracket::Round"),
             Bracket::End => (),
         }
@@ -1,10 +1,12 @@
 use crate::common::gamestate::GameState;
-use crate::common::action::{ PlayerMove, Placement, Move };
+use crate::common::game_tree::GameTree;
+use crate::common::action::{ PlayerMove, Placement, PlacementError, Move, Action };
 use crate::common::board::Board;
 use crate::common::player::{ Player, PlayerId, PlayerColor };
 use crate::common::penguin::Penguin;
 use crate::common::gamestate::PENGUIN_FACTOR;
 use crate::common::util;
+use crate::server::referee::KickReason;
 
 use serde::{ Serialize, Deserialize };
 use serde_json::json;
@@ -41,6 +43,30 @@ pub fn move_to_json_action(board: &Board, move_: Move) -> JSONAction {
     [ [from_position.y, from_position.x], [to_position.y, to_position.x] ]
 }
 
+/// Converts a json position to a Placement on the given board, or None if
+/// the position is out of bounds.
+pub fn json_position_to_placement(board: &Board, json_position: JSONPosition) -> Option<Placement> {
+    let tile_id = board.get_tile_id(json_position[1], json_position[0])?;
+    Some(Placement::new(tile_id))
+}
+
+/// Converts a json action to a Move on the given board, or None if either
+/// position is out of bounds.
+pub fn json_action_to_move(board: &Board, json_action: JSONAction) -> Option<Move> {
+    let from_tile_id = board.get_tile_id(json_action[0][1], json_action[0][0])?;
+    let to_tile_id = board.get_tile_id(json_action[1][1], json_action[1][0])?;
+    Some(Move::new(from_tile_id, to_tile_id))
+}
+
+/// Converts an Action on the given board to the message a client sends to take it:
+/// a Position for a placement or an Action for a move.
+pub fn action_to_message(board: &Board, action: Action) -> ClientToServerMessage {
+    match action {
+        Action::PlacePenguin(placement) => ClientToServerMessage::Position(placement_to_json_position(board, placement)),
+        Action::MovePenguin(move_) => ClientToServerMessage::Action(move_to_json_action(board, move_)),
+    }
+}
+
 /// All the types of client-server messages.
 ///
 /// This type is intended for deserializing messages
@@ -49,6 +75,10 @@ pub fn move_to_json_action(board: &Board, move_: Move) -> JSONAction {
 ///
 /// Most of these variants contain a single element tuple (T,)
 /// so that deserializing them from a 1-element array works.
+///
+/// TakeTurn's third argument lists every move the current player may make,
+/// as computed by the server. It is only sent to clients that opted in to it
+/// when signing up, see SignupMessage, so it is optional when deserializing.
 #[derive(Deserialize, Debug)]
 #[serde(tag = "name", content = "arguments")]
 #[serde(rename_all = "kebab-case")]
@@ -57,7 +87,7 @@ pub enum ServerToClientMessage {
     PlayingAs((PlayerColor,)),
     PlayingWith((Vec<PlayerColor>,)),
     Setup((JSONGameState,)),
-    TakeTurn(JSONGameState, Vec<JSONAction>),
+    TakeTurn(JSONGameState, Vec<JSONAction>, #[serde(default)] Option<Vec<JSONAction>>),
     End((bool,)),
 }
 
@@ -75,6 +105,107 @@ pub enum ClientToServerMessage {
     Action(JSONAction), 
 }
 
+impl ClientToServerMessage {
+    /// Converts this message to the Action it represents on the given board. Returns None
+    /// for a Void message or if any position in the message is out of bounds.
+    pub fn to_action(&self, board: &Board) -> Option<Action> {
+        match self {
+            ClientToServerMessage::Void(_) => None,
+            ClientToServerMessage::Position(json_position) =>
+                json_position_to_placement(board, *json_position).map(Action::PlacePenguin),
+            ClientToServerMessage::Action(json_action) =>
+                json_action_to_move(board, *json_action).map(Action::MovePenguin),
+        }
+    }
+
+    /// Interprets this message as a client's response to a message expecting no data,
+    /// e.g. a start message. Any other response is the wrong message type.
+    pub fn to_void(&self) -> Result<(), KickReason> {
+        match self {
+            ClientToServerMessage::Void(_) => Ok(()),
+            _ => Err(KickReason::WrongMessageType),
+        }
+    }
+
+    /// Interprets this message as a client's response to a setup message. A position that
+    /// is out of bounds on the given board is an illegal placement, and any response other
+    /// than a position is the wrong message type.
+    pub fn to_placement(&self, board: &Board) -> Result<Placement, KickReason> {
+        match self {
+            ClientToServerMessage::Position(json_position) =>
+                json_position_to_placement(board, *json_position)
+                    .ok_or(KickReason::IllegalPlacement(PlacementError::NoTile)),
+            _ => Err(KickReason::WrongMessageType),
+        }
+    }
+
+    /// Interprets this message as a client's response to a take-turn message. An action
+    /// with a position that is out of bounds on the given board is an illegal move, and
+    /// any response other than an action is the wrong message type.
+    pub fn to_move(&self, board: &Board) -> Result<Move, KickReason> {
+        match self {
+            ClientToServerMessage::Action(json_action) =>
+                json_action_to_move(board, *json_action).ok_or(KickReason::IllegalMove),
+            _ => Err(KickReason::WrongMessageType),
+        }
+    }
+}
+
+/// The message a client signs up with. In the published protocol this is only the
+/// client's name, but a client may instead send its name in an object along with
+/// the protocol extensions it opts in to, e.g. {"name":"bob","valid-moves":true}.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum SignupMessage {
+    Name(String),
+    WithExtensions {
+        name: String,
+
+        /// Whether take-turn messages should list the client's valid moves,
+        /// see take_turn_message_with_valid_moves
+        #[serde(rename = "valid-moves", default)]
+        valid_moves: bool,
+
+        /// Whether the client sends each response as [n, response], where n counts the
+        /// messages it has received from the server, including the one being responded to.
+        /// This lets the server ignore responses the client resent, see NumberedResponse.
+        #[serde(rename = "numbered-responses", default)]
+        numbered_responses: bool,
+    },
+}
+
+impl SignupMessage {
+    pub fn name(&self) -> &str {
+        match self {
+            SignupMessage::Name(name) => name,
+            SignupMessage::WithExtensions { name, .. } => name,
+        }
+    }
+
+    /// Whether the client opted in to valid moves being listed in its take-turn messages
+    pub fn wants_valid_moves(&self) -> bool {
+        match self {
+            SignupMessage::Name(_) => false,
+            SignupMessage::WithExtensions { valid_moves, .. } => *valid_moves,
+        }
+    }
+
+    /// Whether the client opted in to numbering its responses, see NumberedResponse
+    pub fn numbers_responses(&self) -> bool {
+        match self {
+            SignupMessage::Name(_) => false,
+            SignupMessage::WithExtensions { numbered_responses, .. } => *numbered_responses,
+        }
+    }
+}
+
+/// A response from a client that opted in to numbering its responses at signup: the
+/// number of messages the client had received from the server when it responded,
+/// followed by the response itself. A response with a lower number than the server
+/// expects answers an earlier message and was resent, so it can be ignored.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct NumberedResponse(pub usize, pub serde_json::Value);
+
 /// Return a start message encoded in json in a String
 pub fn start_message() -> String {
     serde_json::to_string(&json!([ "start", [true] ])).unwrap()
@@ -99,6 +230,16 @@ pub fn take_turn_message(state: &GameState, moves: &[PlayerMove]) -> String {
     serde_json::to_string(&json!([ "take-turn", [ state, actions ] ])).unwrap()
 }
 
+/// Returns a take-turn message as in take_turn_message, followed by the current
+/// player's valid moves. This is only sent to clients that opted in to it at signup.
+/// Assumes every penguin has been placed.
+pub fn take_turn_message_with_valid_moves(state: &GameState, moves: &[PlayerMove]) -> String {
+    let valid_moves = util::map_slice(&state.get_valid_moves(), |move_| move_to_json_action(&state.board, *move_));
+    let state = serialize_gamestate(state);
+    let actions = convert_to_json_actions(moves);
+    serde_json::to_string(&json!([ "take-turn", [ state, actions, valid_moves ] ])).unwrap()
+}
+
 pub fn end_message(winner: bool) -> String {
     serde_json::to_string(&json!([ "end", [ winner ] ])).unwrap()
 }
@@ -108,22 +249,6 @@ pub fn convert_to_json_actions(moves: &[PlayerMove]) -> Vec<JSONAction> {
     util::map_slice(moves, |move_| [ [move_.from.y, move_.from.x] , [move_.to.y, move_.to.x] ])
 }
 
-fn serialize_board(board: &Board) -> JSONBoard {
-    let mut rows = vec![];
-
-    for row_i in 0 .. board.height {
-        let mut new_row = vec![];
-        for col_i in 0 .. board.width {
-            let tile = board.get_tile(col_i, row_i);
-            let fish_count = tile.map_or(0, |tile| tile.fish_count as u32);
-            new_row.push(fish_count);
-        }
-        rows.push(new_row);
-    }
-
-    rows
-}
-
 fn serialize_player(player: &Player, board: &Board) -> JSONPlayer {
     let places = player.penguins.iter().filter_map(|penguin| {
         let tile_id = penguin.tile_id?;
@@ -138,25 +263,75 @@ fn serialize_player(player: &Player, board: &Board) -> JSONPlayer {
     }
 }
 
-fn serialize_players(gamestate: &GameState) -> Vec<JSONPlayer> {
-    let mut json_players = util::map_slice(&gamestate.turn_order, |id| {
-        serialize_player(&gamestate.players[id], &gamestate.board)
-    });
+/// The reason a GameState could not be serialized to the json wire format
+#[derive(Debug, PartialEq, Eq)]
+pub enum SerializeError {
+    /// The wire format identifies players by their PlayerColor, so a game with
+    /// more players than there are colors cannot be represented.
+    TooManyPlayers { players: usize, max: usize },
+}
+
+/// Serializes each player in turn order, starting with the current player.
+/// Returns an error rather than panicking if the game has more players than colors.
+fn serialize_players(gamestate: &GameState) -> Result<Vec<JSONPlayer>, SerializeError> {
+    let max = PlayerColor::iter().count();
+    if gamestate.turn_order.len() > max {
+        return Err(SerializeError::TooManyPlayers { players: gamestate.turn_order.len(), max });
+    }
+
+    let mut json_players: Vec<_> = gamestate.players_in_turn_order()
+        .map(|player| serialize_player(player, &gamestate.board))
+        .collect();
 
     // current player should be first
-    let current_turn_index = gamestate.turn_order.iter().position(|player| {
-        *player == gamestate.current_turn
-    }).unwrap();
+    let current_turn_index = gamestate.turn_index_of(gamestate.current_turn).unwrap();
 
     json_players.rotate_left(current_turn_index);
-    json_players
+    Ok(json_players)
 }
 
+/// Serializes the given GameState to the json wire format, or returns an error
+/// if it cannot be represented in it.
+pub fn try_serialize_gamestate(gamestate: &GameState) -> Result<JSONGameState, SerializeError> {
+    let board = gamestate.board.to_fish_grid();
+    let players = serialize_players(gamestate)?;
+
+    Ok(JSONGameState { players, board })
+}
+
+/// Serializes the given GameState to the json wire format.
+/// This will panic if the game has more players than there are PlayerColors,
+/// which GameState::new does not allow. Use try_serialize_gamestate otherwise.
 pub fn serialize_gamestate(gamestate: &GameState) -> JSONGameState {
-    let board = serialize_board(&gamestate.board);
-    let players = serialize_players(gamestate);
+    try_serialize_gamestate(gamestate)
+        .expect("GameState has more players than the json wire format can represent")
+}
 
-    JSONGameState { players, board }
+/// Exports the given GameTree to json for external analysis, evaluating it only as far
+/// as `depth` moves below its root. Each node has the form:
+///
+/// { "state": <state>, "game_over": <bool>, "children": [ { "move": <action>, "node": <node> }, ... ] }
+///
+/// where states and actions are serialized in the same format used in messages to
+/// remote clients. Children are sorted by their Move and nodes at the given depth
+/// have no children, even if the game is not over.
+pub fn serialize_game_tree(game: &mut GameTree, depth: usize) -> serde_json::Value {
+    let state = serialize_gamestate(game.get_state());
+    let game_over = game.is_game_over();
+
+    let mut children = if depth == 0 {
+        vec![]
+    } else {
+        game.map(|game| serialize_game_tree(game, depth - 1)).into_iter().collect::<Vec<_>>()
+    };
+    children.sort_by_key(|(move_, _)| *move_);
+
+    let board = &game.get_state().board;
+    let children = children.into_iter().map(|(move_, node)| {
+        json!({ "move": move_to_json_action(board, move_), "node": node })
+    }).collect::<Vec<_>>();
+
+    json!({ "state": state, "game_over": game_over, "children": children })
 }
 
 impl JSONGameState {
@@ -213,6 +388,7 @@ fn remove_kicked_players(gamestate: &mut GameState, json_players: &[JSONPlayer])
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::tile::TileId;
 
     #[test]
     fn test_start_message() {
@@ -261,6 +437,53 @@ mod tests {
             ));
     }
 
+    #[test]
+    fn test_take_turn_message_valid_moves() {
+        let mut state = GameState::with_default_board(4, 4, 2);
+        state.place_all_zigzag();
+
+        let message = serde_json::from_str(&take_turn_message_with_valid_moves(&state, &[])).unwrap();
+        let valid_moves = match message {
+            ServerToClientMessage::TakeTurn(_, _, Some(valid_moves)) => valid_moves,
+            other => panic!("Expected a take-turn message with valid moves, found {:?}", other),
+        };
+
+        let expected = util::map_slice(&state.get_valid_moves(), |move_| move_to_json_action(&state.board, *move_));
+        assert!(!expected.is_empty());
+        assert_eq!(valid_moves, expected);
+
+        // The valid moves are optional when deserializing
+        let message = take_turn_message(&state, &[]);
+        assert!(matches!(serde_json::from_str(&message).unwrap(), ServerToClientMessage::TakeTurn(_, _, None)));
+
+        let message = r#"["take-turn",[{"board":[[1]],"players":[]},[]]]"#;
+        match serde_json::from_str(message).unwrap() {
+            ServerToClientMessage::TakeTurn(_, history, None) => assert!(history.is_empty()),
+            other => panic!("Expected a take-turn message without valid moves, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_signup_message() {
+        let name: SignupMessage = serde_json::from_str(r#""bob""#).unwrap();
+        assert_eq!(name, SignupMessage::Name("bob".to_string()));
+        assert_eq!(name.name(), "bob");
+        assert!(!name.wants_valid_moves());
+
+        let extended: SignupMessage = serde_json::from_str(r#"{"name":"bob","valid-moves":true}"#).unwrap();
+        assert_eq!(extended.name(), "bob");
+        assert!(extended.wants_valid_moves());
+
+        let unknown_extensions: SignupMessage = serde_json::from_str(r#"{"name":"bob","colors":true}"#).unwrap();
+        assert!(!unknown_extensions.wants_valid_moves());
+        assert!(!unknown_extensions.numbers_responses());
+
+        let numbered: SignupMessage = serde_json::from_str(r#"{"name":"bob","numbered-responses":true}"#).unwrap();
+        assert!(numbered.numbers_responses());
+        assert!(!numbered.wants_valid_moves());
+        assert!(serde_json::from_str::<SignupMessage>("5").is_err());
+    }
+
     #[test]
     fn test_end_message() {
         assert_eq!(end_message(true), r#"["end",[true]]"#);
@@ -289,4 +512,125 @@ mod tests {
         assert_eq!(serde_json::from_str::<ClientToServerMessage>("[[2,1],[3,4]]").unwrap(), 
             ClientToServerMessage::Action([[2,1],[3,4]]));
     }
+
+    #[test]
+    fn test_client_to_server_to_placement() {
+        let board = Board::with_no_holes(3, 3, 1);
+        assert_eq!(ClientToServerMessage::Position([0, 0]).to_placement(&board), Ok(Placement::new(TileId(0))));
+        assert_eq!(ClientToServerMessage::Position([5, 0]).to_placement(&board),
+            Err(KickReason::IllegalPlacement(PlacementError::NoTile)));
+        assert_eq!(ClientToServerMessage::Void(JSONVoid::Void).to_placement(&board), Err(KickReason::WrongMessageType));
+    }
+
+    #[test]
+    fn test_action_wire_round_trip() {
+        // 0     3     6
+        //    1     4     7
+        // 2     5     8
+        let board = Board::with_no_holes(3, 3, 1);
+
+        let placement = Action::PlacePenguin(Placement::new(TileId(5)));
+        let message = action_to_message(&board, placement);
+        assert_eq!(message, ClientToServerMessage::Position([2, 1]));
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json, "[2,1]");
+        let message = serde_json::from_str::<ClientToServerMessage>(&json).unwrap();
+        assert_eq!(message.to_action(&board), Some(placement));
+
+        let move_ = Action::MovePenguin(Move::new(TileId(0), TileId(4)));
+        let message = action_to_message(&board, move_);
+        assert_eq!(message, ClientToServerMessage::Action([[0, 0], [1, 1]]));
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json, "[[0,0],[1,1]]");
+        let message = serde_json::from_str::<ClientToServerMessage>(&json).unwrap();
+        assert_eq!(message.to_action(&board), Some(move_));
+
+        // Void and out of bounds messages are not actions
+        assert_eq!(ClientToServerMessage::Void(JSONVoid::Void).to_action(&board), None);
+        assert_eq!(ClientToServerMessage::Position([3, 0]).to_action(&board), None);
+        assert_eq!(ClientToServerMessage::Action([[0, 0], [0, 3]]).to_action(&board), None);
+    }
+
+    /// Serializing rotates the players so the current player is first, and the
+    /// reconstructed state assigns PlayerIds in that rotated order. So players
+    /// are compared by color, which is preserved, rather than by PlayerId.
+    fn assert_same_players(original: &GameState, reconstructed: &GameState) {
+        assert_eq!(reconstructed.current_player().color, original.current_player().color);
+
+        let colors_in_turn_order = |state: &GameState| {
+            let mut colors: Vec<_> = state.players_in_turn_order().map(|player| player.color).collect();
+            let current_index = colors.iter().position(|color| *color == state.current_player().color).unwrap();
+            colors.rotate_left(current_index);
+            colors
+        };
+        assert_eq!(colors_in_turn_order(reconstructed), colors_in_turn_order(original));
+
+        for player in original.players.values() {
+            let other = reconstructed.players.values().find(|other| other.color == player.color).unwrap();
+            let placements = |player: &Player| util::map_slice(&player.penguins, |penguin| penguin.tile_id);
+            assert_eq!(placements(other), placements(player));
+            assert_eq!(other.score, player.score);
+        }
+    }
+
+    #[test]
+    fn test_gamestate_round_trip_mid_game() {
+        let mut state = GameState::new(Board::with_no_holes(4, 4, 2), 3);
+        while !state.all_penguins_are_placed() {
+            let placement = crate::server::strategy::find_zigzag_placement(&state);
+            state.place_avatar_for_current_player(placement).unwrap();
+        }
+
+        for _ in 0 .. 2 {
+            let move_ = state.get_valid_moves()[0];
+            state.move_avatar_for_current_player(move_).unwrap();
+        }
+        assert_eq!(state.current_player().color, PlayerColor::brown);
+
+        let json = serde_json::to_string(&serialize_gamestate(&state)).unwrap();
+        let reconstructed = serde_json::from_str::<JSONGameState>(&json).unwrap().to_common_game_state(3);
+        assert_same_players(&state, &reconstructed);
+        assert_eq!(reconstructed.board, state.board);
+
+        // After a player is kicked, the remaining players should still line up
+        state.remove_player(state.turn_order[1]);
+        let reconstructed = serialize_gamestate(&state).to_common_game_state(3);
+        assert_same_players(&state, &reconstructed);
+    }
+
+    #[test]
+    fn test_serialize_too_many_players() {
+        let board = Board::with_no_holes(4, 4, 1);
+        let four_players = GameState::new(board.clone(), 4);
+        assert_eq!(try_serialize_gamestate(&four_players).unwrap().players.len(), 4);
+
+        // Only 4 players can be given a color, so a 5th would have no way to be identified
+        let five_players = GameState::with_players(board, (0..5).map(PlayerId).collect());
+        assert_eq!(serialize_players(&five_players).unwrap_err(),
+            SerializeError::TooManyPlayers { players: 5, max: 4 });
+        assert_eq!(try_serialize_gamestate(&five_players).unwrap_err(),
+            SerializeError::TooManyPlayers { players: 5, max: 4 });
+    }
+
+    #[test]
+    fn test_serialize_game_tree() {
+        let mut state = GameState::with_default_board(5, 3, 2);
+        state.place_all_zigzag();
+        let mut game = GameTree::new(&state);
+        let valid_move_count = game.get_state().get_valid_moves().len();
+
+        let json = serialize_game_tree(&mut game, 1);
+        let children = json["children"].as_array().unwrap();
+        assert_eq!(children.len(), valid_move_count);
+        assert_eq!(json["game_over"], false);
+        assert_eq!(json["state"], serde_json::to_value(serialize_gamestate(game.get_state())).unwrap());
+
+        // Nodes at the depth limit are not expanded
+        assert!(children.iter().all(|child| child["node"]["children"].as_array().unwrap().is_empty()));
+
+        // Exporting to depth 0 only includes the root
+        assert!(serialize_game_tree(&mut game, 0)["children"].as_array().unwrap().is_empty());
+    }
 }
@@ -5,4 +5,5 @@ pub mod message;
 pub mod referee;
 pub mod remote_client;
 pub mod signup;
+pub mod stdio_client;
 pub mod strategy;
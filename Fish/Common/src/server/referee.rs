@@ -2,13 +2,28 @@
 //! which runs complete games of Fish. To do this, it starts and runs the
 //! game loop, sending the gamestate to all players each turn then retrieving
 //! a player's move and validating it until the game is over.
-use crate::common::action::PlayerMove;
+use crate::common::action::{ Action, ActionKind, PlacementError, PlayerMove };
 use crate::common::board::Board;
-use crate::common::gamestate::GameState;
-use crate::common::gamephase::GamePhase;
-use crate::common::game_tree::GameTree;
+use crate::common::gamestate::{ GameState, SharedGameState, TileRule };
+use crate::common::gamephase::{ GamePhase, StepOutcome };
 use crate::common::player::{ PlayerId, PlayerColor };
 use crate::server::client::{ Client, ClientWithId };
+use crate::server::ai_client::AIClient;
+use crate::server::strategy::Strategy;
+use crate::server::message::{ setup_message, take_turn_message };
+use crate::common::util;
+
+use serde::{ Serialize, Deserialize };
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::mpsc::Receiver;
+use std::time::{ Duration, Instant };
+
+/// How long the referee waits to send a message to an observer before giving up on
+/// that observer, so an observer that stops reading cannot stall the game.
+const OBSERVER_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// A referee is in charge of starting, running, and managing a game of fish.
 /// This entails looping until the game is over and on each turn sending the
@@ -22,10 +37,11 @@ use crate::server::client::{ Client, ClientWithId };
 /// 1. Send a well-formed but illegal placement to the referee
 /// 2. Send a well-formed but illegal move to the referee
 /// 3. Send non-well-formed JSON data to the Referee
-/// 4. [Future] Take more than 30 seconds to send their move on their turn
+/// 4. Take longer than the placement or move timeout, if set, to send their action
 struct Referee {
     /// Client input/output stream data, indexed on GameState's PlayerId.
-    /// This Vec is in turn_order for each player.
+    /// This Vec is in turn_order for each player, unless the turn order
+    /// was shuffled by a seed in which case it keeps the original order.
     clients: Vec<ClientWithId>,
 
     /// The state of current game, separated by the current phase it is in.
@@ -35,6 +51,58 @@ struct Referee {
     /// recent being last. Empty until the MovePenguins phase and cleared when
     /// a player is kicked.
     move_history: Vec<PlayerMove>,
+
+    /// The state of the game just before the first move in move_history. This is
+    /// the state when the MovingPenguins phase began, or just after the most recent
+    /// kick, so replaying move_history on top of it gives the current state.
+    history_start: GameState,
+
+    /// Streams of non-playing observers watching this game. Each observer
+    /// is sent the same setup/take-turn messages sent to the current player
+    /// but is never asked for an action. Observers that fail to receive a
+    /// message within OBSERVER_WRITE_TIMEOUT are dropped.
+    observers: Vec<TcpStream>,
+
+    /// Observers that join while the game is in progress. Each is sent enough
+    /// of the game so far to catch up before being added to observers.
+    late_observers: Option<Receiver<TcpStream>>,
+
+    /// The total wall-clock time each client has spent deciding on their
+    /// placements and moves throughout the game.
+    think_time: BTreeMap<PlayerId, Duration>,
+
+    /// Why each kicked client was kicked. Clients who were never kicked have no entry.
+    kick_reasons: BTreeMap<PlayerId, KickReason>,
+
+    /// The longest a client may take to return a placement before being kicked,
+    /// or None if placements may take any amount of time.
+    placement_timeout: Option<Duration>,
+
+    /// The longest a client may take to return a move before being kicked,
+    /// or None if moves may take any amount of time.
+    move_timeout: Option<Duration>,
+
+    /// The most moves that may be made before the game is ended as a draw, or None
+    /// if there is no limit. See run_game_with_move_limit.
+    max_moves: Option<usize>,
+
+    /// The number of moves made so far this game, across all players.
+    moves_made: usize,
+
+    /// Whether the game was ended early because max_moves was reached.
+    move_limit_reached: bool,
+
+    /// The seed the board was created from, or None if the board was given
+    /// directly. See run_game_with_seeds.
+    board_seed: Option<u64>,
+
+    /// The seed the turn order was shuffled with, or None if players take
+    /// turns in the order the clients were given. See run_game_with_seed.
+    turn_order_seed: Option<u64>,
+
+    /// The seed each client was reseeded with, derived from turn_order_seed.
+    /// Empty if the game is not seeded.
+    client_seeds: BTreeMap<PlayerId, u64>,
 }
 
 /// The final GameState of a finished game, along with each player and
@@ -48,16 +116,113 @@ pub struct GameResult {
     /// This is the final state of the game, which may be used to delve
     /// into statistics detail about each player, such as their score
     /// and end positions.
-    pub final_state: GameState
+    pub final_state: GameState,
+
+    /// The total wall-clock time each client spent waiting to return their
+    /// placements and moves, useful for detecting slow players. Kicked players
+    /// keep the time they accumulated before being kicked.
+    pub think_time: BTreeMap<PlayerId, Duration>,
+
+    /// The reason each Kicked player in final_statuses was kicked, useful
+    /// for debugging misbehaving clients. Players who were not kicked have no entry.
+    pub kick_reasons: BTreeMap<PlayerId, KickReason>,
+
+    /// True if the game was ended as a draw because it reached its move limit
+    /// rather than finishing normally. See run_game_with_move_limit.
+    pub move_limit_reached: bool,
+
+    /// The seed the game's board was created from with Board::with_seed, if any.
+    /// See run_game_with_seeds.
+    pub board_seed: Option<u64>,
+
+    /// The seed the game's turn order was shuffled with, if any. Each client's seed is
+    /// derived from it as well, so running the same clients on the same board with this
+    /// seed reproduces the game as long as every client makes its random choices from
+    /// the seed it is given. See run_game_with_seed.
+    pub turn_order_seed: Option<u64>,
+
+    /// The seed each client was given with Client::reseed, derived from turn_order_seed.
+    /// Empty if the game was not seeded.
+    pub client_seeds: BTreeMap<PlayerId, u64>,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// The seeds used to set up a game played by run_game_with_seeds. Running the same
+/// deterministic clients with the same seeds and board size reproduces the game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GameSeeds {
+    /// The seed the board is created from, see Board::with_seed
+    pub board: u64,
+
+    /// The seed the turn order is shuffled with and the clients are reseeded from,
+    /// see run_game_with_seed
+    pub turn_order: u64,
+}
+
+/// A serializable summary of a GameResult, used for logging game outcomes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResultSummary {
+    /// The status of each player, in the same order as GameResult::final_statuses
+    pub statuses: Vec<ClientStatus>,
+
+    /// The final score of each player remaining at the end of the game.
+    /// Kicked players are removed from the game and so have no score.
+    pub scores: BTreeMap<PlayerId, usize>,
+
+    /// The players who won the game
+    pub winners: Vec<PlayerId>,
+}
+
+impl GameResult {
+    /// Summarizes the statuses, scores, and winners of this result for logging.
+    pub fn summary(&self) -> GameResultSummary {
+        let state = &self.final_state;
+        GameResultSummary {
+            statuses: self.final_statuses.clone(),
+            scores: state.players.iter().map(|(id, player)| (*id, player.score)).collect(),
+            winners: state.winning_players.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ClientStatus {
     Won,
     Lost,
     Kicked
 }
 
+/// The reasons a referee can kick a client from a game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KickReason {
+    /// The client failed to respond to a message from the referee without saying why,
+    /// see Client::last_failure.
+    NoResponse,
+
+    /// The client did not send a complete response within its timeout, or responded
+    /// after the referee's placement or move timeout
+    Timeout,
+
+    /// The client's connection closed before it sent a response
+    Disconnected,
+
+    /// The client's response was not valid JSON, or not any kind of message a client may send
+    MalformedMessage,
+
+    /// The client's response was longer than remote_client::MAX_MESSAGE_BYTES
+    MessageTooLarge,
+
+    /// The client responded with a well-formed but illegal placement, for the given reason
+    IllegalPlacement(PlacementError),
+
+    /// The client responded with a well-formed but illegal move
+    IllegalMove,
+
+    /// The client responded with a well-formed message of the wrong type for the
+    /// current phase of the game, e.g. a move while penguins are being placed. This
+    /// usually indicates a buggy client rather than a cheating one.
+    WrongMessageType,
+}
+
 /// Runs a complete game of Fish, setting up the board and
 /// waiting for player input for gameplay to occur, and terminating
 /// when a player (or multiple) have won. Check out Planning/player-protocol.md
@@ -66,6 +231,11 @@ pub enum ClientStatus {
 /// Clients will know the game has started when the referee sends each player
 /// the initial game state before the first turn.
 /// 
+/// The default board is fixed and the turn order is the order of the given clients,
+/// so running the same deterministic clients on the same board again produces the
+/// same final GameState. See run_game_with_seeds to play on a random board in a
+/// random turn order while recording the seeds needed to reproduce the game.
+/// 
 /// Returns the Win,Loss,Kicked status of each player and the final GameState
 pub fn run_game(clients: Vec<Box<dyn Client>>, board: Option<Board>) -> GameResult {
     let clients: Vec<_> = clients.into_iter().enumerate()
@@ -86,24 +256,269 @@ pub fn run_game(clients: Vec<Box<dyn Client>>, board: Option<Board>) -> GameResu
 /// 
 /// Returns the Win,Loss,Kicked status of each player and the final GameState
 pub fn run_game_shared(clients: &[ClientWithId], board: Option<Board>) -> GameResult {
-    let board = board.unwrap_or(Board::with_no_holes(5, 5, 3));
-    let mut referee = Referee::new(clients.to_vec(), board);
+    run_game_with_observers(clients, board, vec![])
+}
+
+/// Runs a game with a Vec of mutably shared clients, additionally sending
+/// each of the given observer streams every setup/take-turn message sent
+/// during the game. Observers are watch-only: they are never asked for
+/// placements or moves. An observer that fails to accept a message within
+/// OBSERVER_WRITE_TIMEOUT is dropped and sent nothing further, without
+/// affecting the game or the other observers.
+///
+/// Returns the Win,Loss,Kicked status of each player and the final GameState
+pub fn run_game_with_observers(clients: &[ClientWithId], board: Option<Board>, observers: Vec<TcpStream>) -> GameResult {
+    let referee = Referee::new(clients.to_vec(), board, observers, None);
+    run_referee(referee)
+}
+
+/// Runs a game with a Vec of mutably shared clients, accepting observers
+/// sent over the given channel while the game is in progress. Before the
+/// start of each turn any newly received observers are caught up on the
+/// game so far (see Referee::attach_observer) and then sent every following
+/// setup/take-turn message as with run_game_with_observers.
+///
+/// Returns the Win,Loss,Kicked status of each player and the final GameState
+pub fn run_game_with_late_observers(clients: &[ClientWithId], board: Option<Board>, late_observers: Receiver<TcpStream>) -> GameResult {
+    let referee = Referee::new(clients.to_vec(), board, vec![], Some(late_observers));
+    run_referee(referee)
+}
 
-    referee.initialize_clients();
+/// Runs a game with a Vec of mutably shared clients, kicking any client which
+/// takes longer than placement_timeout to return a placement or longer than
+/// move_timeout to return a move with KickReason::Timeout. Since in-house clients cannot be interrupted,
+/// a slow client's action is only rejected once it has been returned.
+///
+/// Returns the Win,Loss,Kicked status of each player and the final GameState
+pub fn run_game_with_timeouts(clients: &[ClientWithId], board: Option<Board>, placement_timeout: Duration, move_timeout: Duration) -> GameResult {
+    let mut referee = Referee::new(clients.to_vec(), board, vec![], None);
+    referee.placement_timeout = Some(placement_timeout);
+    referee.move_timeout = Some(move_timeout);
+    run_referee(referee)
+}
+
+/// Runs a game with a Vec of mutably shared clients, ending the game as a draw once
+/// max_moves moves have been made in total. Under the standard rules every move removes
+/// a tile so games always end, but variant rules such as TileRule::Deplete may not
+/// guarantee this, and the limit keeps a buggy variant from running forever.
+///
+/// In a draw every player who was not kicked is a winner, and the returned
+/// GameResult's move_limit_reached flag is set to tell it apart from a normal tie.
+pub fn run_game_with_move_limit(clients: &[ClientWithId], board: Option<Board>, max_moves: usize) -> GameResult {
+    let mut referee = Referee::new(clients.to_vec(), board, vec![], None);
+    referee.max_moves = Some(max_moves);
+    run_referee(referee)
+}
+
+/// Runs a game with a Vec of mutably shared clients, with the turn order shuffled
+/// by the given seed rather than following the order of the clients. Before the game
+/// starts each client is also given its own seed derived from this one with
+/// Client::reseed, which e.g. restarts an AIClient's RandomStrategy from that seed.
+/// The referee has no other source of randomness, so rerunning a game with the seed
+/// recorded in its GameResult reproduces it exactly as long as every client makes
+/// its random choices from the seed it is given.
+///
+/// The returned GameResult's final_statuses are still in the order clients were given.
+pub fn run_game_with_seed(clients: &[ClientWithId], board: Option<Board>, seed: u64) -> GameResult {
+    let mut referee = Referee::new(clients.to_vec(), board, vec![], None);
+    referee.seed_game(seed);
+    run_referee(referee)
+}
+
+/// Runs a game with a Vec of mutably shared clients on a board of the given size created
+/// by Board::with_seed from seeds.board, with the turn order shuffled by seeds.turn_order
+/// as in run_game_with_seed. Both seeds are recorded in the returned GameResult, so the
+/// game can be reproduced by running the same deterministic clients with them again.
+pub fn run_game_with_seeds(clients: &[ClientWithId], rows: u32, columns: u32, seeds: GameSeeds) -> GameResult {
+    let board = Board::with_seed(rows, columns, seeds.board);
+    let mut referee = Referee::new(clients.to_vec(), Some(board), vec![], None);
+    referee.board_seed = Some(seeds.board);
+    referee.seed_game(seeds.turn_order);
+    run_referee(referee)
+}
+
+/// Runs a game with a Vec of mutably shared clients as in run_game_shared, writing a copy
+/// of the current GameState into shared_state when the game starts and after every turn.
+/// Anything holding onto shared_state, e.g. the GUI, can then follow along as the game is
+/// played. See LiveGame to play the game a turn at a time from another event loop instead.
+///
+/// Returns the Win,Loss,Kicked status of each player and the final GameState
+pub fn run_game_with_shared_state(clients: &[ClientWithId], board: Option<Board>, shared_state: SharedGameState) -> GameResult {
+    let mut game = LiveGame::new(clients, board, shared_state);
+    while game.do_turn() {}
+    game.into_result()
+}
+
+/// A game of Fish played a single turn at a time, keeping a SharedGameState up to date
+/// with the game after every turn. Unlike the run_game functions, which block until the
+/// game is over, this lets the game be driven by another event loop such as the GUI's,
+/// so the GUI can redraw the shared state between turns.
+pub struct LiveGame {
+    referee: Referee,
+
+    /// The state to keep up to date with the game, if any. This is only None for
+    /// games run to completion without a LiveGame being exposed, see run_referee.
+    shared_state: Option<SharedGameState>,
+}
+
+impl LiveGame {
+    /// Starts a game between the given clients on the given board, or the default board
+    /// if None, sending each client the initial GameState and writing it into shared_state.
+    pub fn new(clients: &[ClientWithId], board: Option<Board>, shared_state: SharedGameState) -> LiveGame {
+        let referee = Referee::new(clients.to_vec(), board, vec![], None);
+        LiveGame::start(referee, Some(shared_state))
+    }
+
+    /// Starts the game of the given referee, sending each client the initial GameState
+    /// and writing it into shared_state if there is one. Every game is started this way,
+    /// see run_referee.
+    fn start(mut referee: Referee, shared_state: Option<SharedGameState>) -> LiveGame {
+        referee.initialize_clients();
+        referee.update_gamephase_if_needed();
+
+        let game = LiveGame { referee, shared_state };
+        game.update_shared_state();
+        game
+    }
+
+    /// Plays the current player's turn, then writes the resulting GameState into the
+    /// shared state. Returns whether a turn was played, which is false once the game is over.
+    pub fn do_turn(&mut self) -> bool {
+        if self.is_game_over() {
+            return false;
+        }
+
+        self.referee.do_player_turn();
+        self.update_shared_state();
+        true
+    }
 
-    while !referee.is_game_over() {
-        referee.do_player_turn();
+    pub fn is_game_over(&self) -> bool {
+        self.referee.is_game_over()
     }
 
-    referee.get_game_result()
+    /// Returns the result of this game. Assumes the game has been played to completion,
+    /// see Referee::get_game_result.
+    pub fn into_result(self) -> GameResult {
+        self.referee.get_game_result()
+    }
+
+    fn update_shared_state(&self) {
+        if let Some(shared_state) = &self.shared_state {
+            *shared_state.borrow_mut() = self.referee.phase.get_state().clone();
+        }
+    }
+}
+
+/// Plays the given number of games between two in-house AI clients using the
+/// given strategies on copies of the given board, alternating which client moves
+/// first each game to remove any first-move advantage. Strategies keep any state
+/// they have between games.
+///
+/// Returns (a_wins, b_wins, ties), which always sum to the number of games. A game
+/// is a tie if both or neither of the clients won it.
+pub fn simulate(strategy_a: Box<dyn Strategy>, strategy_b: Box<dyn Strategy>, board: Board, games: usize) -> (usize, usize, usize) {
+    let client_a = ClientWithId::new(0, Box::new(AIClient::new(strategy_a)));
+    let client_b = ClientWithId::new(1, Box::new(AIClient::new(strategy_b)));
+
+    let (mut a_wins, mut b_wins, mut ties) = (0, 0, 0);
+
+    for game in 0 .. games {
+        let a_goes_first = game % 2 == 0;
+        let clients = if a_goes_first {
+            vec![client_a.clone(), client_b.clone()]
+        } else {
+            vec![client_b.clone(), client_a.clone()]
+        };
+
+        let result = run_game_shared(&clients, Some(board.clone()));
+        let first_won = result.final_statuses[0] == ClientStatus::Won;
+        let second_won = result.final_statuses[1] == ClientStatus::Won;
+        let (a_won, b_won) = if a_goes_first { (first_won, second_won) } else { (second_won, first_won) };
+
+        match (a_won, b_won) {
+            (true, false) => a_wins += 1,
+            (false, true) => b_wins += 1,
+            _ => ties += 1,
+        }
+    }
+
+    (a_wins, b_wins, ties)
+}
+
+/// Measures how many more fish the first player collects than the second on the given
+/// board when both play with strategies made by the given factory. Two games are played
+/// on copies of the board with the same two clients, swapping which client goes first,
+/// so that any difference between the clients themselves cancels out.
+///
+/// Returns the first player's score minus the second player's score, averaged over both
+/// games and rounded toward zero. This is positive if going first is an advantage and
+/// negative if it is a disadvantage. Kicked players are counted as scoring 0.
+pub fn measure_first_move_advantage<F>(board: Board, strategy_factory: F) -> i64
+    where F: Fn() -> Box<dyn Strategy>
+{
+    let client_a = ClientWithId::new(0, Box::new(AIClient::new(strategy_factory())));
+    let client_b = ClientWithId::new(1, Box::new(AIClient::new(strategy_factory())));
+
+    let total_advantage: i64 = [(&client_a, &client_b), (&client_b, &client_a)].iter().map(|(first, second)| {
+        let clients = vec![(*first).clone(), (*second).clone()];
+        let result = run_game_shared(&clients, Some(board.clone()));
+        let score = |client: &ClientWithId| {
+            result.final_state.players.get(&client.id).map_or(0, |player| player.score as i64)
+        };
+        score(first) - score(second)
+    }).sum();
+
+    total_advantage / 2
+}
+
+/// Runs the game of the given referee to completion.
+fn run_referee(referee: Referee) -> GameResult {
+    let mut game = LiveGame::start(referee, None);
+    while game.do_turn() {}
+    game.into_result()
 }
 
 impl Referee {
-    fn new(clients: Vec<ClientWithId>, board: Board) -> Referee {
+    /// Creates a referee for a game between the given clients on the given board, or on a 5x5
+    /// board with 3 fish per tile if None. The game starts once it is run, see run_referee.
+    fn new(clients: Vec<ClientWithId>, board: Option<Board>, observers: Vec<TcpStream>, late_observers: Option<Receiver<TcpStream>>) -> Referee {
+        let board = board.unwrap_or(Board::with_no_holes(5, 5, 3));
         let client_ids = clients.iter().map(|client| client.id).collect();
         let state = GameState::with_players(board, client_ids);
+        let history_start = state.clone();
         let phase = GamePhase::PlacingPenguins(state);
-        Referee { clients, phase, move_history: vec![] }
+        let think_time = clients.iter().map(|client| (client.id, Duration::default())).collect();
+        for observer in observers.iter() {
+            observer.set_write_timeout(Some(OBSERVER_WRITE_TIMEOUT)).ok();
+        }
+
+        Referee {
+            clients, phase, move_history: vec![], history_start, observers, late_observers,
+            think_time, kick_reasons: BTreeMap::new(), placement_timeout: None, move_timeout: None,
+            max_moves: None, moves_made: 0, move_limit_reached: false,
+            board_seed: None, turn_order_seed: None, client_seeds: BTreeMap::new(),
+        }
+    }
+
+    /// Restarts the game with its turn order shuffled by the given seed, and reseeds
+    /// each client with a seed derived from it in the order the clients were given.
+    /// Must be called before the game has begun.
+    fn seed_game(&mut self, seed: u64) {
+        let mut client_ids: Vec<_> = self.clients.iter().map(|client| client.id).collect();
+        util::shuffle_with_seed(&mut client_ids, seed);
+
+        let state = GameState::with_players(self.phase.get_state().board.clone(), client_ids);
+        self.history_start = state.clone();
+        self.phase = GamePhase::PlacingPenguins(state);
+        self.turn_order_seed = Some(seed);
+
+        let mut seed_state = seed;
+        for client in self.clients.iter() {
+            let client_seed = util::next_random(&mut seed_state);
+            client.borrow_mut().reseed(client_seed);
+            self.client_seeds.insert(client.id, client_seed);
+        }
     }
 
     fn get_client_player_color(&self, client: &ClientWithId) -> PlayerColor {
@@ -124,12 +539,12 @@ impl Referee {
             let color = self.get_client_player_color(client);
             let result = client.borrow_mut().initialize_game(state, color);
             if result.is_none() {
-                clients_to_kick.push(client.id);
+                clients_to_kick.push((client.id, client_failure(client)));
             }
         }
 
-        for id in clients_to_kick {
-            self.kick_player(id);
+        for (id, reason) in clients_to_kick {
+            self.kick_player(id, reason);
         }
     }
 
@@ -139,7 +554,8 @@ impl Referee {
     /// Assumes that the game this referee was hosting has been played to
     /// completion - otherwise no winners will be returned.
     fn get_game_result(self) -> GameResult {
-        let Referee { clients, phase, .. } = self;
+        let Referee { clients, phase, think_time, kick_reasons, move_limit_reached, board_seed, turn_order_seed,
+            client_seeds, .. } = self;
 
         let final_statuses = clients.into_iter().map(|client| {
             if client.kicked {
@@ -156,60 +572,149 @@ impl Referee {
         GameResult {
             final_state: phase.take_state(),
             final_statuses,
+            think_time,
+            kick_reasons,
+            move_limit_reached,
+            board_seed,
+            turn_order_seed,
+            client_seeds,
         }
     }
     
     /// Waits for input from the current player in the GameState,
-    /// then acts upon that input
+    /// then acts upon that input by advancing the game with GamePhase::step.
+    /// The player is kicked if they fail to give an action or give an invalid one.
     fn do_player_turn(&mut self) {
-        let success = match &self.phase {
-            GamePhase::Starting => Some(()),
-            GamePhase::PlacingPenguins(_) => self.do_player_placement(),
-            GamePhase::MovingPenguins(_) => self.do_player_move(),
-            GamePhase::Done(_) => Some(()),
+        self.attach_late_observers();
+        let was_placing = matches!(self.phase, GamePhase::PlacingPenguins(_));
+
+        let action = match self.phase.expected_action() {
+            Some(ActionKind::Placement) => self.get_player_placement(),
+            Some(ActionKind::Move) => self.get_player_move(),
+            None => return self.update_gamephase_if_needed(),
+        };
+
+        // Moves are only valid if they can also be recorded in the move history
+        let player_move = match action {
+            Ok(Action::MovePenguin(move_)) => {
+                let color = self.get_client_player_color(self.current_client());
+                PlayerMove::new(color, move_, self.phase.get_state())
+            },
+            _ => None,
         };
+        // Placements are validated as they are received, so only a move can still be illegal here
+        let kick_reason = action.err().unwrap_or(KickReason::IllegalMove);
+        let action = action.ok().filter(|action| matches!(action, Action::PlacePenguin(_)) || player_move.is_some());
+        let remaining_before = remaining_progress(self.phase.get_state());
 
-        if success.is_none() {
-            self.kick_current_player();
+        match self.phase.step(|_| action) {
+            StepOutcome::Applied(Action::MovePenguin(_)) => {
+                assert_move_made_progress(remaining_before, self.phase.get_state());
+                self.move_history.extend(player_move);
+                self.moves_made += 1;
+            },
+            StepOutcome::Applied(Action::PlacePenguin(_)) | StepOutcome::GameOver => (),
+            StepOutcome::Kicked(player) => self.kick_player(player, kick_reason),
         }
 
-        self.update_gamephase_if_needed();
+        self.finish_gamephase_update(was_placing);
     }
 
-    /// Retrieve a player's next placement from their input stream then tries to take that placement.
-    /// If the placement cannot be received from the input stream (e.g. due to a timeout) or the
-    /// placement is invalid in any way then None will be returned. Otherwise, Some is returned.
-    /// 
-    /// Invariant: If None is returned then the current_turn does not change.
-    fn do_player_placement(&mut self) -> Option<()> {
-        let placement = self.current_client().borrow_mut().get_placement(self.phase.get_state())?;
-        match &mut self.phase {
-            GamePhase::PlacingPenguins(gamestate) => gamestate.place_avatar_for_current_player(placement),
-            _ => unreachable!("do_player_placement called outside of the PlacingPenguins phase"),
+    /// Retrieve a player's next placement from their input stream. If the placement cannot be
+    /// received from the input stream (e.g. due to a timeout), takes longer than the placement
+    /// timeout, or is illegal then the reason the player should be kicked is returned.
+    fn get_player_placement(&mut self) -> Result<Action, KickReason> {
+        self.notify_observers(setup_message(self.phase.get_state()));
+
+        let start_time = Instant::now();
+        let placement = self.current_client().borrow_mut().get_placement(self.phase.get_state());
+        let elapsed = start_time.elapsed();
+        self.add_think_time(elapsed);
+
+        let placement = placement.ok_or_else(|| self.current_client_failure())?;
+        if matches!(self.placement_timeout, Some(timeout) if elapsed > timeout) {
+            return Err(KickReason::Timeout);
         }
+
+        self.phase.get_state().validate_placement(placement).map_err(KickReason::IllegalPlacement)?;
+        Ok(Action::PlacePenguin(placement))
     }
 
-    /// Retrieve a player's next move from their input stream then try to take that move.
-    /// If the move is invalid in any way or if the move cannot be parsed from the input
-    /// stream (e.g. if the stream timeouts) then None is returned. Otherwise Some is returned.
-    /// 
-    /// Invariant: If None is returned then the current_turn does not change.
-    fn do_player_move(&mut self) -> Option<()> {
+    /// Retrieve a player's next move from their input stream. If the move cannot be parsed
+    /// from the input stream (e.g. if the stream timeouts) or takes longer than the move
+    /// timeout then the reason the player should be kicked is returned.
+    fn get_player_move(&mut self) -> Result<Action, KickReason> {
         let move_history = self.get_move_history_for_current_client();
+        self.notify_observers(take_turn_message(self.phase.get_state(), &move_history));
+
+        let start_time = Instant::now();
+        let move_ = self.current_client().borrow_mut().get_move(self.phase.get_state(), &move_history);
+        let elapsed = start_time.elapsed();
+        self.add_think_time(elapsed);
+
+        let move_ = move_.ok_or_else(|| self.current_client_failure())?;
+        if matches!(self.move_timeout, Some(timeout) if elapsed > timeout) {
+            return Err(KickReason::Timeout);
+        }
+
+        Ok(Action::MovePenguin(move_))
+    }
+
+    /// Returns why the current client failed to return an action, see client_failure.
+    fn current_client_failure(&self) -> KickReason {
+        client_failure(self.current_client())
+    }
+
+    /// Add the given time to the total think time of the current player.
+    fn add_think_time(&mut self, time: Duration) {
+        let current_player = self.phase.current_turn();
+        *self.think_time.entry(current_player).or_default() += time;
+    }
+
+    /// Send the given message to each observer of this game. Observers
+    /// that fail to receive the message, e.g. because they disconnected or
+    /// stopped reading, are dropped since they have no effect on the outcome
+    /// of the game and would otherwise slow down every following turn.
+    fn notify_observers(&mut self, message: String) {
+        self.observers.retain(|mut observer| observer.write_all(message.as_bytes()).is_ok());
+    }
+
+    /// Attach each observer that has joined since the last turn.
+    fn attach_late_observers(&mut self) {
+        let new_observers = match &self.late_observers {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => vec![],
+        };
 
-        let move_ = self.current_client().borrow_mut().get_move(self.phase.get_state(), &move_history)?;
-        let current_player_color = self.get_client_player_color(self.current_client());
+        for observer in new_observers {
+            self.attach_observer(observer);
+        }
+    }
 
-        match &mut self.phase {
-            GamePhase::MovingPenguins(gametree) => {
-                let starting_state = gametree.get_state();
-                let player_move = PlayerMove::new(current_player_color, move_, starting_state)?;
+    /// Catch the given observer up on the game so far, then add it to the
+    /// list of observers to receive every following message.
+    ///
+    /// While penguins are being placed the observer is sent a setup message
+    /// with the current state. Once penguins are moving it is instead sent a
+    /// setup message with history_start followed by a take-turn message with
+    /// the current state and the full move_history, which when replayed on top
+    /// of history_start reconstructs the current state. As in notify_observers,
+    /// the observer is dropped if it fails to receive these messages.
+    fn attach_observer(&mut self, mut observer: TcpStream) {
+        observer.set_write_timeout(Some(OBSERVER_WRITE_TIMEOUT)).ok();
 
-                self.phase.try_do_move(move_)?;
-                self.move_history.push(player_move);
-                Some(())
+        let catch_up = match &self.phase {
+            GamePhase::Starting | GamePhase::PlacingPenguins(_) => {
+                vec![setup_message(self.phase.get_state())]
             },
-            _ => unreachable!("do_player_move called outside of the MovingPenguins phase"),
+            GamePhase::MovingPenguins(_) | GamePhase::Done(_) => vec![
+                setup_message(&self.history_start),
+                take_turn_message(self.phase.get_state(), &self.move_history),
+            ],
+        };
+
+        if catch_up.iter().all(|message| observer.write_all(message.as_bytes()).is_ok()) {
+            self.observers.push(observer);
         }
     }
 
@@ -227,10 +732,14 @@ impl Referee {
     }
 
     /// Kick the given player from the game, removing all their penguins and
-    /// their position in the turn order. This does not notify the player that
-    /// they were kicked.
-    fn kick_player(&mut self, player: PlayerId) {
+    /// their position in the turn order, and recording the reason they were kicked.
+    /// This does not notify the player that they were kicked.
+    ///
+    /// The player is removed before the game can end, so if none of the remaining
+    /// players can move the winners are decided among the remaining players only.
+    fn kick_player(&mut self, player: PlayerId, reason: KickReason) {
         self.phase.get_state_mut().remove_player(player);
+        self.kick_reasons.insert(player, reason);
 
         self.clients.iter_mut()
             .find(|client| client.id == player)
@@ -243,6 +752,7 @@ impl Referee {
         // Clear the move history when we kick players so as to not retain moves
         // made by players that are no longer in the game
         self.move_history.clear();
+        self.history_start = self.phase.get_state().clone();
 
         // The game ends early if all clients are kicked
         if self.clients.iter().all(|client| client.kicked) {
@@ -250,28 +760,30 @@ impl Referee {
         }
     }
 
-    /// Kick the player whose turn it currently is. See kick_player for
-    /// the details of kicking a player.
-    fn kick_current_player(&mut self) {
-        let current_player = self.phase.get_state().current_turn;
-        self.kick_player(current_player);
-    }
-
     /// Player placements and moves will update the current
     /// GameState/GameTree but we still need to check if we've
     /// finished the placement/moves phase and update the current
     /// GamePhase as appropriate here.
+    ///
+    /// If the board fills up before every penguin is placed then no penguin
+    /// can move either, so the game ends immediately rather than asking the
+    /// current player for a placement that cannot exist.
     fn update_gamephase_if_needed(&mut self) {
-        if let GamePhase::PlacingPenguins(state) = &mut self.phase {
-            if state.all_penguins_are_placed() {
-                self.phase = GamePhase::MovingPenguins(GameTree::new(state));
-            }
+        let was_placing = matches!(self.phase, GamePhase::PlacingPenguins(_));
+        self.phase.update_phase_if_needed();
+        self.finish_gamephase_update(was_placing);
+    }
+
+    /// Finishes updating the GamePhase after GamePhase::update_phase_if_needed, given whether
+    /// the game was in the PlacingPenguins phase beforehand: the move history starts over
+    /// once the movement phase begins, and the game ends as a draw at the move limit.
+    fn finish_gamephase_update(&mut self, was_placing: bool) {
+        if was_placing && matches!(self.phase, GamePhase::MovingPenguins(_)) {
+            self.history_start = self.phase.get_state().clone();
         }
 
-        // Test if MovingPenguins is finished even after testing the above in case we
-        // start a game after placing penguins where immediately no penguin can move.
-        if let GamePhase::MovingPenguins(GameTree::End(state)) = &self.phase {
-            self.phase = GamePhase::Done(state.clone());
+        if matches!(self.max_moves, Some(max_moves) if self.moves_made >= max_moves) && !self.is_game_over() {
+            self.end_game_as_draw();
         }
     }
 
@@ -279,17 +791,67 @@ impl Referee {
     fn is_game_over(&self) -> bool {
         self.phase.is_game_over()
     }
+
+    /// Ends the game immediately with every remaining player as a winner,
+    /// recording that the move limit was the reason it ended.
+    fn end_game_as_draw(&mut self) {
+        let mut state = self.phase.get_state().clone();
+        state.winning_players = Some(state.players.keys().copied().collect());
+        self.phase = GamePhase::Done(state);
+        self.move_limit_reached = true;
+    }
+}
+
+/// Returns why the given client's most recent call failed, as reported by the
+/// client itself, or KickReason::NoResponse if the client cannot tell.
+fn client_failure(client: &ClientWithId) -> KickReason {
+    client.client.borrow().last_failure().unwrap_or(KickReason::NoResponse)
+}
+
+/// Returns a measure of how much of the given game's board is left to play through.
+/// Every valid move must strictly decrease this so that games always end: under the
+/// standard rules this is the number of tiles left, since each move removes a tile.
+/// Under TileRule::Deplete a tile may stay on the board with one fewer fish, so
+/// the fish left on the board are counted as well.
+fn remaining_progress(state: &GameState) -> usize {
+    let tiles = state.board.tile_count();
+    match state.tile_rule {
+        TileRule::Remove => tiles,
+        TileRule::Deplete => tiles + state.board.tiles.values().map(|tile| tile.get_fish_count()).sum::<usize>(),
+    }
+}
+
+/// Panics if a move left the given game with as much of its board left to play through
+/// as remaining_before, see remaining_progress. This is impossible under the standard
+/// rules, so it can only happen if a variant rule lets a move leave the board unchanged,
+/// in which case the game may never end and it is better to fail loudly than to hang.
+fn assert_move_made_progress(remaining_before: usize, state_after: &GameState) {
+    let remaining_after = remaining_progress(state_after);
+    assert!(remaining_after < remaining_before,
+        "A move did not change the board: {} tiles/fish remained before the move and {} after (tile rule {:?})",
+        remaining_before, remaining_after, state_after.tile_rule);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use super::ClientStatus::*;
-    use crate::server::strategy::Strategy;
-    use crate::server::ai_client::AIClient;
     use crate::common::action::{ Move, Placement };
+    use crate::common::game_tree::GameTree;
+    use crate::common::boardposn::BoardPosn;
     use crate::common::tile::TileId;
 
+    use crate::server::strategy::{ find_zigzag_placement, find_minmax_move, ZigZagMinMaxStrategy, GreedyStrategy, RandomStrategy };
+    use crate::server::remote_client::{ RemoteClient, MAX_MESSAGE_BYTES };
+    use crate::server::message::{ ServerToClientMessage, serialize_gamestate, placement_to_json_position };
+
+    use serde_json::json;
+
+    use std::cell::RefCell;
+    use std::net::TcpListener;
+    use std::rc::Rc;
+    use std::sync::mpsc::{ channel, Sender };
+
     pub struct CheatingStrategy;
 
     impl Strategy for CheatingStrategy {
@@ -319,6 +881,113 @@ mod tests {
         assert_eq!(result.final_statuses, vec![Won, Lost]);
     }
 
+    /// Without any randomness in the referee, re-running a game with the same
+    /// clients and board must reproduce the same final state.
+    #[test]
+    fn run_game_is_reproducible() {
+        let make_players = || -> Vec<Box<dyn Client>> { vec![
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ]};
+
+        let board = Board::with_no_holes(4, 5, 2);
+        let first = run_game(make_players(), Some(board.clone()));
+        let second = run_game(make_players(), Some(board));
+        assert!(first.final_state == second.final_state);
+        assert_eq!(first.final_state.turn_number(), second.final_state.turn_number());
+        assert_eq!(first.final_statuses, second.final_statuses);
+    }
+
+    /// Games run with the same seed should shuffle the turn order the same way and so
+    /// play out identically, while the statuses stay in the order clients were given.
+    #[test]
+    fn run_game_with_seed_is_reproducible() {
+        let make_clients = || -> Vec<ClientWithId> { vec![
+            ClientWithId::new(0, Box::new(AIClient::with_zigzag_minmax_strategy())),
+            ClientWithId::new(1, Box::new(AIClient::new(Box::new(GreedyStrategy)))),
+            ClientWithId::new(2, Box::new(AIClient::with_zigzag_minmax_strategy())),
+        ]};
+
+        let board = Board::with_no_holes(4, 5, 2);
+        let first = run_game_with_seed(&make_clients(), Some(board.clone()), 7);
+        let second = run_game_with_seed(&make_clients(), Some(board.clone()), 7);
+        assert_eq!(first.turn_order_seed, Some(7));
+        assert_eq!(first.board_seed, None);
+        assert!(first.final_state == second.final_state);
+        assert_eq!(first.final_state.turn_order, second.final_state.turn_order);
+        assert_eq!(first.final_statuses, second.final_statuses);
+
+        let mut expected_turn_order = vec![PlayerId(0), PlayerId(1), PlayerId(2)];
+        util::shuffle_with_seed(&mut expected_turn_order, 7);
+        assert_eq!(first.final_state.turn_order, expected_turn_order);
+
+        let unseeded = run_game_shared(&make_clients(), Some(board));
+        assert_eq!(unseeded.turn_order_seed, None);
+        assert!(unseeded.client_seeds.is_empty());
+        assert_eq!(unseeded.final_state.turn_order, vec![PlayerId(0), PlayerId(1), PlayerId(2)]);
+    }
+
+    /// The game's seed should also control clients with random strategies, so games with the
+    /// same seed play out identically even if the strategies were created with different seeds.
+    #[test]
+    fn run_game_with_seed_seeds_random_strategies() {
+        let make_clients = |strategy_seed| -> Vec<ClientWithId> { vec![
+            ClientWithId::new(0, Box::new(AIClient::new(Box::new(RandomStrategy::new(strategy_seed))))),
+            ClientWithId::new(1, Box::new(AIClient::new(Box::new(RandomStrategy::new(strategy_seed + 1))))),
+        ]};
+
+        let board = Board::with_no_holes(4, 5, 2);
+        let first = run_game_with_seed(&make_clients(1), Some(board.clone()), 7);
+        let second = run_game_with_seed(&make_clients(100), Some(board), 7);
+        assert!(first.final_state == second.final_state);
+        assert_eq!(first.final_state.turn_number(), second.final_state.turn_number());
+        assert_eq!(first.final_statuses, second.final_statuses);
+        assert_eq!(first.client_seeds, second.client_seeds);
+        assert_eq!(first.client_seeds.len(), 2);
+    }
+
+    /// Rerunning a game with the seeds recorded in its GameResult should reproduce it exactly
+    #[test]
+    fn run_game_with_seeds_is_reproducible() {
+        let make_clients = || -> Vec<ClientWithId> { vec![
+            ClientWithId::new(0, Box::new(AIClient::with_zigzag_minmax_strategy())),
+            ClientWithId::new(1, Box::new(AIClient::new(Box::new(GreedyStrategy)))),
+            ClientWithId::new(2, Box::new(AIClient::with_zigzag_minmax_strategy())),
+        ]};
+
+        let first = run_game_with_seeds(&make_clients(), 4, 5, GameSeeds { board: 3, turn_order: 9 });
+        assert_eq!(first.board_seed, Some(3));
+        assert_eq!(first.turn_order_seed, Some(9));
+
+        let seeds = GameSeeds { board: first.board_seed.unwrap(), turn_order: first.turn_order_seed.unwrap() };
+        let second = run_game_with_seeds(&make_clients(), 4, 5, seeds);
+        assert!(first.final_state == second.final_state);
+        assert_eq!(first.final_state.board.to_fish_grid(), second.final_state.board.to_fish_grid());
+        assert_eq!(first.final_state.turn_order, second.final_state.turn_order);
+        assert_eq!(first.final_statuses, second.final_statuses);
+    }
+
+    /// A summary of a game with a kicked player should survive a round trip through json
+    #[test]
+    fn game_result_summary_round_trip() {
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+            Box::new(AIClient::new(Box::new(MoveCheatingStrategy))),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let result = run_game(players, Some(Board::with_no_holes(4, 5, 1)));
+        let summary = result.summary();
+        assert_eq!(summary.statuses, result.final_statuses);
+        assert_eq!(summary.statuses[1], Kicked);
+        assert!(!summary.scores.contains_key(&PlayerId(1)));
+        assert!(!summary.winners.is_empty());
+
+        let json = serde_json::to_string(&summary).unwrap();
+        let deserialized: GameResultSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, summary);
+    }
+
     /// Runs a game that should start with no possible player moves, although
     /// they can each place all of their penguins.
     #[test]
@@ -363,6 +1032,105 @@ mod tests {
         assert_eq!(result.final_statuses, vec![Won, Kicked]);
     }
 
+    /// A strategy that places penguins legally but always tries to move a penguin
+    /// from tile 0 onto itself.
+    pub struct MoveCheatingStrategy;
+
+    impl Strategy for MoveCheatingStrategy {
+        fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+            find_zigzag_placement(gamestate)
+        }
+
+        fn find_move(&mut self, _game: &mut GameTree) -> Move {
+            Move::new(TileId(0), TileId(0))
+        }
+    }
+
+    #[test]
+    fn run_game_illegal_placement_kick_reason() {
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::new(Box::new(CheatingStrategy))),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::IllegalPlacement(PlacementError::Occupied)));
+    }
+
+    #[test]
+    fn run_game_illegal_move_kick_reason() {
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::new(Box::new(MoveCheatingStrategy))),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::IllegalMove));
+        assert_eq!(result.kick_reasons.get(&PlayerId(1)), None);
+    }
+
+    /// A remote client whose connection is closed before the game starts can never
+    /// respond to the referee, so it should be kicked for disconnecting.
+    #[test]
+    fn run_game_disconnect_kick_reason() {
+        let listener = TcpListener::bind("127.0.0.1:8091").expect("Could not create listener");
+        let client_stream = TcpStream::connect("127.0.0.1:8091").expect("Could not connect stream");
+        let (server_stream, _) = listener.accept().expect("Could not accept client");
+        drop(client_stream);
+
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+            Box::new(RemoteClient::new(server_stream, Duration::from_secs(1))),
+        ];
+
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Won, Kicked]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(1)), Some(&KickReason::Disconnected));
+        assert_eq!(result.kick_reasons.len(), 1);
+    }
+
+    /// A remote client that stays connected but never responds should be kicked for timing out
+    #[test]
+    fn run_game_timeout_kick_reason() {
+        let listener = TcpListener::bind("127.0.0.1:8105").expect("Could not create listener");
+        let _client_stream = TcpStream::connect("127.0.0.1:8105").expect("Could not connect stream");
+        let (server_stream, _) = listener.accept().expect("Could not accept client");
+
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+            Box::new(RemoteClient::new(server_stream, Duration::from_millis(200))),
+        ];
+
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Won, Kicked]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(1)), Some(&KickReason::Timeout));
+    }
+
+    /// A remote client responding with JSON that is not any client message should be kicked
+    /// for sending a malformed message, without waiting for the response timeout
+    #[test]
+    fn run_game_malformed_message_kick_reason() {
+        let remote_player = spawn_remote_player(8106, |message| {
+            match message {
+                ServerToClientMessage::Setup(_) => json!({ "position": [0, 0] }),
+                _ => json!("void"),
+            }
+        });
+
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(remote_player),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let start = Instant::now();
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::MalformedMessage));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
     #[test]
     fn run_game_two_cheaters() {
         let players_cheater_first: Vec<Box<dyn Client>> = vec![
@@ -384,4 +1152,528 @@ mod tests {
         let result = run_game(players_cheater_first, None);
         assert_eq!(result.final_statuses, vec![Kicked, Kicked, Kicked]);
     }
+
+    /// Runs a game on a board with too few tiles for every penguin to be placed.
+    /// The board fills up after 4 placements, at which point the game should
+    /// end cleanly with both players tied at 0 rather than panicking in the
+    /// zigzag strategy when it can find no open tile.
+    #[test]
+    fn run_game_board_fills_during_placement() {
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let board = Board::with_no_holes(2, 2, 1);
+        let result = run_game(players, Some(board));
+        assert!(result.final_state.is_game_over());
+        assert!(!result.final_state.all_penguins_are_placed());
+        assert_eq!(result.final_state.get_occupied_tiles().len(), 4);
+        assert_eq!(result.final_statuses, vec![Won, Won]);
+    }
+
+    /// Kicking the second-to-last player when the last player has no moves left must
+    /// end the game with the surviving player as the sole winner, even though the
+    /// kicked player had the higher score.
+    #[test]
+    fn kick_second_to_last_player_when_last_is_stuck() {
+        // A single column of tiles: tile 0 can only move to tile 1, and tile 4
+        // is cut off from the rest of the board by holes at tiles 2 and 3.
+        let board = Board::from_tiles(vec![vec![1], vec![1], vec![0], vec![0], vec![1]]);
+        let clients = vec![
+            ClientWithId::new(0, Box::new(AIClient::with_zigzag_minmax_strategy())),
+            ClientWithId::new(1, Box::new(AIClient::with_zigzag_minmax_strategy())),
+        ];
+        let mut referee = Referee::new(clients, Some(board), vec![], None);
+
+        let mut state = referee.phase.get_state().clone();
+        for (player_id, tile_id, score) in [(0, 0, 5), (1, 4, 0)].iter() {
+            let player = state.players.get_mut(&PlayerId(*player_id)).unwrap();
+            player.penguins.truncate(1);
+            player.penguins[0].tile_id = Some(TileId(*tile_id));
+            player.score = *score;
+        }
+        referee.phase = GamePhase::MovingPenguins(GameTree::new(&state));
+        assert_eq!(referee.phase.current_turn(), PlayerId(0));
+        assert!(!referee.is_game_over());
+
+        referee.kick_player(PlayerId(0), KickReason::IllegalMove);
+        referee.update_gamephase_if_needed();
+        assert!(referee.is_game_over());
+        assert_eq!(referee.phase.get_state().winning_players, Some(vec![PlayerId(1)]));
+
+        let result = referee.get_game_result();
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::IllegalMove));
+    }
+
+    /// Runs a handful of games between the zigzag minmax and greedy strategies, checking
+    /// that every game is counted exactly once as a win for either strategy or a tie.
+    #[test]
+    fn simulate_counts_every_game() {
+        let games = 5;
+        let (a_wins, b_wins, ties) = simulate(
+            Box::new(ZigZagMinMaxStrategy), Box::new(GreedyStrategy), Board::with_no_holes(3, 5, 1), games);
+
+        assert_eq!(a_wins + b_wins + ties, games);
+    }
+
+    /// On boards where every tile has the same number of fish, going first
+    /// should be worth at most a single fish when both players play alike.
+    #[test]
+    fn measure_first_move_advantage_symmetric() {
+        let advantage = measure_first_move_advantage(Board::with_no_holes(4, 4, 1), || Box::new(GreedyStrategy));
+        assert_eq!(advantage, 0);
+
+        let advantage = measure_first_move_advantage(Board::with_no_holes(3, 5, 1), || Box::new(GreedyStrategy));
+        assert!(advantage.abs() <= 1);
+    }
+
+    /// A strategy that plays normally but deliberately waits before each action.
+    pub struct SlowStrategy;
+
+    impl Strategy for SlowStrategy {
+        fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+            std::thread::sleep(Duration::from_millis(20));
+            find_zigzag_placement(gamestate)
+        }
+
+        fn find_move(&mut self, game: &mut GameTree) -> Move {
+            std::thread::sleep(Duration::from_millis(20));
+            find_minmax_move(game, 1)
+        }
+    }
+
+    /// Runs a game between a slow and a fast player and checks that the slow
+    /// player's accumulated think time is greater than the fast player's.
+    /// Each player takes at least 4 turns placing penguins so the slow player
+    /// should have accumulated at least 80ms.
+    #[test]
+    fn run_game_think_time() {
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(AIClient::new(Box::new(SlowStrategy))),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let board = Board::with_no_holes(3, 5, 1);
+        let result = run_game(players, Some(board));
+
+        let slow_time = result.think_time[&PlayerId(0)];
+        let fast_time = result.think_time[&PlayerId(1)];
+        assert!(slow_time >= Duration::from_millis(80));
+        assert!(slow_time > fast_time);
+    }
+
+    /// A strategy that places penguins immediately but deliberately waits before each move.
+    struct SlowMoveStrategy;
+
+    impl Strategy for SlowMoveStrategy {
+        fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+            find_zigzag_placement(gamestate)
+        }
+
+        fn find_move(&mut self, game: &mut GameTree) -> Move {
+            std::thread::sleep(Duration::from_millis(100));
+            find_minmax_move(game, 1)
+        }
+    }
+
+    /// A player who is fast at placing but slow at moving should survive a generous
+    /// placement timeout and be kicked on its first move by a tight move timeout.
+    /// Since its placements are immediate, any think time of at least 100ms shows
+    /// that it was asked for a move.
+    #[test]
+    fn run_game_move_timeout() {
+        let clients = vec![
+            ClientWithId::new(0, Box::new(AIClient::new(Box::new(SlowMoveStrategy)))),
+            ClientWithId::new(1, Box::new(AIClient::new(Box::new(GreedyStrategy)))),
+        ];
+
+        let board = Board::with_no_holes(3, 5, 1);
+        let result = run_game_with_timeouts(&clients, Some(board), Duration::from_secs(1), Duration::from_millis(50));
+
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons[&PlayerId(0)], KickReason::Timeout);
+        assert!(result.think_time[&PlayerId(0)] >= Duration::from_millis(100));
+    }
+
+    /// A normal game stopped by an artificially low move limit should end as a draw
+    /// immediately after the limit is reached, and be reported as such.
+    #[test]
+    fn run_game_move_limit() {
+        let clients = vec![
+            ClientWithId::new(0, Box::new(AIClient::with_zigzag_minmax_strategy())),
+            ClientWithId::new(1, Box::new(AIClient::new(Box::new(GreedyStrategy)))),
+        ];
+
+        let board = Board::with_no_holes(3, 5, 1);
+        let result = run_game_with_move_limit(&clients, Some(board.clone()), 3);
+
+        assert!(result.move_limit_reached);
+        assert_eq!(result.final_statuses, vec![Won, Won]);
+        assert_eq!(result.final_state.winning_players, Some(vec![PlayerId(0), PlayerId(1)]));
+
+        // 8 penguins were placed on a 15-tile board, and each of the 3 moves removed a tile
+        assert_eq!(result.final_state.board.tiles.len(), 12);
+
+        // A limit that is never reached has no effect on the game
+        let result = run_game_with_move_limit(&clients, Some(board.clone()), 1000);
+        let unlimited = run_game_shared(&clients, Some(board));
+        assert!(!result.move_limit_reached);
+        assert!(!unlimited.move_limit_reached);
+        assert_eq!(result.final_state, unlimited.final_state);
+    }
+
+    /// Runs a game with a single observer and checks that the observer received
+    /// one state message per turn taken. Each placement is a turn, and since each
+    /// move removes exactly one tile, the number of moves is the number of tiles removed.
+    #[test]
+    fn run_game_with_observer() {
+        let listener = TcpListener::bind("127.0.0.1:8090").expect("Could not create listener");
+        let observer_stream = TcpStream::connect("127.0.0.1:8090").expect("Could not connect stream");
+        let (observer, _) = listener.accept().expect("Could not accept observer");
+
+        let players: Vec<_> = vec![
+            ClientWithId::new(0, Box::new(AIClient::with_zigzag_minmax_strategy())),
+            ClientWithId::new(1, Box::new(AIClient::with_zigzag_minmax_strategy())),
+        ];
+
+        let board = Board::with_no_holes(3, 5, 1);
+        let initial_tile_count = board.tiles.len();
+        let result = run_game_with_observers(&players, Some(board), vec![observer]);
+
+        let placements: usize = result.final_state.players.values().map(|player| player.penguins.len()).sum();
+        let moves = initial_tile_count - result.final_state.board.tiles.len();
+
+        let messages = serde_json::Deserializer::from_reader(observer_stream)
+            .into_iter::<serde_json::Value>()
+            .count();
+
+        assert_eq!(result.final_statuses, vec![Won, Lost]);
+        assert_eq!(messages, placements + moves);
+    }
+
+    /// Observers should be given a write timeout, and dropped once a message fails to reach them
+    #[test]
+    fn disconnected_observers_are_dropped() {
+        let listener = TcpListener::bind("127.0.0.1:8108").expect("Could not create listener");
+        let observer_stream = TcpStream::connect("127.0.0.1:8108").expect("Could not connect stream");
+        let (observer, _) = listener.accept().expect("Could not accept observer");
+
+        let clients = vec![
+            ClientWithId::new(0, Box::new(AIClient::with_zigzag_minmax_strategy())),
+            ClientWithId::new(1, Box::new(AIClient::with_zigzag_minmax_strategy())),
+        ];
+        let mut referee = Referee::new(clients, Some(Board::with_no_holes(3, 5, 1)), vec![observer], None);
+        assert_eq!(referee.observers[0].write_timeout().unwrap(), Some(OBSERVER_WRITE_TIMEOUT));
+
+        // The first write after the observer disconnects can still succeed, but
+        // the observer resets the connection so the following writes fail
+        drop(observer_stream);
+        for _ in 0 .. 10 {
+            referee.notify_observers(setup_message(referee.phase.get_state()));
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(referee.observers.is_empty());
+    }
+
+    /// A strategy that plays normally, but on its first move hands the given
+    /// observer stream to the referee to simulate an observer joining mid-game.
+    pub struct LateObserverStrategy {
+        observer: Option<TcpStream>,
+        sender: Sender<TcpStream>,
+    }
+
+    impl Strategy for LateObserverStrategy {
+        fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+            find_zigzag_placement(gamestate)
+        }
+
+        fn find_move(&mut self, game: &mut GameTree) -> Move {
+            if let Some(observer) = self.observer.take() {
+                self.sender.send(observer).unwrap();
+            }
+            find_minmax_move(game, 1)
+        }
+    }
+
+    /// Runs a game where an observer joins after the first move and checks that
+    /// replaying the move history it is sent on top of the starting state it is
+    /// sent reconstructs the current state it is sent.
+    #[test]
+    fn run_game_late_observer_reconstructs_state() {
+        let listener = TcpListener::bind("127.0.0.1:8092").expect("Could not create listener");
+        let observer_stream = TcpStream::connect("127.0.0.1:8092").expect("Could not connect stream");
+        let (observer, _) = listener.accept().expect("Could not accept observer");
+
+        let (sender, receiver) = channel();
+        let late_observer_strategy = LateObserverStrategy { observer: Some(observer), sender };
+
+        let players: Vec<_> = vec![
+            ClientWithId::new(0, Box::new(AIClient::new(Box::new(late_observer_strategy)))),
+            ClientWithId::new(1, Box::new(AIClient::with_zigzag_minmax_strategy())),
+        ];
+
+        let board = Board::with_no_holes(3, 5, 1);
+        run_game_with_late_observers(&players, Some(board), receiver);
+
+        let mut messages = serde_json::Deserializer::from_reader(observer_stream)
+            .into_iter::<ServerToClientMessage>()
+            .map(|message| message.unwrap());
+
+        let mut state = match messages.next() {
+            Some(ServerToClientMessage::Setup((history_start,))) => history_start.to_common_game_state(2),
+            other => panic!("Expected a setup message, found {:?}", other),
+        };
+
+        let (current_state, history) = match messages.next() {
+            Some(ServerToClientMessage::TakeTurn(current_state, history, _)) => (current_state, history),
+            other => panic!("Expected a take-turn message, found {:?}", other),
+        };
+
+        assert!(!history.is_empty());
+        for [from, to] in history {
+            let from = state.board.get_tile_id(from[1], from[0]).unwrap();
+            let to = state.board.get_tile_id(to[1], to[0]).unwrap();
+            assert_eq!(state.move_avatar_for_current_player(Move::new(from, to)), Some(()));
+        }
+
+        let reconstructed = serde_json::to_value(serialize_gamestate(&state)).unwrap();
+        assert_eq!(reconstructed, serde_json::to_value(current_state).unwrap());
+    }
+
+    /// Connect a RemoteClient to a fake remote player on the given port which
+    /// replies to every message from the referee with the result of respond.
+    fn spawn_remote_player(port: u16, respond: fn(ServerToClientMessage) -> serde_json::Value) -> RemoteClient {
+        let address = format!("127.0.0.1:{}", port);
+        let listener = TcpListener::bind(&address).expect("Could not create listener");
+
+        std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).expect("Could not connect stream");
+            let messages = serde_json::Deserializer::from_reader(stream.try_clone().unwrap())
+                .into_iter::<ServerToClientMessage>();
+
+            for message in messages {
+                let response = match message {
+                    Ok(message) => respond(message),
+                    Err(_) => break,
+                };
+                stream.write_all(response.to_string().as_bytes()).ok();
+            }
+        });
+
+        let (stream, _) = listener.accept().expect("Could not accept remote player");
+        RemoteClient::new(stream, Duration::from_secs(5))
+    }
+
+    /// Places penguins in the zigzag fashion and responds to each other message with void.
+    fn respond_with_zigzag_placement(message: ServerToClientMessage) -> serde_json::Value {
+        match message {
+            ServerToClientMessage::Setup((state,)) => {
+                let state = state.to_common_game_state(2);
+                let placement = find_zigzag_placement(&state);
+                json!(placement_to_json_position(&state.board, placement))
+            },
+            _ => json!("void"),
+        }
+    }
+
+    #[test]
+    fn run_game_move_during_placement_kick_reason() {
+        let remote_player = spawn_remote_player(8093, |message| {
+            match message {
+                ServerToClientMessage::Setup(_) => json!([[0, 0], [1, 0]]),
+                _ => json!("void"),
+            }
+        });
+
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(remote_player),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::WrongMessageType));
+    }
+
+    /// A client sending a message longer than MAX_MESSAGE_BYTES should be kicked as
+    /// soon as the limit is reached rather than having the whole message buffered.
+    #[test]
+    fn run_game_oversized_message_kicked() {
+        let remote_player = spawn_remote_player(8098, |_| {
+            json!("a".repeat(MAX_MESSAGE_BYTES as usize * 256))
+        });
+
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(remote_player),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let start = Instant::now();
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::MessageTooLarge));
+
+        // The fake remote player's response timeout is 5 seconds
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn run_game_placement_during_move_kick_reason() {
+        let remote_player = spawn_remote_player(8094, |message| {
+            match message {
+                ServerToClientMessage::TakeTurn(..) => json!([0, 0]),
+                message => respond_with_zigzag_placement(message),
+            }
+        });
+
+        let players: Vec<Box<dyn Client>> = vec![
+            Box::new(remote_player),
+            Box::new(AIClient::with_zigzag_minmax_strategy()),
+        ];
+
+        let result = run_game(players, None);
+        assert_eq!(result.final_statuses, vec![Kicked, Won]);
+        assert_eq!(result.kick_reasons.get(&PlayerId(0)), Some(&KickReason::WrongMessageType));
+    }
+
+    /// Every move in a standard game removes a tile, so the progress check in
+    /// do_player_turn should never fire no matter how the game is played.
+    #[test]
+    fn test_standard_games_always_make_progress() {
+        for board in [Board::with_no_holes(3, 5, 1), Board::with_no_holes(4, 4, 3), Board::with_holes(4, 4, vec![BoardPosn::from((1, 1))], 3)] {
+            let players: Vec<Box<dyn Client>> = vec![
+                Box::new(AIClient::with_zigzag_minmax_strategy()),
+                Box::new(AIClient::new(Box::new(GreedyStrategy))),
+                Box::new(AIClient::with_zigzag_minmax_strategy()),
+            ];
+
+            let result = run_game(players, Some(board));
+            assert!(result.final_state.is_game_over());
+        }
+    }
+
+    #[test]
+    fn test_remaining_progress() {
+        let mut state = GameState::new(Board::with_no_holes(2, 2, 3), 2);
+        assert_eq!(remaining_progress(&state), 4);
+
+        state.tile_rule = TileRule::Deplete;
+        assert_eq!(remaining_progress(&state), 4 + 4 * 3);
+
+        // Depleting a tile keeps it on the board but still counts as progress
+        let before = remaining_progress(&state);
+        state.deplete_tile(TileId(0));
+        assert_move_made_progress(before, &state);
+    }
+
+    /// A move that leaves the board unchanged, as a broken variant rule might,
+    /// must be caught rather than letting the game go on forever.
+    #[test]
+    #[should_panic(expected = "A move did not change the board")]
+    fn test_no_op_move_is_caught() {
+        let state = GameState::new(Board::with_no_holes(2, 2, 3), 2);
+        assert_move_made_progress(remaining_progress(&state), &state);
+    }
+
+    /// Checks that the shared state given to run_game_with_shared_state has been updated to
+    /// the state of the game each time this client is asked for an action, counting each ask.
+    struct SharedStateCheckingClient {
+        inner: AIClient,
+        shared_state: SharedGameState,
+        turns: Rc<RefCell<usize>>,
+    }
+
+    impl SharedStateCheckingClient {
+        fn assert_shared_state_is(&self, gamestate: &GameState) {
+            let shared_state = self.shared_state.borrow();
+            assert_eq!(shared_state.board, gamestate.board);
+            assert_eq!(shared_state.current_turn, gamestate.current_turn);
+            assert_eq!(shared_state.turn_number(), gamestate.turn_number());
+            *self.turns.borrow_mut() += 1;
+        }
+    }
+
+    impl Client for SharedStateCheckingClient {
+        fn tournament_starting(&mut self) -> Option<()> {
+            self.inner.tournament_starting()
+        }
+
+        fn tournament_ending(&mut self, won: bool) -> Option<()> {
+            self.inner.tournament_ending(won)
+        }
+
+        fn initialize_game(&mut self, initial_gamestate: &GameState, player_color: PlayerColor) -> Option<()> {
+            self.inner.initialize_game(initial_gamestate, player_color)
+        }
+
+        fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
+            self.assert_shared_state_is(gamestate);
+            self.inner.get_placement(gamestate)
+        }
+
+        fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
+            self.assert_shared_state_is(gamestate);
+            self.inner.get_move(gamestate, previous)
+        }
+    }
+
+    /// Every turn should see the shared state updated to the game after the previous
+    /// turn, and once the game ends the shared state should hold the final state.
+    #[test]
+    fn test_run_game_with_shared_state() {
+        let board = Board::with_no_holes(3, 5, 1);
+        let shared_state = Rc::new(RefCell::new(GameState::new(board.clone(), 2)));
+        let turns = Rc::new(RefCell::new(0));
+
+        let clients: Vec<_> = util::make_n(2, |id| {
+            let client = SharedStateCheckingClient {
+                inner: AIClient::with_zigzag_minmax_strategy(),
+                shared_state: shared_state.clone(),
+                turns: turns.clone(),
+            };
+            ClientWithId::new(id, Box::new(client))
+        });
+
+        let result = run_game_with_shared_state(&clients, Some(board), shared_state.clone());
+        assert!(result.final_state.is_game_over());
+
+        // Each turn is a placement or a move, and each move removes a tile
+        let placements: usize = result.final_state.players.values().map(|player| player.penguins.len()).sum();
+        let moves = 15 - result.final_state.board.tile_count();
+        assert_eq!(*turns.borrow(), placements + moves);
+
+        let shared_state = shared_state.borrow();
+        assert_eq!(shared_state.board, result.final_state.board);
+        assert_eq!(shared_state.turn_number(), result.final_state.turn_number());
+        assert_eq!(shared_state.winning_players, result.final_state.winning_players);
+    }
+
+    /// A LiveGame only plays a turn when asked, and stops once the game is over
+    #[test]
+    fn test_live_game_turn_by_turn() {
+        let board = Board::with_no_holes(3, 5, 1);
+        let shared_state = Rc::new(RefCell::new(GameState::new(board.clone(), 2)));
+        let clients: Vec<_> = util::make_n(2, |id| ClientWithId::new(id, Box::new(AIClient::with_zigzag_minmax_strategy())));
+
+        let mut game = LiveGame::new(&clients, Some(board), shared_state.clone());
+        assert!(shared_state.borrow().players.values().all(|player| player.penguins.iter().all(|penguin| penguin.tile_id.is_none())));
+
+        assert!(game.do_turn());
+        assert_eq!(shared_state.borrow().board.tile_count(), 15);
+        assert_eq!(shared_state.borrow().get_occupied_tiles().len(), 1);
+
+        let mut turns = 1;
+        while game.do_turn() {
+            turns += 1;
+        }
+        assert!(game.is_game_over());
+        assert!(!game.do_turn());
+
+        let result = game.into_result();
+        let placements: usize = result.final_state.players.values().map(|player| player.penguins.len()).sum();
+        assert_eq!(turns, placements + 15 - result.final_state.board.tile_count());
+        assert_eq!(shared_state.borrow().winning_players, result.final_state.winning_players);
+    }
 }
@@ -3,15 +3,48 @@ use crate::common::gamestate::GameState;
 use crate::common::player::PlayerColor;
 use crate::common::util;
 use crate::server::client::Client;
+use crate::server::referee::KickReason;
 use crate::server::message::*;
 
 use std::net::TcpStream;
-use std::time::Duration;
-use std::io::Write;
+use std::time::{ Duration, Instant };
+use std::io::{ Read, Write };
 
 use serde::Deserialize;
 use serde_json::Deserializer;
 
+/// The most bytes read from a remote client for a single message. Every valid message from
+/// a client is a short name, position, or action, so anything longer is rejected rather
+/// than buffered, preventing a malicious client from exhausting the server's memory.
+pub const MAX_MESSAGE_BYTES: u64 = 4096;
+
+/// The reasons a message could not be received from a remote client
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ReceiveError {
+    /// No complete message arrived within the timeout
+    Timeout,
+
+    /// The stream ended, e.g. because the client closed its connection
+    Disconnected,
+
+    /// The client sent something that is not valid JSON or not the expected type of message
+    Malformed,
+
+    /// The client sent more than MAX_MESSAGE_BYTES without completing a message
+    TooLarge,
+}
+
+impl From<ReceiveError> for KickReason {
+    fn from(error: ReceiveError) -> KickReason {
+        match error {
+            ReceiveError::Timeout => KickReason::Timeout,
+            ReceiveError::Disconnected => KickReason::Disconnected,
+            ReceiveError::Malformed => KickReason::MalformedMessage,
+            ReceiveError::TooLarge => KickReason::MessageTooLarge,
+        }
+    }
+}
+
 /// A remote client that is communicated with only through TcpStream.
 /// This RemoteClient will handle serialization of each ServerToClientMessage
 /// into json and sending them through tcp.
@@ -22,6 +55,18 @@ use serde_json::Deserializer;
 pub struct RemoteClient {
     stream: TcpStream,
     timeout: Duration,
+
+    /// Why the most recent request failed, if it did. See Client::last_failure.
+    last_failure: Option<KickReason>,
+
+    /// Whether the client opted in at signup to take-turn messages listing its valid moves
+    send_valid_moves: bool,
+
+    /// Whether the client opted in at signup to numbering its responses, see NumberedResponse
+    numbered_responses: bool,
+
+    /// The number of messages sent to the client since it signed up
+    requests_sent: usize,
 }
 
 impl RemoteClient {
@@ -30,43 +75,95 @@ impl RemoteClient {
     pub fn new(stream: TcpStream, timeout: Duration) -> RemoteClient {
         stream.set_read_timeout(Some(timeout)).unwrap();
         stream.set_write_timeout(Some(timeout)).unwrap();
-        RemoteClient { stream, timeout }
+        RemoteClient { stream, timeout, last_failure: None, send_valid_moves: false, numbered_responses: false, requests_sent: 0 }
     }
 
-    /// Receives and validates a name from the given TcpStream.
+    /// Receives and validates a name from the given TcpStream, along with any
+    /// protocol extensions the client opts in to. See SignupMessage.
     /// A valid name:
     /// - Is between 1 and 12 characters inclusive
     /// - Consists of only ascii alphabetic characters
     pub fn get_name(&mut self, timeout: Duration) -> Option<String> {
-        let name: String = self.receive_with_timeout(timeout)?;
+        let signup: SignupMessage = self.receive_with_timeout(timeout).ok()?;
+        let name = signup.name();
         if !name.is_empty() && name.len() <= 12 && name.chars().all(|c| c.is_ascii_alphabetic()) {
-            Some(name)
+            self.send_valid_moves = signup.wants_valid_moves();
+            self.numbered_responses = signup.numbers_responses();
+            Some(name.to_string())
         } else {
             None
         }
     }
 
-    fn receive<'a, T: Deserialize<'a>>(&mut self) -> Option<T> {
+    fn receive<'a, T: Deserialize<'a>>(&mut self) -> Result<T, ReceiveError> {
         self.receive_with_timeout(self.timeout)
     }
 
-    fn receive_with_timeout<'a, T: Deserialize<'a>>(&mut self, timeout: Duration) -> Option<T> {
-        let mut de = Deserializer::from_reader(self.stream.try_clone().unwrap());
-        util::try_with_timeout(timeout, || {
-            T::deserialize(&mut de).ok()
-        })
+    /// Receives a single message of at most MAX_MESSAGE_BYTES. A longer message appears
+    /// to the deserializer as the stream ending, as does the client disconnecting, and
+    /// either causes an error to be returned immediately rather than after the timeout.
+    /// So does a message that is not valid JSON or does not have the type expected.
+    fn receive_with_timeout<'a, T: Deserialize<'a>>(&mut self, timeout: Duration) -> Result<T, ReceiveError> {
+        let mut reader = self.stream.try_clone().unwrap().take(MAX_MESSAGE_BYTES);
+        let mut de = Deserializer::from_reader(&mut reader);
+        let result = util::try_with_timeout(timeout, || {
+            match T::deserialize(&mut de) {
+                Ok(message) => Some(Ok(message)),
+                Err(error) if error.is_eof() => Some(Err(ReceiveError::Disconnected)),
+                // The stream's read timeout expiring is an io error, so keep waiting for the message
+                Err(error) if error.is_io() => None,
+                Err(_) => Some(Err(ReceiveError::Malformed)),
+            }
+        }).unwrap_or(Err(ReceiveError::Timeout));
+
+        // Reaching the end of the reader with its whole limit used means the message was cut off
+        match result {
+            Err(ReceiveError::Disconnected) if reader.limit() == 0 => Err(ReceiveError::TooLarge),
+            result => result,
+        }
+    }
+
+    /// Receives the response to the most recent message sent to a client that numbers its
+    /// responses. Responses the client resent to earlier messages are skipped, as is a
+    /// resent signup message, all within the same timeout as a single response.
+    fn receive_numbered(&mut self) -> Result<ClientToServerMessage, ReceiveError> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let value: serde_json::Value = self.receive_with_timeout(timeout)?;
+
+            match serde_json::from_value(value.clone()) {
+                Ok(NumberedResponse(number, response)) if number == self.requests_sent =>
+                    return serde_json::from_value(response).map_err(|_| ReceiveError::Malformed),
+                Ok(NumberedResponse(number, _)) if number < self.requests_sent => continue,
+                Err(_) if matches!(serde_json::from_value(value), Ok(SignupMessage::WithExtensions { .. })) => continue,
+                _ => return Err(ReceiveError::Malformed),
+            }
+        }
     }
 
-    fn call(&mut self, message: String) -> Option<ClientToServerMessage> {
-        self.stream.write(message.as_bytes()).ok()?;
-        self.receive()
+    /// Sends the given message and receives the client's response, or returns
+    /// why no response could be received.
+    fn call(&mut self, message: String) -> Result<ClientToServerMessage, KickReason> {
+        self.stream.write(message.as_bytes()).map_err(|_| KickReason::Disconnected)?;
+        self.requests_sent += 1;
+
+        if self.numbered_responses {
+            Ok(self.receive_numbered()?)
+        } else {
+            Ok(self.receive()?)
+        }
     }
 
     fn void_call(&mut self, message: String) -> Option<()> {
-        match self.call(message)? {
-            ClientToServerMessage::Void(_) => Some(()),
-            _ => None
-        }
+        let result = self.call(message).and_then(|response| response.to_void());
+        self.record_failure(result)
+    }
+
+    /// Records the reason the given result failed, if it did, as this client's last_failure
+    fn record_failure<T>(&mut self, result: Result<T, KickReason>) -> Option<T> {
+        self.last_failure = result.as_ref().err().copied();
+        result.ok()
     }
 }
 
@@ -90,24 +187,30 @@ impl Client for RemoteClient {
         self.void_call(playing_with_message(&other_colors))
     }
 
+    /// Requests a placement, recording the reason in last_failure if none could be made.
+    /// A client that sends anything other than a position is considered to have sent
+    /// the wrong message type for the phase rather than an illegal placement.
     fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
-        match self.call(setup_message(gamestate))? {
-            ClientToServerMessage::Position(json_placement) => {
-                let tile_id = gamestate.board.get_tile_id(json_placement[1], json_placement[0])?;
-                Some(Placement::new(tile_id))
-            },
-            _ => None
-        }
+        let result = self.call(setup_message(gamestate))
+            .and_then(|response| response.to_placement(&gamestate.board));
+        self.record_failure(result)
     }
 
+    /// Requests a move, recording the reason in last_failure if none could be made.
+    /// A client that sends anything other than an action is considered to have sent
+    /// the wrong message type for the phase rather than an illegal move.
     fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
-        match self.call(take_turn_message(gamestate, previous))? {
-            ClientToServerMessage::Action(json_move) => {
-                let from_tile_id = gamestate.board.get_tile_id(json_move[0][1], json_move[0][0])?;
-                let to_tile_id = gamestate.board.get_tile_id(json_move[1][1], json_move[1][0])?;
-                Some(Move::new(from_tile_id, to_tile_id))
-            },
-            _ => None
-        }
+        let message = if self.send_valid_moves {
+            take_turn_message_with_valid_moves(gamestate, previous)
+        } else {
+            take_turn_message(gamestate, previous)
+        };
+
+        let result = self.call(message).and_then(|response| response.to_move(&gamestate.board));
+        self.record_failure(result)
+    }
+
+    fn last_failure(&self) -> Option<KickReason> {
+        self.last_failure
     }
 }
@@ -2,6 +2,7 @@ use crate::server::remote_client::RemoteClient;
 use crate::server::client::Client;
 
 use std::net::TcpListener;
+use std::sync::mpsc::channel;
 use std::time::{ Duration, Instant };
 
 const SIGNUP_NAME_TIMEOUT: Duration = Duration::from_secs(10);
@@ -16,7 +17,8 @@ const MAX_SIGNUP_PLAYERS: usize = 10;
 /// Will sign up a minimum of MIN_SIGNUP_PLAYERS and a max of MAX_SIGNUP_PLAYERS. If the minimum is
 /// not reached within the first waiting period, one more waiting period will be run. Once
 /// MAX_SIGNUP_PLAYERS have signed up, the waiting period ends.
-/// A player will not be signed up if they don't provide their name within SIGNUP_NAME_TIMEOUT.
+/// A player will not be signed up if they don't provide their name within SIGNUP_NAME_TIMEOUT,
+/// or before the end of the waiting period they connected in.
 pub fn signup_clients(port: usize, client_timeout: Duration, signup_timeout: Duration) -> Option<Vec<Box<dyn Client>>> {
     let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).unwrap();
     listener.set_nonblocking(true).unwrap();
@@ -44,17 +46,26 @@ fn await_clients(
     name_timeout: Duration,
 ) {
     let now = Instant::now();
+    let (named_sender, named_receiver) = channel();
 
-    // Accept clients and their names in order, blocking for each client until they are
-    // both connected and have sent their name. Only then will we try to accept a connection
-    // from the next client.
+    // Accept clients as they connect, waiting for each client's name on its own thread so
+    // that a client who is slow to send their name (or never does) doesn't stop others from
+    // signing up. Clients are signed up in the order their names are received.
     while now.elapsed() < signup_timeout && clients.len() < MAX_SIGNUP_PLAYERS {
         if let Ok((stream, _)) = listener.accept() {
-            let mut remote_client = RemoteClient::new(stream, client_timeout);
-            // as long as clients have a valid name we don't care if they are unique
-            if remote_client.get_name(name_timeout).is_some() {
-                clients.push(Box::new(remote_client));
-            }
+            let named_sender = named_sender.clone();
+            std::thread::spawn(move || {
+                let mut remote_client = RemoteClient::new(stream, client_timeout);
+                // as long as clients have a valid name we don't care if they are unique
+                if remote_client.get_name(name_timeout).is_some() {
+                    // The signup window may have closed already, in which case this client is dropped
+                    named_sender.send(remote_client).ok();
+                }
+            });
+        }
+
+        if let Ok(remote_client) = named_receiver.try_recv() {
+            clients.push(Box::new(remote_client));
         }
     }
 }
@@ -139,7 +150,7 @@ mod tests {
             std::thread::spawn(move || {
                 std::thread::sleep(TIMEOUT_200MS);
                 let ai = AIClient::with_zigzag_minmax_strategy();
-                if let Some(mut client) = ClientToServerProxy::new("name".to_string(), Box::new(ai), "127.0.0.1:8087", TIMEOUT_1S) {
+                if let Ok(mut client) = ClientToServerProxy::new("name".to_string(), Box::new(ai), "127.0.0.1:8087", TIMEOUT_1S) {
                     client.send_name();
                 }
             })
@@ -197,4 +208,44 @@ mod tests {
             thread.join().unwrap();
         }
     }
+
+    /// A client that connects first but never sends its name should not stop the
+    /// clients connecting after it from signing up, even though the name timeout is
+    /// much longer than the signup window.
+    #[test]
+    fn test_await_clients_silent_client() {
+        let silent_thread = std::thread::spawn(|| {
+            // Connect before any of the other clients
+            std::thread::sleep(TIMEOUT_200MS / 2);
+            let ai = AIClient::with_zigzag_minmax_strategy();
+            let _client = ClientToServerProxy::new("silent".to_string(), Box::new(ai), "127.0.0.1:8096", TIMEOUT_1S)
+                .expect("Unable to create client to server proxy");
+            // Stay connected past the end of the signup window without sending a name
+            std::thread::sleep(TIMEOUT_1S * 2);
+        });
+
+        let threads: Vec<_> = (0..5).map(|_| {
+            std::thread::spawn(move || {
+                std::thread::sleep(TIMEOUT_200MS);
+                let ai = AIClient::with_zigzag_minmax_strategy();
+                let mut client = ClientToServerProxy::new("name".to_string(), Box::new(ai), "127.0.0.1:8096", TIMEOUT_1S)
+                    .expect("Unable to create client to server proxy");
+                client.send_name().expect("Unable to send name");
+            })
+        }).collect();
+
+        let listener = TcpListener::bind("127.0.0.1:8096").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let mut clients = vec![];
+        let start = Instant::now();
+        await_clients(&listener, &mut clients, TIMEOUT_1S, TIMEOUT_1S, SIGNUP_NAME_TIMEOUT);
+
+        assert_eq!(clients.len(), 5);
+        assert!(start.elapsed() < TIMEOUT_1S * 2);
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        silent_thread.join().unwrap();
+    }
 }
@@ -0,0 +1,215 @@
+//! This file contains a Client for playing against an external Fish engine
+//! that runs as a child process, speaking line-delimited JSON over its stdin/stdout.
+use crate::common::action::{ Placement, Move, PlayerMove };
+use crate::common::gamestate::GameState;
+use crate::common::player::PlayerColor;
+use crate::server::client::Client;
+use crate::server::referee::KickReason;
+use crate::server::message::*;
+
+use std::io::Write;
+use std::process::{ Child, ChildStdin, Command, Stdio };
+use std::sync::mpsc::{ channel, Receiver, RecvTimeoutError };
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{ Deserializer, Value };
+
+/// A client backed by an external process, allowing engines written in any language to
+/// play in games and tournaments. Each message from message.rs is written to the process's
+/// stdin followed by a newline, and each response is read as a JSON value from its stdout.
+/// Other than the transport, this behaves the same as RemoteClient.
+///
+/// The process is killed when the StdioClient is dropped.
+pub struct StdioClient {
+    child: Child,
+    stdin: ChildStdin,
+
+    /// Each JSON value written to the process's stdout, read on a separate thread so
+    /// that waiting for a response can time out. None is sent for a value that is not
+    /// a valid ClientToServerMessage.
+    responses: Receiver<Option<ClientToServerMessage>>,
+    timeout: Duration,
+
+    /// Whether a response has failed to arrive in time. Any later response may be the late
+    /// reply to that request rather than a reply to the next one, so once this is set no
+    /// more requests are sent and each fails with KickReason::Timeout.
+    timed_out: bool,
+
+    /// Why the most recent request failed, if it did. See Client::last_failure.
+    last_failure: Option<KickReason>,
+}
+
+impl StdioClient {
+    /// Spawns the given command with piped stdin and stdout, waiting at most the
+    /// given Duration for each response from it. Returns an error if the command
+    /// could not be spawned.
+    pub fn spawn(command: &mut Command, timeout: Duration) -> std::io::Result<StdioClient> {
+        let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, responses) = channel();
+        thread::spawn(move || {
+            for value in Deserializer::from_reader(stdout).into_iter::<Value>() {
+                let message = value.ok().and_then(|value| serde_json::from_value(value).ok());
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(StdioClient { child, stdin, responses, timeout, timed_out: false, last_failure: None })
+    }
+
+    /// Sends the given message and receives the process's response. A process that has
+    /// exited, or closed its stdout, is considered to have disconnected. A process that
+    /// has already failed to respond in time is not sent the message, see timed_out.
+    fn call(&mut self, message: String) -> Result<ClientToServerMessage, KickReason> {
+        if self.timed_out {
+            return Err(KickReason::Timeout);
+        }
+
+        writeln!(self.stdin, "{}", message).map_err(|_| KickReason::Disconnected)?;
+        self.stdin.flush().map_err(|_| KickReason::Disconnected)?;
+        match self.responses.recv_timeout(self.timeout) {
+            Ok(Some(response)) => Ok(response),
+            Ok(None) => Err(KickReason::MalformedMessage),
+            Err(RecvTimeoutError::Timeout) => {
+                self.timed_out = true;
+                Err(KickReason::Timeout)
+            },
+            Err(RecvTimeoutError::Disconnected) => Err(KickReason::Disconnected),
+        }
+    }
+
+    fn void_call(&mut self, message: String) -> Option<()> {
+        let result = self.call(message).and_then(|response| response.to_void());
+        self.record_failure(result)
+    }
+
+    /// Records the reason the given result failed, if it did, as this client's last_failure
+    fn record_failure<T>(&mut self, result: Result<T, KickReason>) -> Option<T> {
+        self.last_failure = result.as_ref().err().copied();
+        result.ok()
+    }
+}
+
+impl Drop for StdioClient {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+impl Client for StdioClient {
+    fn tournament_starting(&mut self) -> Option<()> {
+        self.void_call(start_message())
+    }
+
+    fn tournament_ending(&mut self, won: bool) -> Option<()> {
+        self.void_call(end_message(won))
+    }
+
+    fn initialize_game(&mut self, initial_gamestate: &GameState, player_color: PlayerColor) -> Option<()> {
+        self.void_call(playing_as_message(player_color))?;
+
+        let other_colors = initial_gamestate.players.iter()
+            .map(|player| player.1.color)
+            .filter(|color| *color != player_color)
+            .collect::<Vec<PlayerColor>>();
+
+        self.void_call(playing_with_message(&other_colors))
+    }
+
+    /// Requests a placement, recording the reason in last_failure if none could be made.
+    /// See RemoteClient::get_placement.
+    fn get_placement(&mut self, gamestate: &GameState) -> Option<Placement> {
+        let result = self.call(setup_message(gamestate))
+            .and_then(|response| response.to_placement(&gamestate.board));
+        self.record_failure(result)
+    }
+
+    /// Requests a move, recording the reason in last_failure if none could be made.
+    /// See RemoteClient::get_move.
+    fn get_move(&mut self, gamestate: &GameState, previous: &[PlayerMove]) -> Option<Move> {
+        let result = self.call(take_turn_message(gamestate, previous))
+            .and_then(|response| response.to_move(&gamestate.board));
+        self.record_failure(result)
+    }
+
+    fn last_failure(&self) -> Option<KickReason> {
+        self.last_failure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::board::Board;
+    use crate::common::tile::TileId;
+
+    /// Spawns a shell script standing in for an external engine. It places a penguin at
+    /// row 0, column 1 whenever asked for a placement, and acknowledges any other message.
+    fn spawn_fake_engine() -> StdioClient {
+        let script = r#"
+            while read -r line; do
+                case "$line" in
+                    '["setup"'*) echo '[0,1]' ;;
+                    *) echo '"void"' ;;
+                esac
+            done
+        "#;
+        StdioClient::spawn(Command::new("sh").args(["-c", script]), Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn test_stdio_client_placement() {
+        let mut client = spawn_fake_engine();
+        let state = GameState::new(Board::with_no_holes(3, 3, 1), 2);
+
+        assert_eq!(client.tournament_starting(), Some(()));
+        assert_eq!(client.initialize_game(&state, PlayerColor::red), Some(()));
+
+        // Row 0, column 1 of a 3x3 board is tile 3
+        assert_eq!(client.get_placement(&state), Some(Placement::new(TileId(3))));
+        assert_eq!(client.last_failure(), None);
+
+        // The fake engine acknowledges take-turn messages rather than sending a move
+        let mut moving_state = state.clone();
+        moving_state.place_avatar_for_current_player(Placement::new(TileId(3))).unwrap();
+        assert_eq!(client.get_move(&moving_state, &[]), None);
+        assert_eq!(client.last_failure(), Some(KickReason::WrongMessageType));
+    }
+
+    #[test]
+    fn test_stdio_client_unresponsive() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 5"]);
+        let mut client = StdioClient::spawn(&mut command, Duration::from_millis(100)).unwrap();
+        let state = GameState::new(Board::with_no_holes(3, 3, 1), 2);
+
+        assert_eq!(client.get_placement(&state), None);
+        assert_eq!(client.last_failure(), Some(KickReason::Timeout));
+    }
+
+    /// A reply that arrives after the timeout should not be read as the reply to the next message
+    #[test]
+    fn test_stdio_client_late_reply() {
+        let script = r#"
+            read -r line; sleep 0.3; echo '[0,1]'
+            while read -r line; do echo '"void"'; done
+        "#;
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        let mut client = StdioClient::spawn(&mut command, Duration::from_millis(100)).unwrap();
+        let state = GameState::new(Board::with_no_holes(3, 3, 1), 2);
+
+        assert_eq!(client.get_placement(&state), None);
+        assert_eq!(client.last_failure(), Some(KickReason::Timeout));
+
+        thread::sleep(Duration::from_millis(500));
+        assert_eq!(client.tournament_ending(false), None);
+        assert_eq!(client.last_failure(), Some(KickReason::Timeout));
+    }
+}
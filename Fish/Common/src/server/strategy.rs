@@ -2,11 +2,11 @@
 //! the player when playing the game.
 use crate::common::gamestate::GameState;
 use crate::common::game_tree::GameTree;
-use crate::common::player::PlayerId;
+use crate::common::player::{ Player, PlayerId };
 use crate::common::action::{ Placement, Move };
-use crate::common::util::{ all_min_by_key, all_max_by_key };
+use crate::common::util::{ all_min_by_key, all_max_by_key, random_range };
 
-use std::collections::HashMap;
+use std::collections::{ HashMap, BTreeMap };
 
 /// A Strategy is something that can be used to determine which placements
 /// or moves a player should take. Each strategy should ideally search through
@@ -17,6 +17,17 @@ use std::collections::HashMap;
 pub trait Strategy {
     fn find_placement(&mut self, gamestate: &GameState) -> Placement;
     fn find_move(&mut self, game: &mut GameTree) -> Move;
+
+    /// Returns the move find_move would return along with the score the strategy expects
+    /// it to lead to, e.g. the minmax value of the move. Strategies that don't score their
+    /// moves return None for the score.
+    fn find_move_scored(&mut self, game: &mut GameTree) -> (Move, Option<i64>) {
+        (self.find_move(game), None)
+    }
+
+    /// Restarts any randomness this strategy uses from the given seed, see Client::reseed.
+    /// Deterministic strategies ignore it.
+    fn reseed(&mut self, _seed: u64) {}
 }
 
 pub struct ZigZagMinMaxStrategy;
@@ -29,6 +40,273 @@ impl Strategy for ZigZagMinMaxStrategy {
     fn find_move(&mut self, game: &mut GameTree) -> Move {
         find_minmax_move(game, 2)
     }
+
+    fn find_move_scored(&mut self, game: &mut GameTree) -> (Move, Option<i64>) {
+        let (move_, score) = find_minmax_move_scored(game, 2);
+        (move_, Some(score))
+    }
+}
+
+/// A zigzag placement strategy that greedily takes the move earning the most fish
+/// this turn, without considering how opponents may respond. This is equivalent
+/// to a minmax search with a lookahead of 1 round.
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        find_zigzag_placement(gamestate)
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        find_minmax_move(game, 1)
+    }
+
+    fn find_move_scored(&mut self, game: &mut GameTree) -> (Move, Option<i64>) {
+        let (move_, score) = find_minmax_move_scored(game, 1);
+        (move_, Some(score))
+    }
+}
+
+/// A zigzag placement, minmax move strategy that picks its lookahead for each move
+/// based on how many tiles remain on the board. See adaptive_lookahead.
+pub struct AdaptiveDepthStrategy;
+
+impl Strategy for AdaptiveDepthStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        find_zigzag_placement(gamestate)
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        let lookahead = adaptive_lookahead(game.get_state());
+        find_minmax_move(game, lookahead)
+    }
+
+    fn find_move_scored(&mut self, game: &mut GameTree) -> (Move, Option<i64>) {
+        let lookahead = adaptive_lookahead(game.get_state());
+        let (move_, score) = find_minmax_move_scored(game, lookahead);
+        (move_, Some(score))
+    }
+}
+
+/// Returns the minmax lookahead to use for the given gamestate. Large boards
+/// have too many branches to search deeply, so the lookahead grows as tiles are
+/// removed and the game nears its end.
+pub fn adaptive_lookahead(state: &GameState) -> usize {
+    match state.board.tiles.len() {
+        0 ..= 12 => 4,
+        13 ..= 20 => 3,
+        21 ..= 30 => 2,
+        _ => 1,
+    }
+}
+
+/// A zigzag placement, minmax move strategy that scores the states at the
+/// lookahead cutoff with mobility_heuristic rather than just the player's score.
+pub struct MobilityStrategy {
+    /// How many rounds to look ahead when searching for a move
+    pub lookahead: usize,
+
+    /// How many points each move of mobility advantage is worth. See mobility_heuristic.
+    pub mobility_weight: i64,
+}
+
+impl Strategy for MobilityStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        find_zigzag_placement(gamestate)
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        let mobility_weight = self.mobility_weight;
+        find_minmax_move_with_heuristic(game, self.lookahead, &move |state, player| {
+            mobility_heuristic(state, player, mobility_weight)
+        })
+    }
+}
+
+/// A zigzag placement strategy that finds the same moves as ZigZagMinMaxStrategy with the
+/// given lookahead, searching with alpha-beta pruning and ordered moves to skip branches
+/// that cannot change the result. See find_minmax_move_pruned.
+pub struct PrunedMinMaxStrategy {
+    /// How many rounds to look ahead when searching for a move
+    pub lookahead: usize,
+}
+
+impl Strategy for PrunedMinMaxStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        find_zigzag_placement(gamestate)
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        find_minmax_move_pruned(game, self.lookahead, true)
+    }
+}
+
+/// A zigzag placement, minmax move strategy that breaks ties between equally-scored
+/// moves by avoiding dead ends. See find_minmax_move_avoiding_dead_ends.
+pub struct DeadEndAvoidingStrategy {
+    /// How many rounds to look ahead when searching for a move
+    pub lookahead: usize,
+}
+
+impl Strategy for DeadEndAvoidingStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        find_zigzag_placement(gamestate)
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        find_minmax_move_avoiding_dead_ends(game, self.lookahead)
+    }
+}
+
+/// A zigzag placement strategy that searches for moves like ZigZagMinMaxStrategy, except
+/// opponents are modeled as choosing uniformly at random between their moves rather than
+/// always minimizing this player's score. This plays better against weak opponents, since
+/// it won't pass up a move only because a perfect opponent could counter it.
+/// See find_expectimax_move.
+pub struct ExpectimaxStrategy {
+    /// How many rounds to look ahead when searching for a move
+    pub lookahead: usize,
+}
+
+impl Strategy for ExpectimaxStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        find_zigzag_placement(gamestate)
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        find_expectimax_move(game, self.lookahead)
+    }
+}
+
+/// A strategy that places penguins on and moves them to pseudo-random tiles, chosen
+/// uniformly among the valid placements and moves. The same seed always plays the same
+/// game against the same opponents.
+pub struct RandomStrategy {
+    seed_state: u64,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> RandomStrategy {
+        RandomStrategy { seed_state: seed }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn find_placement(&mut self, gamestate: &GameState) -> Placement {
+        let occupied_tiles = gamestate.get_occupied_tiles();
+        let open_tiles: Vec<_> = gamestate.board.tiles.keys()
+            .filter(|tile_id| !occupied_tiles.contains(tile_id))
+            .collect();
+
+        let index = random_range(&mut self.seed_state, open_tiles.len());
+        Placement::new(*open_tiles[index])
+    }
+
+    fn find_move(&mut self, game: &mut GameTree) -> Move {
+        let moves = game.get_state().get_valid_moves();
+        moves[random_range(&mut self.seed_state, moves.len())]
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.seed_state = seed;
+    }
+}
+
+/// Optional parameters for strategies constructed by name with strategy_from_name.
+/// Strategies ignore any parameters that don't apply to them, and use their own
+/// defaults for any that are None.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StrategyParams {
+    /// How many rounds to look ahead when searching for a move
+    pub lookahead: Option<usize>,
+
+    /// How many points each move of mobility advantage is worth, see MobilityStrategy
+    pub mobility_weight: Option<i64>,
+
+    /// The seed for strategies that make random choices, see RandomStrategy.
+    /// Seeded games reseed these strategies from the game's seed, see Strategy::reseed.
+    pub seed: Option<u64>,
+}
+
+/// Constructs a strategy from the given parameters, see STRATEGY_REGISTRY.
+pub type StrategyConstructor = fn(StrategyParams) -> Box<dyn Strategy>;
+
+/// Every strategy that can be constructed by name, along with its constructor.
+pub const STRATEGY_REGISTRY: &[(&str, StrategyConstructor)] = &[
+    ("zigzag-minmax", |_| Box::new(ZigZagMinMaxStrategy)),
+    ("greedy", |_| Box::new(GreedyStrategy)),
+    ("adaptive", |_| Box::new(AdaptiveDepthStrategy)),
+    ("mobility", |params| Box::new(MobilityStrategy {
+        lookahead: params.lookahead.unwrap_or(2),
+        mobility_weight: params.mobility_weight.unwrap_or(1),
+    })),
+    ("pruned-minmax", |params| Box::new(PrunedMinMaxStrategy {
+        lookahead: params.lookahead.unwrap_or(2),
+    })),
+    ("avoid-dead-ends", |params| Box::new(DeadEndAvoidingStrategy {
+        lookahead: params.lookahead.unwrap_or(2),
+    })),
+    ("expectimax", |params| Box::new(ExpectimaxStrategy {
+        lookahead: params.lookahead.unwrap_or(2),
+    })),
+    ("random", |params| Box::new(RandomStrategy::new(params.seed.unwrap_or(0)))),
+];
+
+/// Constructs the strategy registered under the given name in STRATEGY_REGISTRY with
+/// the given parameters, or returns None if there is no strategy with that name.
+/// This allows the binaries to choose strategies from their arguments.
+pub fn strategy_from_name(name: &str, params: StrategyParams) -> Option<Box<dyn Strategy>> {
+    STRATEGY_REGISTRY.iter()
+        .find(|(registered_name, _)| *registered_name == name)
+        .map(|(_, constructor)| constructor(params))
+}
+
+/// Scores the given state for a minmax search on behalf of the given player.
+/// The search always treats higher values as better for the player.
+pub type Heuristic = dyn Fn(&GameState, PlayerId) -> i64;
+
+/// Combines the values of each move an opponent could make into the value of the
+/// opponent's turn, modeling how the searching player expects opponents to play.
+/// The given values are never empty.
+pub type OpponentModel = dyn Fn(&[i64]) -> i64;
+
+/// The default opponent model: opponents take whichever move is worst for the searching player.
+pub fn minimizing_opponents(values: &[i64]) -> i64 {
+    values.iter().copied().min().unwrap()
+}
+
+/// Models opponents that choose uniformly at random between their moves, valuing
+/// their turn at the average of its moves' values, rounded to the nearest integer.
+pub fn random_opponents(values: &[i64]) -> i64 {
+    let total: i64 = values.iter().sum();
+    (total as f64 / values.len() as f64).round() as i64
+}
+
+/// The default heuristic: the player's current score.
+pub fn score_heuristic(state: &GameState, player: PlayerId) -> i64 {
+    state.player_score(player) as i64
+}
+
+/// A heuristic favoring states where the given player has more legal moves than
+/// their opponents: score + mobility_weight * (player's mobility - opponents' mobility),
+/// where a player's mobility is the number of legal moves their penguins could make
+/// and the opponents' mobility is the total of every other player's mobility.
+pub fn mobility_heuristic(state: &GameState, player: PlayerId, mobility_weight: i64) -> i64 {
+    let occupied_tiles = state.get_occupied_tiles();
+    let mobility = |player: &Player| -> i64 {
+        player.penguins.iter()
+            .filter_map(|penguin| state.get_tile(penguin.tile_id?))
+            .map(|tile| tile.all_reachable_tiles(&state.board, &occupied_tiles).len() as i64)
+            .sum()
+    };
+
+    let player_mobility = state.players.get(&player).map_or(0, &mobility);
+    let opponent_mobility: i64 = state.players.values()
+        .filter(|opponent| opponent.player_id != player)
+        .map(&mobility)
+        .sum();
+
+    score_heuristic(state, player) + mobility_weight * (player_mobility - opponent_mobility)
 }
 
 /// Finds a spot to place a penguin for the current player
@@ -68,58 +346,348 @@ pub fn find_zigzag_placement(state: &GameState) -> Placement {
 /// 
 /// Panics if the game is already over.
 pub fn find_minmax_move(game: &mut GameTree, lookahead: usize) -> Move {
+    find_minmax_move_with_heuristic(game, lookahead, &score_heuristic)
+}
+
+/// Returns the move found by find_minmax_move along with its score: the current player's
+/// score after the given number of rounds, assuming every player plays as find_minmax_move
+/// expects them to.
+///
+/// Panics if the game is already over.
+pub fn find_minmax_move_scored(game: &mut GameTree, lookahead: usize) -> (Move, i64) {
+    find_scored_minmax_move_with_cache(game, lookahead, &score_heuristic, &minimizing_opponents, &mut MaxiMinCache::new())
+}
+
+/// Returns the move to maximize the current player's expected score after looking ahead
+/// a given number of rounds, as in find_minmax_move, except opponents are assumed to pick
+/// each of their moves with equal probability. See random_opponents.
+///
+/// Panics if the game is already over.
+pub fn find_expectimax_move(game: &mut GameTree, lookahead: usize) -> Move {
+    find_scored_minmax_move_with_cache(game, lookahead, &score_heuristic, &random_opponents, &mut MaxiMinCache::new()).0
+}
+
+/// Returns the move found by find_minmax_move, except states at the end of the lookahead
+/// are valued using the given heuristic rather than the current player's score.
+///
+/// Panics if the game is already over.
+pub fn find_minmax_move_with_heuristic(game: &mut GameTree, lookahead: usize, heuristic: &Heuristic) -> Move {
+    find_minmax_move_with_cache(game, lookahead, heuristic, &mut MaxiMinCache::new())
+}
+
+/// Returns the move found by find_minmax_move_with_heuristic, using the given cache
+/// to avoid re-searching states that have already been seen.
+fn find_minmax_move_with_cache(game: &mut GameTree, lookahead: usize, heuristic: &Heuristic, cache: &mut MaxiMinCache) -> Move {
+    find_scored_minmax_move_with_cache(game, lookahead, heuristic, &minimizing_opponents, cache).0
+}
+
+/// Returns the move found by find_minmax_move_with_cache along with its heuristic value,
+/// valuing opponents' turns with the given opponent model.
+fn find_scored_minmax_move_with_cache(game: &mut GameTree, lookahead: usize, heuristic: &Heuristic,
+    opponent_model: &OpponentModel, cache: &mut MaxiMinCache) -> (Move, i64)
+{
     let player_to_maximize_score = game.get_state().current_turn;
+    let mut node_budget = usize::MAX;
+    let (score, move_) = find_best_score_and_moves(game, player_to_maximize_score, lookahead, cache, &mut node_budget,
+        heuristic, opponent_model);
+    (move_.expect("The game is over, there are no valid moves!"), score)
+}
+
+/// Returns a move with the same score as find_minmax_move, but when several moves tie
+/// for the best score, prefers moves whose penguin can still move again afterward over
+/// moves leaving it stranded in a dead end. Remaining ties are broken by position as in
+/// find_minmax_move. Since this only breaks ties, a move into a dead end is still taken
+/// whenever it scores strictly better than every other move.
+/// A lookahead of 0 is treated as a lookahead of 1.
+///
+/// Panics if the game is already over.
+pub fn find_minmax_move_avoiding_dead_ends(game: &mut GameTree, lookahead: usize) -> Move {
+    let player = game.get_state().current_turn;
     let mut cache = MaxiMinCache::new();
-    let (_, move_) = find_best_score_and_moves(game, player_to_maximize_score, lookahead, &mut cache);
-    move_.expect("The game is over, there are no valid moves!")
+    let mut node_budget = usize::MAX;
+
+    let moves = game.map(|game_after_move| {
+        find_best_score_and_moves(game_after_move, player, lookahead.max(1) - 1, &mut cache, &mut node_budget,
+            &score_heuristic, &minimizing_opponents).0
+    });
+
+    let state = game.get_state();
+    let moves = all_max_by_key(moves.into_iter(), |(_, score)| *score);
+    let moves = all_min_by_key(moves, |(move_, _)| is_dead_end(state, *move_));
+    let moves = all_min_by_key(moves, |(move_, _)| state.board.get_tile_position(move_.from));
+    let mut moves = all_min_by_key(moves, |(move_, _)| state.board.get_tile_position(move_.to));
+
+    moves.next().expect("The game is over, there are no valid moves!").0
+}
+
+/// Returns true if the penguin making the given move would have no reachable
+/// tiles from its destination immediately after the move is made.
+fn is_dead_end(state: &GameState, move_: Move) -> bool {
+    let mut occupied_tiles = state.get_occupied_tiles();
+    occupied_tiles.remove(&move_.from);
+
+    // The tile the penguin leaves is removed, so it cannot be moved through
+    let mut board = state.board.clone();
+    board.remove_tile(move_.from);
+
+    match board.tiles.get(&move_.to) {
+        Some(tile) => tile.all_reachable_tiles(&board, &occupied_tiles).is_empty(),
+        None => true,
+    }
+}
+
+/// Returns the move to maximize the current player's score, as in find_minmax_move,
+/// but stops searching once node_budget GameTree nodes have been expanded. This
+/// bounds the computation for an arbitrarily large lookahead.
+///
+/// The search is iteratively deepened, starting with a lookahead of 1 round. Once
+/// the budget runs out, the best move from the deepest fully-completed search is
+/// returned. The root node is always expanded so a valid move is always returned,
+/// even if the budget runs out before a single search completes. A lookahead of 0
+/// is treated as a lookahead of 1 for the same reason.
+///
+/// Panics if the game is already over.
+pub fn find_minmax_move_capped(game: &mut GameTree, lookahead: usize, node_budget: usize) -> Move {
+    let player_to_maximize_score = game.get_state().current_turn;
+    let mut node_budget = std::cmp::max(node_budget, 1);
+    let mut best_move = None;
+
+    for depth in 1 ..= lookahead.max(1) {
+        let mut cache = MaxiMinCache::new();
+        let (_, move_) = find_best_score_and_moves(game, player_to_maximize_score, depth, &mut cache, &mut node_budget,
+            &score_heuristic, &minimizing_opponents);
+
+        // Only keep the results of an incomplete search if there were no previous results
+        if node_budget > 0 || best_move.is_none() {
+            best_move = move_;
+        }
+        if node_budget == 0 {
+            break;
+        }
+    }
+
+    best_move.expect("The game is over, there are no valid moves!")
+}
+
+/// Returns the same move as find_minmax_move, found with an alpha-beta search which skips
+/// any branches that cannot change the result. If order_moves is true, the moves at each
+/// node are searched in order of a quick estimate of the fish they earn (see search_order)
+/// so that good moves are found early and more branches are skipped.
+///
+/// Moves at the root are still compared by their exact scores, so ties are broken by
+/// position exactly as in find_minmax_move. A lookahead of 0 is treated as a lookahead of 1.
+///
+/// Panics if the game is already over.
+pub fn find_minmax_move_pruned(game: &mut GameTree, lookahead: usize, order_moves: bool) -> Move {
+    let mut nodes_expanded = 0;
+    find_pruned_move(game, lookahead, order_moves, &mut nodes_expanded)
 }
 
-type MaxiMinCache = HashMap<u64, (usize, Move)>;
+/// Searches for the move found by find_minmax_move_pruned, counting each node whose
+/// children are searched in nodes_expanded.
+fn find_pruned_move(game: &mut GameTree, lookahead: usize, order_moves: bool, nodes_expanded: &mut usize) -> Move {
+    let player = game.get_state().current_turn;
+    let board = game.get_state().board.clone();
+    let mut best: Option<(i64, Move)> = None;
 
+    *nodes_expanded += 1;
+    for move_ in search_order(game.get_state(), order_moves) {
+        // Searching with alpha just below the best score so far means any move scoring
+        // at least as well is given its exact score, so ties can be broken by position.
+        let alpha = best.map_or(i64::MIN, |(score, _)| score.saturating_sub(1));
+        let game_after_move = game.get_game_after_move(move_).unwrap();
+        let score = find_pruned_score(game_after_move, player, lookahead.max(1) - 1, alpha, i64::MAX, order_moves, nodes_expanded);
+
+        let position = |move_: Move| (board.get_tile_position(move_.from), board.get_tile_position(move_.to));
+        best = match best {
+            Some((best_score, best_move)) if best_score > score
+                || (best_score == score && position(best_move) <= position(move_)) => best,
+            _ => Some((score, move_)),
+        };
+    }
+
+    best.expect("The game is over, there are no valid moves!").1
+}
+
+/// Returns the heuristic value of the given game for the given player, as found by
+/// find_best_score_and_moves, if it is within the window between alpha and beta.
+/// Otherwise, this returns a bound at or outside of that window:
+/// a value <= alpha if the true value is <= alpha, or >= beta if the true value is >= beta.
+fn find_pruned_score(game: &mut GameTree, player: PlayerId, lookahead: usize, mut alpha: i64, mut beta: i64,
+    order_moves: bool, nodes_expanded: &mut usize) -> i64
+{
+    let state = game.get_state();
+    let is_players_turn = state.current_turn == player;
+
+    if game.is_game_over() || lookahead == 0 || !state.can_player_move(player) {
+        return score_heuristic(state, player);
+    }
+
+    let lookahead = lookahead - if is_players_turn { 1 } else { 0 };
+    let mut value = if is_players_turn { i64::MIN } else { i64::MAX };
+
+    *nodes_expanded += 1;
+    for move_ in search_order(game.get_state(), order_moves) {
+        let game_after_move = game.get_game_after_move(move_).unwrap();
+        let score = find_pruned_score(game_after_move, player, lookahead, alpha, beta, order_moves, nodes_expanded);
+
+        if is_players_turn {
+            value = std::cmp::max(value, score);
+            alpha = std::cmp::max(alpha, value);
+        } else {
+            value = std::cmp::min(value, score);
+            beta = std::cmp::min(beta, value);
+        }
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    value
+}
+
+/// Returns the valid moves of the given state in the order they should be searched.
+/// If order_moves is true, moves are sorted by the fish on the tile they capture plus
+/// the fish on the tile they land on (captured by the penguin's next move), highest first.
+/// Otherwise the moves are left in the order given by GameState::get_valid_moves.
+fn search_order(state: &GameState, order_moves: bool) -> Vec<Move> {
+    let mut moves = state.get_valid_moves();
+    if order_moves {
+        let fish_on = |tile_id| state.get_tile(tile_id).map_or(0, |tile| tile.get_fish_count());
+        moves.sort_by_key(|move_| std::cmp::Reverse(fish_on(move_.from) + fish_on(move_.to)));
+    }
+    moves
+}
+
+/// The maximum number of states a MaxiMinCache holds by default
+const MAXIMIN_CACHE_CAPACITY: usize = 1 << 16;
+
+/// Caches the best score and move found for each state hash during a minmax search.
+/// The cache holds a fixed number of entries, evicting the least recently used entry
+/// once full so that memory is bounded on deep searches. Since an evicted state is
+/// simply searched again, the capacity only affects how long a search takes.
+struct MaxiMinCache {
+    capacity: usize,
+
+    /// Maps each state hash to its (score, move) and the time it was last used
+    entries: HashMap<u64, (i64, Move, u64)>,
+
+    /// Maps the time each entry was last used to its state hash, oldest first
+    recently_used: BTreeMap<u64, u64>,
+
+    /// Incremented on each use so that every entry has a unique last-used time
+    clock: u64,
+}
+
+impl MaxiMinCache {
+    fn new() -> MaxiMinCache {
+        MaxiMinCache::with_capacity(MAXIMIN_CACHE_CAPACITY)
+    }
+
+    /// Creates an empty cache holding at most the given number of entries.
+    /// Panics if capacity is 0.
+    fn with_capacity(capacity: usize) -> MaxiMinCache {
+        assert!(capacity > 0, "A MaxiMinCache must be able to hold at least 1 entry");
+        MaxiMinCache { capacity, entries: HashMap::new(), recently_used: BTreeMap::new(), clock: 0 }
+    }
+
+    /// Returns the cached score and move for the given state hash, marking it as the most recently used
+    fn get(&mut self, hash: u64) -> Option<(i64, Move)> {
+        let clock = self.tick();
+        let (score, move_, last_used) = self.entries.get_mut(&hash)?;
+        self.recently_used.remove(last_used);
+        self.recently_used.insert(clock, hash);
+        *last_used = clock;
+        Some((*score, *move_))
+    }
+
+    /// Caches the score and move for the given state hash, evicting the least
+    /// recently used entry if the cache is full.
+    fn insert(&mut self, hash: u64, score: i64, move_: Move) {
+        let clock = self.tick();
+        if let Some((_, _, last_used)) = self.entries.get(&hash) {
+            self.recently_used.remove(last_used);
+        } else if self.entries.len() == self.capacity {
+            let (&oldest, &oldest_hash) = self.recently_used.iter().next().unwrap();
+            self.recently_used.remove(&oldest);
+            self.entries.remove(&oldest_hash);
+        }
+        self.entries.insert(hash, (score, move_, clock));
+        self.recently_used.insert(clock, hash);
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+/// Hashes only the parts of the state that affect the rest of the game, see GameState::canonical_key
 fn hash_state(state: &GameState) -> u64 {
     use std::hash::{ Hash, Hasher };
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    state.hash(&mut hasher);
+    state.canonical_key().hash(&mut hasher);
     hasher.finish()
 }
 
 /// Traverse the Game tree to find a set of moves that maximizes the score of the given player,
-/// assuming all opponents want to minimize the player's score.
+/// assuming opponents play as described by the given opponent model. With minimizing_opponents,
+/// all opponents want to minimize the player's score.
 /// 
-/// Returns the score of the given player and a Vec of all moves of each player in reverse order
-///     i.e. the first move taken in the path occurs last in the Vec
+/// Returns the heuristic value of the given player's best outcome and the move to reach it
 /// 
 /// Termination: lookahead decreases by 1 each time the given player takes a turn. Since the
 ///   turn order will always come back to the same player eventually (unless the game ends), this is
-///   continuously decreasing. The function terminates when either lookahead reaches 0 or a Game::End\
-///   node is given, whichever comes first.
+///   continuously decreasing. The function terminates when either lookahead reaches 0, the
+///   node_budget reaches 0, or a Game::End node is given, whichever comes first.
+///
+/// The node_budget is decreased by 1 for each node whose children are searched. Once it reaches 0
+/// any further nodes are treated as leaves and scored as-is.
+///
+/// Leaves are scored with the given heuristic, and the value of each opponent's turn is the
+/// opponent model applied to the values of each of their moves.
 /// 
 /// See find_best_move for the specific algorithm used to select the best move.
-fn find_best_score_and_moves(game: &mut GameTree, player: PlayerId, lookahead: usize, cache: &mut MaxiMinCache) -> (usize, Option<Move>) {
+fn find_best_score_and_moves(game: &mut GameTree, player: PlayerId, lookahead: usize, cache: &mut MaxiMinCache,
+    node_budget: &mut usize, heuristic: &Heuristic, opponent_model: &OpponentModel) -> (i64, Option<Move>)
+{
     let state = game.get_state();
     let is_players_turn = state.current_turn == player;
 
-    if game.is_game_over() || lookahead == 0 || !state.can_player_move(player) {
-        (state.player_score(player), None)
+    if game.is_game_over() || lookahead == 0 || *node_budget == 0 || !state.can_player_move(player) {
+        (heuristic(state, player), None)
     } else {
         // Lookahead is counted in rounds where every player takes a turn,
         // so only decrease it when the given player takes a turn.
         let lookahead = lookahead - if is_players_turn { 1 } else { 0 };
 
         let hash = hash_state(state);
-        if let Some((score, move_)) = cache.get(&hash) {
-            return (*score, Some(*move_));
+        if let Some((score, move_)) = cache.get(hash) {
+            return (score, Some(move_));
         }
 
+        *node_budget -= 1;
+
         // Recurse first, getting the expected states after each possible move the current player can take
         // assuming the given player maximizes their score and all opponents minimize it.
         let possible_moves = game.map(|game_after_move| {
-            find_best_score_and_moves(game_after_move, player, lookahead, cache)
+            find_best_score_and_moves(game_after_move, player, lookahead, cache, node_budget, heuristic, opponent_model)
         });
 
+        // The opponent's turn is valued by the opponent model, even though the move returned is the one that
+        // minimizes the given player's score. Only the given player's moves are ever taken from the search.
+        let opponent_value = if is_players_turn {
+            None
+        } else {
+            let values: Vec<i64> = possible_moves.values().map(|(score, _)| *score).collect();
+            Some(opponent_model(&values))
+        };
+
         // Maximize the score for the given player if it's their turn, otherwise take the move that minimizes it
         let (score, move_) = find_best_move(game.get_state(), is_players_turn, possible_moves);
-        cache.insert(hash, (score, move_));
+        let score = opponent_value.unwrap_or(score);
+        cache.insert(hash, score, move_);
         (score, Some(move_))
     }
 }
@@ -131,14 +699,15 @@ fn find_best_score_and_moves(game: &mut GameTree, player: PlayerId, lookahead: u
 /// multiple equally-scored moves.
 /// 
 /// Returns the (key, value) pair of the given hashmap that represents the best turn following the rules above.
-fn find_best_move(state: &GameState, is_players_turn: bool, moves: HashMap<Move, (usize, Option<Move>)>) -> (usize, Move) {
+fn find_best_move(state: &GameState, is_players_turn: bool, moves: HashMap<Move, (i64, Option<Move>)>) -> (i64, Move) {
     let moves = if is_players_turn {
         all_max_by_key(moves.into_iter(), |(_, (score, _))| *score)
     } else {
         all_min_by_key(moves.into_iter(), |(_, (score, _))| *score)
     };
 
-    // If we still have a tie, settle it by the penguin's position then the destination position in that  order
+    // If we still have a tie, settle it by the penguin's position then the destination position in that  order.
+    // This intentionally compares BoardPosns rather than using Move's Ord, which orders by TileId instead.
     let moves = all_min_by_key(moves, |(move_, _)| state.board.get_tile_position(move_.from));
     let mut moves = all_min_by_key(moves, |(move_, _)| state.board.get_tile_position(move_.to));
 
@@ -150,6 +719,11 @@ fn find_best_move(state: &GameState, is_players_turn: bool, moves: HashMap<Move,
 pub mod tests {
     use super::*;
     use crate::common::tile::TileId;
+    use crate::common::board::{ Board, BoardPreset };
+    use crate::server::ai_client::AIClient;
+    use crate::server::client::Client;
+    use crate::common::gamestate::tests::single_penguin_path_game;
+    use crate::server::referee::{ run_game, ClientStatus };
 
     /// Place a penguin using the ZigZag strategy
     pub fn take_zigzag_placement(state: &mut GameState) {
@@ -221,9 +795,7 @@ pub mod tests {
         // has 3 fish on each tile
         let mut state = GameState::with_default_board(3, 5, 2);
 
-        while !state.all_penguins_are_placed() {
-            take_zigzag_placement(&mut state);
-        }
+        state.place_all_zigzag();
 
         // placements of penguins (p1 = player1, p2 = player2)
         // 3 fish on each tile
@@ -246,15 +818,52 @@ pub mod tests {
         assert!(penguin.is_some());
     }
 
+    /// This test ensures that an enormous lookahead with a tiny node budget
+    /// still quickly returns a valid move rather than searching the whole tree.
+    #[test]
+    fn test_move_penguin_minmax_capped() {
+        let mut state = GameState::with_default_board(5, 5, 2);
+
+        state.place_all_zigzag();
+
+        let start_time = std::time::Instant::now();
+        let move_ = find_minmax_move_capped(&mut GameTree::new(&state), usize::MAX, 10);
+
+        assert!(start_time.elapsed() < std::time::Duration::from_secs(1));
+        assert!(state.get_valid_moves().contains(&move_));
+
+        // With an unlimited budget the result should match the uncapped search
+        let capped_move = find_minmax_move_capped(&mut GameTree::new(&state), 1, usize::MAX);
+        assert_eq!(capped_move, find_minmax_move(&mut GameTree::new(&state), 1));
+
+        // A lookahead of 0 still searches a single round rather than finding no move
+        assert_eq!(find_minmax_move_capped(&mut GameTree::new(&state), 0, usize::MAX), capped_move);
+    }
+
+    /// A full 5x5 board should be searched shallowly while the same board with
+    /// most of its tiles removed should be searched more deeply.
+    #[test]
+    fn test_adaptive_lookahead() {
+        let mut state = GameState::with_default_board(5, 5, 2);
+        let full_board_lookahead = adaptive_lookahead(&state);
+        assert_eq!(full_board_lookahead, 2);
+
+        for tile_id in 0 .. 15 {
+            state.board.remove_tile(TileId(tile_id));
+        }
+        let nearly_finished_lookahead = adaptive_lookahead(&state);
+        assert_eq!(nearly_finished_lookahead, 4);
+
+        assert!(nearly_finished_lookahead > full_board_lookahead);
+    }
+
     /// This test ensures that the algorithm will make winning moves
     /// when looking many turns ahead.
     #[test]
     fn test_move_penguin_minmax_lookahead() {
         let mut state = GameState::with_default_board(3, 5, 2);
 
-        while !state.all_penguins_are_placed() {
-            take_zigzag_placement(&mut state); // place all penguins using the zigzag method
-        }
+        state.place_all_zigzag();
 
         // initial placements of penguins (p1 = player1, p2 = player2)
         // 3 fish on each tile
@@ -305,4 +914,223 @@ pub mod tests {
         let new_pos = state.board.get_tile_position(new_tile);
         assert_eq!(new_pos, (3, 1).into());
     }
+
+    /// Pit the mobility heuristic against the plain score-based minmax strategy, both
+    /// looking ahead 2 rounds, on a 5x5 board with 1 fish per tile. The mobility strategy
+    /// should win whether it moves first or second.
+    #[test]
+    fn test_mobility_strategy_beats_zigzag_minmax() {
+        let make_mobility_player = || -> Box<dyn Client> {
+            Box::new(AIClient::new(Box::new(MobilityStrategy { lookahead: 2, mobility_weight: 1 })))
+        };
+
+        let players = vec![make_mobility_player(), Box::new(AIClient::with_zigzag_minmax_strategy())];
+        let result = run_game(players, Some(Board::with_no_holes(5, 5, 1)));
+        assert_eq!(result.final_statuses, vec![ClientStatus::Won, ClientStatus::Lost]);
+
+        let players = vec![Box::new(AIClient::with_zigzag_minmax_strategy()), make_mobility_player()];
+        let result = run_game(players, Some(Board::with_no_holes(5, 5, 1)));
+        assert_eq!(result.final_statuses, vec![ClientStatus::Lost, ClientStatus::Won]);
+    }
+
+    /// Totals the fish earned by the first player over games against RandomStrategy
+    /// opponents, one game per seed, all played on the same board.
+    fn total_fish_against_random(make_strategy: impl Fn() -> Box<dyn Strategy>, seeds: std::ops::Range<u64>, board: &Board) -> usize {
+        seeds.map(|seed| {
+            let players: Vec<Box<dyn Client>> = vec![
+                Box::new(AIClient::new(make_strategy())),
+                Box::new(AIClient::new(Box::new(RandomStrategy::new(seed)))),
+            ];
+            let result = run_game(players, Some(board.clone()));
+            result.final_state.player_score(PlayerId(0))
+        }).sum()
+    }
+
+    /// Against an opponent moving at random, assuming the opponent plays perfectly makes the
+    /// minmax strategy too cautious. Expectimax should earn more fish over the same seeded games.
+    #[test]
+    fn test_expectimax_beats_minmax_against_random() {
+        let board = Board::preset(BoardPreset::Sparse);
+        let expectimax_fish = total_fish_against_random(|| Box::new(ExpectimaxStrategy { lookahead: 2 }), 0 .. 3, &board);
+        let minmax_fish = total_fish_against_random(|| Box::new(ZigZagMinMaxStrategy), 0 .. 3, &board);
+        assert!(expectimax_fish > minmax_fish, "expectimax earned {} fish, minmax earned {}", expectimax_fish, minmax_fish);
+    }
+
+    #[test]
+    fn test_opponent_models() {
+        assert_eq!(minimizing_opponents(&[3, -1, 4]), -1);
+        assert_eq!(random_opponents(&[3, -1, 4]), 2);
+        assert_eq!(random_opponents(&[1, 2]), 2);
+        assert_eq!(random_opponents(&[5]), 5);
+    }
+
+    /// RandomStrategy should only make valid placements and moves, the same ones for the same seed
+    #[test]
+    fn test_random_strategy() {
+        let play = |seed| {
+            let players: Vec<Box<dyn Client>> = vec![
+                Box::new(AIClient::new(Box::new(RandomStrategy::new(seed)))),
+                Box::new(AIClient::new(Box::new(RandomStrategy::new(seed + 1)))),
+            ];
+            let result = run_game(players, Some(Board::preset(BoardPreset::Sparse)));
+            assert_eq!(result.final_statuses.iter().filter(|status| **status == ClientStatus::Kicked).count(), 0);
+            result.final_state.player_score(PlayerId(0))
+        };
+        assert_eq!(play(7), play(7));
+    }
+
+    /// Each registered strategy should play a full game without being kicked
+    #[test]
+    fn test_strategy_from_name() {
+        for (name, _) in STRATEGY_REGISTRY {
+            let strategy = strategy_from_name(name, StrategyParams::default())
+                .unwrap_or_else(|| panic!("{} is not registered", name));
+
+            let players: Vec<Box<dyn Client>> = vec![
+                Box::new(AIClient::new(strategy)),
+                Box::new(AIClient::new(Box::new(GreedyStrategy))),
+            ];
+            let result = run_game(players, Some(Board::with_no_holes(3, 4, 1)));
+            assert!(result.final_state.is_game_over());
+            assert_ne!(result.final_statuses[0], ClientStatus::Kicked, "{} was kicked", name);
+        }
+
+        let params = StrategyParams { lookahead: Some(1), mobility_weight: Some(3), seed: Some(7) };
+        assert!(strategy_from_name("mobility", params).is_some());
+        assert!(strategy_from_name("random", params).is_some());
+
+        assert!(strategy_from_name("minmax", StrategyParams::default()).is_none());
+        assert!(strategy_from_name("", StrategyParams::default()).is_none());
+    }
+
+    /// The pruned search should find the same moves as find_minmax_move, expanding
+    /// fewer nodes when moves capturing more fish are searched first.
+    #[test]
+    fn test_find_minmax_move_pruned() {
+        let board = Board::from_tiles(vec![
+            vec![1, 5, 2, 1, 3],
+            vec![4, 1, 1, 5, 2],
+            vec![2, 3, 5, 1, 1],
+            vec![1, 2, 1, 4, 3],
+        ]);
+        let mut state = GameState::new(board, 2);
+        state.place_all_zigzag();
+
+        let (mut ordered_total, mut unordered_total) = (0, 0);
+        let mut game = GameTree::new(&state);
+
+        while !game.is_game_over() {
+            for lookahead in 1 ..= 2 {
+                let expected = find_minmax_move(&mut game, lookahead);
+
+                let mut unordered_nodes = 0;
+                let unordered = find_pruned_move(&mut game, lookahead, false, &mut unordered_nodes);
+                let mut ordered_nodes = 0;
+                let ordered = find_pruned_move(&mut game, lookahead, true, &mut ordered_nodes);
+
+                assert_eq!(unordered, expected);
+                assert_eq!(ordered, expected);
+                unordered_total += unordered_nodes;
+                ordered_total += ordered_nodes;
+            }
+
+            let move_ = find_minmax_move(&mut game, 2);
+            game = game.take_game_after_move(move_).unwrap();
+        }
+
+        assert!(ordered_total < unordered_total);
+
+        // A lookahead of 0 searches as deeply as a lookahead of 1
+        let mut game = GameTree::new(&state);
+        assert_eq!(find_minmax_move_pruned(&mut game, 0, true), find_minmax_move_pruned(&mut game, 1, true));
+
+        // The pruned strategy plays the same moves as the minmax strategy it replaces
+        let mut pruned = strategy_from_name("pruned-minmax", StrategyParams::default()).unwrap();
+        assert_eq!(pruned.find_move(&mut game), ZigZagMinMaxStrategy.find_move(&mut game));
+    }
+
+    /// Both of the penguin's moves capture the same fish, but moving to tile 2
+    /// strands it since tile 1 is a hole and tile 3 is removed once it leaves.
+    #[test]
+    fn test_find_minmax_move_avoiding_dead_ends() {
+        let mut state = single_penguin_path_game("
+            1 1 1 1
+             x 1 1 1
+        ", 2);
+        state.place_avatar_for_player(PlayerId(0), TileId(3)).unwrap();
+        state.place_avatar_for_player(PlayerId(1), TileId(7)).unwrap();
+
+        let dead_end = Move::new(TileId(3), TileId(2));
+        let open = Move::new(TileId(3), TileId(4));
+        assert!(is_dead_end(&state, dead_end));
+        assert!(!is_dead_end(&state, open));
+
+        // Without looking far enough ahead to see the difference, the tie is broken by position
+        let mut game = GameTree::new(&state);
+        assert_eq!(find_minmax_move(&mut game, 1), dead_end);
+        assert_eq!(find_minmax_move_avoiding_dead_ends(&mut game, 1), open);
+        assert_eq!(find_minmax_move_avoiding_dead_ends(&mut game, 2), open);
+
+        // A lookahead of 0 searches as far as a lookahead of 1
+        assert_eq!(find_minmax_move_avoiding_dead_ends(&mut game, 0), open);
+    }
+
+    #[test]
+    fn test_maximin_cache_evicts_least_recently_used() {
+        let move_ = Move::new(TileId(0), TileId(1));
+        let mut cache = MaxiMinCache::with_capacity(2);
+
+        cache.insert(1, 10, move_);
+        cache.insert(2, 20, move_);
+        assert_eq!(cache.get(1), Some((10, move_)));
+
+        // 2 is now the least recently used entry
+        cache.insert(3, 30, move_);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some((10, move_)));
+        assert_eq!(cache.get(3), Some((30, move_)));
+
+        // Re-inserting an existing entry replaces it rather than evicting another
+        cache.insert(3, 31, move_);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(1), Some((10, move_)));
+        assert_eq!(cache.get(3), Some((31, move_)));
+    }
+
+    #[test]
+    fn test_find_minmax_move_tiny_cache() {
+        let mut state = GameState::with_default_board(4, 5, 2);
+        state.place_all_zigzag();
+
+        let mut game = GameTree::new(&state);
+        while !game.is_game_over() {
+            let expected = find_minmax_move(&mut game, 2);
+            let mut cache = MaxiMinCache::with_capacity(2);
+            assert_eq!(find_minmax_move_with_cache(&mut game, 2, &score_heuristic, &mut cache), expected);
+            assert!(cache.entries.len() <= 2);
+
+            game = game.take_game_after_move(expected).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_mobility_heuristic() {
+        // 0     3     6
+        //    1     4     7
+        // 2     5     8
+        let mut state = GameState::new(Board::with_no_holes(3, 3, 1), 2);
+        state.place_avatar_for_player(PlayerId(0), TileId(0));
+        state.place_avatar_for_player(PlayerId(1), TileId(8));
+        state.board.remove_tile(TileId(7));
+        state.board.remove_tile(TileId(5));
+
+        // 0 -> player 0    8 -> player 1    5, 7 -> holes
+        // Player 0 can reach 1 and 2 (moving through 1 is blocked by the hole at 5).
+        // Player 1 can reach 4, 3, and 6 (moving towards 7 is blocked since it is a hole).
+        // Neither player has any fish yet.
+        assert_eq!(mobility_heuristic(&state, PlayerId(0), 0), 0);
+        assert_eq!(mobility_heuristic(&state, PlayerId(0), 1), -1);
+        assert_eq!(mobility_heuristic(&state, PlayerId(1), 2), 2);
+    }
 }